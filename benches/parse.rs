@@ -0,0 +1,47 @@
+// Parser throughput on large output bursts, e.g. `cat` of a big log or a paste
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use termind::renderer::TerminalParser;
+
+/// About 4 MB of colored log lines, wide characters, cursor movement and
+/// OSC title updates
+fn mixed_output() -> Vec<u8> {
+    let mut output = Vec::with_capacity(4 << 20);
+    let mut line = 0;
+    while output.len() < 4 << 20 {
+        let text = format!(
+            "\x1b[3{}m{:>6}\x1b[0m INFO request handled in {}ms \u{4e2d}\u{6587} path=/api/v1/items/{}\r\n",
+            line % 8,
+            line,
+            line % 997,
+            line * 31
+        );
+        output.extend_from_slice(text.as_bytes());
+        if line % 100 == 0 {
+            output.extend_from_slice(format!("\x1b]0;build step {}\x07\x1b[H\x1b[2K\x1b[1mprogress\x1b[22m\x1b[24;1H", line).as_bytes());
+        }
+        line += 1;
+    }
+    output
+}
+
+fn parse_burst(c: &mut Criterion) {
+    let input = mixed_output();
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.sample_size(20);
+    group.bench_function("mixed_4mb", |b| {
+        b.iter_batched(
+            || TerminalParser::new(24, 80),
+            |mut parser| {
+                parser.parse(black_box(&input));
+                parser
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_burst);
+criterion_main!(benches);
@@ -56,6 +56,21 @@ pub enum ProjectType {
     Unknown,
 }
 
+impl ProjectType {
+    /// Stable name, as stored in the block database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectType::Rust => "rust",
+            ProjectType::Node => "node",
+            ProjectType::Python => "python",
+            ProjectType::Go => "go",
+            ProjectType::Java => "java",
+            ProjectType::Docker => "docker",
+            ProjectType::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitContext {
     pub repository_root: String,
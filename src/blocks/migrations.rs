@@ -0,0 +1,286 @@
+// Versioned schema migrations for the block store
+// Each migration runs once, in order, inside its own transaction
+
+use chrono::Utc;
+use sqlx::{Pool, Sqlite, Transaction};
+use crate::error::Result;
+use crate::text::strip_ansi;
+
+/// Schema version a freshly migrated database ends up at
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+enum Step {
+    Sql(&'static str),
+    /// `ALTER TABLE ... ADD COLUMN`, skipped when the column already exists
+    /// (databases from before versioning may have added it themselves)
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+    /// Fill `target` with `source` stripped of escape sequences, for every block
+    StripAnsi {
+        source: &'static str,
+        target: &'static str,
+    },
+}
+
+struct Migration {
+    description: &'static str,
+    steps: &'static [Step],
+}
+
+/// Migration `i` upgrades the schema to version `i + 1`. Append only.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "blocks table with full-text search",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS blocks (
+                    id TEXT PRIMARY KEY,
+                    timestamp TEXT NOT NULL,
+                    cwd TEXT NOT NULL,
+                    shell TEXT NOT NULL,
+                    command TEXT NOT NULL,
+                    args TEXT NOT NULL, -- JSON array
+                    exit_code INTEGER,
+                    duration_ms INTEGER,
+                    stdout TEXT NOT NULL,
+                    stderr TEXT NOT NULL,
+                    tags TEXT NOT NULL -- JSON array
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE VIRTUAL TABLE IF NOT EXISTS blocks_fts USING fts5(
+                    id UNINDEXED,
+                    command,
+                    stdout,
+                    stderr,
+                    tags,
+                    content='blocks',
+                    content_rowid='rowid'
+                )
+                "#,
+            ),
+            // Keep the FTS table in sync
+            Step::Sql(
+                r#"
+                CREATE TRIGGER IF NOT EXISTS blocks_ai AFTER INSERT ON blocks BEGIN
+                  INSERT INTO blocks_fts(rowid, id, command, stdout, stderr, tags)
+                  VALUES (new.rowid, new.id, new.command, new.stdout, new.stderr, new.tags);
+                END
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TRIGGER IF NOT EXISTS blocks_ad AFTER DELETE ON blocks BEGIN
+                  INSERT INTO blocks_fts(blocks_fts, rowid, id, command, stdout, stderr, tags)
+                  VALUES('delete', old.rowid, old.id, old.command, old.stdout, old.stderr, old.tags);
+                END
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TRIGGER IF NOT EXISTS blocks_au AFTER UPDATE ON blocks BEGIN
+                  INSERT INTO blocks_fts(blocks_fts, rowid, id, command, stdout, stderr, tags)
+                  VALUES('delete', old.rowid, old.id, old.command, old.stdout, old.stderr, old.tags);
+                  INSERT INTO blocks_fts(rowid, id, command, stdout, stderr, tags)
+                  VALUES (new.rowid, new.id, new.command, new.stdout, new.stderr, new.tags);
+                END
+                "#,
+            ),
+            // Index rows written before the FTS table existed
+            Step::Sql("INSERT INTO blocks_fts(blocks_fts) VALUES('rebuild')"),
+        ],
+    },
+    Migration {
+        description: "screen snapshots",
+        steps: &[Step::AddColumn {
+            table: "blocks",
+            column: "screen_snapshot",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        description: "index output without escape sequences",
+        steps: &[
+            Step::AddColumn {
+                table: "blocks",
+                column: "stdout_plain",
+                definition: "TEXT NOT NULL DEFAULT ''",
+            },
+            Step::AddColumn {
+                table: "blocks",
+                column: "stderr_plain",
+                definition: "TEXT NOT NULL DEFAULT ''",
+            },
+            Step::StripAnsi { source: "stdout", target: "stdout_plain" },
+            Step::StripAnsi { source: "stderr", target: "stderr_plain" },
+            Step::Sql("DROP TRIGGER IF EXISTS blocks_ai"),
+            Step::Sql("DROP TRIGGER IF EXISTS blocks_ad"),
+            Step::Sql("DROP TRIGGER IF EXISTS blocks_au"),
+            Step::Sql("DROP TABLE IF EXISTS blocks_fts"),
+            Step::Sql(
+                r#"
+                CREATE VIRTUAL TABLE blocks_fts USING fts5(
+                    id UNINDEXED,
+                    command,
+                    stdout_plain,
+                    stderr_plain,
+                    tags,
+                    content='blocks',
+                    content_rowid='rowid'
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TRIGGER blocks_ai AFTER INSERT ON blocks BEGIN
+                  INSERT INTO blocks_fts(rowid, id, command, stdout_plain, stderr_plain, tags)
+                  VALUES (new.rowid, new.id, new.command, new.stdout_plain, new.stderr_plain, new.tags);
+                END
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TRIGGER blocks_ad AFTER DELETE ON blocks BEGIN
+                  INSERT INTO blocks_fts(blocks_fts, rowid, id, command, stdout_plain, stderr_plain, tags)
+                  VALUES('delete', old.rowid, old.id, old.command, old.stdout_plain, old.stderr_plain, old.tags);
+                END
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TRIGGER blocks_au AFTER UPDATE ON blocks BEGIN
+                  INSERT INTO blocks_fts(blocks_fts, rowid, id, command, stdout_plain, stderr_plain, tags)
+                  VALUES('delete', old.rowid, old.id, old.command, old.stdout_plain, old.stderr_plain, old.tags);
+                  INSERT INTO blocks_fts(rowid, id, command, stdout_plain, stderr_plain, tags)
+                  VALUES (new.rowid, new.id, new.command, new.stdout_plain, new.stderr_plain, new.tags);
+                END
+                "#,
+            ),
+            Step::Sql("INSERT INTO blocks_fts(blocks_fts) VALUES('rebuild')"),
+        ],
+    },
+    Migration {
+        description: "repository and project type columns",
+        steps: &[
+            Step::AddColumn {
+                table: "blocks",
+                column: "repo_root",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "blocks",
+                column: "project_type",
+                definition: "TEXT",
+            },
+            Step::Sql("CREATE INDEX IF NOT EXISTS blocks_repo_root ON blocks(repo_root, timestamp)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS blocks_project_type ON blocks(project_type, timestamp)"),
+        ],
+    },
+    Migration {
+        description: "exit code index",
+        steps: &[
+            Step::Sql("CREATE INDEX IF NOT EXISTS blocks_exit_code ON blocks(exit_code, timestamp)"),
+        ],
+    },
+    Migration {
+        description: "block metadata",
+        steps: &[Step::AddColumn {
+            table: "blocks",
+            column: "metadata",
+            definition: "TEXT NOT NULL DEFAULT '{}'", // JSON object
+        }],
+    },
+];
+
+/// Bring the database up to `SCHEMA_VERSION`, applying only the missing migrations
+pub async fn run(pool: &Pool<Sqlite>) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate() {
+        let version = index as i64 + 1;
+        let mut tx = pool.begin().await?;
+
+        // Checked inside the transaction in case another process got here first
+        if current_version(&mut tx).await? >= version {
+            continue;
+        }
+
+        tracing::info!("Applying block store migration {}: {}", version, migration.description);
+        for step in migration.steps {
+            apply_step(&mut tx, step).await?;
+        }
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(version)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Highest applied migration, 0 for a database that predates versioning
+pub async fn version(pool: &Pool<Sqlite>) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+    let version = current_version(&mut tx).await?;
+    tx.commit().await?;
+    Ok(version)
+}
+
+async fn current_version(tx: &mut Transaction<'_, Sqlite>) -> Result<i64> {
+    let (version,): (i64,) = sqlx::query_as("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(version)
+}
+
+async fn apply_step(tx: &mut Transaction<'_, Sqlite>, step: &Step) -> Result<()> {
+    match step {
+        Step::Sql(sql) => {
+            sqlx::query(sql).execute(&mut **tx).await?;
+        }
+        Step::AddColumn { table, column, definition } => {
+            let (exists,): (bool,) = sqlx::query_as(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?",
+            )
+            .bind(table)
+            .bind(column)
+            .fetch_one(&mut **tx)
+            .await?;
+            if !exists {
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+        Step::StripAnsi { source, target } => {
+            let rows: Vec<(i64, String)> = sqlx::query_as(&format!("SELECT rowid, {} FROM blocks", source))
+                .fetch_all(&mut **tx)
+                .await?;
+            for (rowid, raw) in rows {
+                sqlx::query(&format!("UPDATE blocks SET {} = ? WHERE rowid = ?", target))
+                    .bind(strip_ansi(&raw))
+                    .bind(rowid)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
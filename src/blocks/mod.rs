@@ -2,10 +2,17 @@
 // This will store command blocks with SQLite in Phase A Week 3
 
 pub mod context;
+mod migrations;
+pub mod storage;
+
+pub use migrations::SCHEMA_VERSION;
+pub use storage::{BlockStorage, MemoryBlockStore};
+
+use std::collections::HashMap;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use crate::blocks::context::ExecutionContext;
+use crate::blocks::context::{ExecutionContext, ProjectType};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
@@ -21,6 +28,13 @@ pub struct Block {
     pub stderr: String,
     pub tags: Vec<String>,
     pub context: Option<ExecutionContext>,
+    /// Rendered screen text at completion, when snapshot capture is enabled
+    #[serde(default)]
+    pub screen_snapshot: Option<String>,
+    /// Annotations from users and integrations, e.g. `ticket` = `JIRA-123`.
+    /// Stored with the block but not full-text indexed.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
 }
 
 impl Block {
@@ -38,9 +52,17 @@ impl Block {
             stderr: String::new(),
             tags: Vec::new(),
             context: None,
+            screen_snapshot: None,
+            metadata: HashMap::new(),
         }
     }
     
+    /// Fill `args` with the command line's arguments, after the program name
+    pub fn with_parsed_args(mut self) -> Self {
+        self.args = CommandSpec::parse(&self.command).args;
+        self
+    }
+    
     pub fn with_context(mut self, context: ExecutionContext) -> Self {
         self.context = Some(context);
         self
@@ -62,29 +84,345 @@ impl Block {
         self
     }
     
+    pub fn with_screen_snapshot(mut self, snapshot: String) -> Self {
+        self.screen_snapshot = Some(snapshot);
+        self
+    }
+    
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+    
     pub fn success(&self) -> bool {
         self.exit_code == Some(0)
     }
+    
+    /// The signal that killed the command, when its exit code is `128 + n`
+    pub fn signal(&self) -> Option<i32> {
+        self.exit_code.filter(|code| SIGNAL_EXIT_CODES.contains(code)).map(|code| code - 128)
+    }
+    
+    /// Single-line listing entry: exit status and the command's first line,
+    /// fitted to `max_cols` display columns
+    pub fn one_line_summary(&self, max_cols: usize) -> String {
+        let status = match self.exit_code {
+            Some(0) => "✓".to_string(),
+            Some(code) => format!("✗{}", code),
+            None => "…".to_string(),
+        };
+        let command = self.command.lines().next().unwrap_or("");
+        let ellipsis = if self.command.trim_end().contains('\n') { " …" } else { "" };
+        crate::text::truncate_display(&format!("{} {}{}", status, command, ellipsis), max_cols)
+    }
+    
+    /// Markdown for sharing this block: the command in a code fence, a status
+    /// line, and stdout/stderr in their own fences. Output longer than
+    /// `MARKDOWN_OUTPUT_MAX_LINES` is cut with a note.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        push_fenced(&mut markdown, "sh", &format!("$ {}", self.command));
+        
+        let exit = self.exit_code.map_or("unknown".to_string(), |code| code.to_string());
+        markdown.push_str(&format!("\n**Exit code:** {}", exit));
+        if let Some(duration_ms) = self.duration_ms {
+            markdown.push_str(&format!(" · **Duration:** {} ms", duration_ms));
+        }
+        markdown.push_str(&format!(" · **Directory:** {}\n", inline_code(&self.cwd)));
+        
+        for (label, output) in [("stdout", &self.stdout), ("stderr", &self.stderr)] {
+            if output.is_empty() {
+                continue;
+            }
+            markdown.push_str(&format!("\n**{}**\n\n", label));
+            push_fenced(&mut markdown, "", &truncate_lines(output, MARKDOWN_OUTPUT_MAX_LINES));
+        }
+        
+        markdown
+    }
+}
+
+/// Output lines kept per stream by `Block::to_markdown`
+pub const MARKDOWN_OUTPUT_MAX_LINES: usize = 200;
+
+/// Append `content` in a code fence longer than any backtick run inside it,
+/// so backticks in the content can't close the fence early
+fn push_fenced(markdown: &mut String, info: &str, content: &str) {
+    let fence = "`".repeat((longest_backtick_run(content) + 1).max(3));
+    markdown.push_str(&format!("{}{}\n{}\n{}\n", fence, info, content.trim_end_matches('\n'), fence));
+}
+
+fn inline_code(text: &str) -> String {
+    let ticks = "`".repeat(longest_backtick_run(text) + 1);
+    // Pad with spaces when the text starts or ends with a backtick
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{} {} {}", ticks, text, ticks)
+    } else {
+        format!("{}{}{}", ticks, text, ticks)
+    }
+}
+
+/// Shell words of a command line, with quotes and escapes resolved. A line
+/// `shlex` can't parse, such as one with an unterminated quote, is split on
+/// whitespace instead.
+pub fn split_command_line(command: &str) -> Vec<String> {
+    shlex::split(command)
+        .unwrap_or_else(|| command.split_whitespace().map(str::to_string).collect())
+}
+
+/// A command line and its arguments, the shape of `Block::command` and
+/// `Block::args` without the run's results
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandSpec {
+    /// The full line, as typed
+    pub command: String,
+    /// Shell words after the program name
+    pub args: Vec<String>,
+}
+
+impl CommandSpec {
+    pub fn parse(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            args: split_command_line(command).into_iter().skip(1).collect(),
+        }
+    }
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn truncate_lines(output: &str, max_lines: usize) -> String {
+    let total = output.lines().count();
+    if total <= max_lines {
+        return output.to_string();
+    }
+    
+    let mut kept: Vec<&str> = output.lines().take(max_lines).collect();
+    let note = format!("... ({} more lines truncated)", total - max_lines);
+    kept.push(&note);
+    kept.join("\n")
 }
 
 // Block detector for identifying command boundaries in terminal output
+use std::collections::VecDeque;
 use crate::error::Result;
+use tokio::sync::broadcast;
+use crate::pty::{IdleEvent, RetryConfig, SignalEvent};
+use crate::renderer::{TerminalEvent, TextGrid};
+use crate::text::strip_ansi;
+
+/// Exit status shells report for a command killed by SIGINT (128 + 2)
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Exit codes shells report for a command killed by a signal: `128 + n`
+/// for signals 1 through 64
+pub const SIGNAL_EXIT_CODES: std::ops::RangeInclusive<i32> = 129..=192;
 
-pub struct BlockDetector {
-    store: BlockStore,
+/// Tag added to blocks whose command was interrupted with Ctrl-C
+pub const INTERRUPTED_TAG: &str = "interrupted";
+
+/// Tag added to blocks closed by `IdleDetector` rather than a shell marker;
+/// their exit code is assumed, not reported
+pub const IDLE_TAG: &str = "idle-inferred";
+
+/// Finished blocks buffered per `BlockDetector::subscribe` receiver; a receiver
+/// further behind than this skips the oldest and gets `RecvError::Lagged`
+pub const BLOCK_STREAM_CAPACITY: usize = 64;
+
+/// Blocks kept for a later store after every retry failed; past this the
+/// oldest is dropped
+pub const DEAD_LETTER_CAPACITY: usize = 256;
+
+/// Retries for a failed block write: short, since they hold up the reader
+fn default_store_retry() -> RetryConfig {
+    RetryConfig {
+        max_retries: 3,
+        base_delay: Duration::from_millis(50),
+        max_delay: Duration::from_secs(1),
+        ..RetryConfig::default()
+    }
+}
+
+/// A block `BlockDetector` finished, and whether it reached the store
+#[derive(Debug, Clone)]
+pub enum FinishedBlock {
+    /// Persisted and sent to subscribers
+    Stored(Block),
+    /// Storing failed; the block waits in `dead_letters` for a retry
+    Deferred(Block),
+}
+
+impl FinishedBlock {
+    pub fn block(&self) -> &Block {
+        match self {
+            Self::Stored(block) | Self::Deferred(block) => block,
+        }
+    }
+    
+    pub fn into_block(self) -> Block {
+        match self {
+            Self::Stored(block) | Self::Deferred(block) => block,
+        }
+    }
+    
+    pub fn is_stored(&self) -> bool {
+        matches!(self, Self::Stored(_))
+    }
+}
+
+/// Turns shell activity into blocks and persists each one in `S` when it finishes
+pub struct BlockDetector<S = BlockStore> {
+    store: S,
+    finished: broadcast::Sender<Block>,
     current_block: Option<Block>,
+    capture_snapshots: bool,
+    // SIGINT was forwarded while the current command ran
+    interrupted: bool,
+    store_retry: RetryConfig,
+    // Finished blocks whose store failed after every retry, oldest first
+    dead_letters: VecDeque<Block>,
 }
 
 impl BlockDetector {
+    /// A detector storing blocks in the default SQLite database
     pub async fn new() -> Result<Self> {
-        Ok(Self {
-            store: BlockStore::new().await?,
+        Ok(Self::with_storage(BlockStore::new().await?))
+    }
+}
+
+impl<S: BlockStorage> BlockDetector<S> {
+    /// A detector persisting finished blocks to `store`
+    pub fn with_storage(store: S) -> Self {
+        Self {
+            store,
+            finished: broadcast::channel(BLOCK_STREAM_CAPACITY).0,
             current_block: None,
-        })
+            capture_snapshots: false,
+            interrupted: false,
+            store_retry: default_store_retry(),
+            dead_letters: VecDeque::new(),
+        }
+    }
+    
+    /// Where finished blocks are persisted
+    pub fn storage(&self) -> &S {
+        &self.store
+    }
+    
+    /// How often, and with what backoff, a failed block write is retried
+    /// before the block is set aside in the dead-letter queue
+    pub fn with_store_retry(mut self, config: RetryConfig) -> Self {
+        self.store_retry = config;
+        self
+    }
+    
+    /// Finished blocks not yet persisted because storing them kept failing
+    pub fn dead_letters(&self) -> &VecDeque<Block> {
+        &self.dead_letters
+    }
+    
+    /// Try once more to store each dead-lettered block, keeping those that
+    /// still fail. Returns how many were persisted.
+    pub async fn retry_dead_letters(&mut self) -> usize {
+        let mut stored = 0;
+        for _ in 0..self.dead_letters.len() {
+            let Some(block) = self.dead_letters.pop_front() else {
+                break;
+            };
+            match self.store.store(block.clone()).await {
+                Ok(()) => {
+                    stored += 1;
+                    let _ = self.finished.send(block);
+                }
+                Err(_) => self.dead_letters.push_back(block),
+            }
+        }
+        stored
+    }
+    
+    /// Store `block`, retrying with backoff; a block that can't be stored is
+    /// queued in `dead_letters` instead of failing the caller. Returns
+    /// whether it was persisted.
+    async fn store_with_retry(&mut self, block: &Block) -> bool {
+        let mut attempt = 0;
+        loop {
+            match self.store.store(block.clone()).await {
+                Ok(()) => return true,
+                Err(e) => {
+                    attempt += 1;
+                    if attempt >= self.store_retry.max_retries {
+                        tracing::warn!("Keeping block {} for later, storing it failed {} times: {}", block.id, attempt, e);
+                        if self.dead_letters.len() >= DEAD_LETTER_CAPACITY {
+                            self.dead_letters.pop_front();
+                        }
+                        self.dead_letters.push_back(block.clone());
+                        return false;
+                    }
+                    tracing::debug!("Storing block {} failed ({}), retrying", block.id, e);
+                    tokio::time::sleep(self.store_retry.delay(attempt - 1)).await;
+                }
+            }
+        }
+    }
+    
+    /// Store the rendered screen with each block finished via `finish_command_with_screen`
+    pub fn with_screen_snapshots(mut self, enabled: bool) -> Self {
+        self.capture_snapshots = enabled;
+        self
+    }
+    
+    /// Receive each block once it is finished and stored, in completion order.
+    /// A block that had to wait in `dead_letters` arrives when a retry stores
+    /// it, which is ahead of the block whose write triggered the retry.
+    pub fn subscribe(&self) -> broadcast::Receiver<Block> {
+        self.finished.subscribe()
     }
     
     pub fn start_command(&mut self, command: String, cwd: String, shell: String) {
-        self.current_block = Some(Block::new(command, cwd, shell));
+        self.current_block = Some(Block::new(command, cwd, shell).with_parsed_args());
+        self.interrupted = false;
+    }
+    
+    /// Flag the in-progress command as interrupted; no-op between commands
+    pub fn mark_interrupted(&mut self) {
+        if self.current_block.is_some() {
+            self.interrupted = true;
+        }
+    }
+    
+    /// React to a signal forwarded to the child by `SignalHandler`, or to
+    /// Ctrl+C typed into the terminal
+    pub fn handle_signal(&mut self, event: &SignalEvent) {
+        if *event == SignalEvent::Interrupt {
+            self.mark_interrupted();
+        }
+    }
+    
+    /// Start a block for a command the shell announced through its
+    /// integration script (`TerminalEvent::CommandStarted`, from OSC 633),
+    /// so it is captured without waiting for the prompt markers. Other
+    /// events are ignored. Returns whether a block was started.
+    pub fn handle_terminal_event(&mut self, event: &TerminalEvent, shell: &str) -> bool {
+        let TerminalEvent::CommandStarted { command, cwd } = event else {
+            return false;
+        };
+        let cwd = cwd.as_ref().map(|cwd| cwd.to_string_lossy().into_owned()).unwrap_or_default();
+        self.start_command(command.clone(), cwd, shell.to_string());
+        true
+    }
+    
+    /// Close the in-progress block when `IdleDetector` infers the command
+    /// finished, for shells without OSC 133. The exit code is assumed to be 0.
+    pub async fn handle_idle(&mut self, event: IdleEvent) -> Result<Option<FinishedBlock>> {
+        let IdleEvent::CommandLikelyDone = event;
+        let Some(block) = self.current_block.as_mut() else {
+            return Ok(None);
+        };
+        block.tags.push(IDLE_TAG.to_string());
+        let duration_ms = (Utc::now() - block.timestamp).num_milliseconds().max(0) as u64;
+        self.finish_command(0, duration_ms).await
     }
     
     pub fn add_output(&mut self, output: &str, is_stderr: bool) {
@@ -97,15 +435,73 @@ impl BlockDetector {
         }
     }
     
-    pub async fn finish_command(&mut self, exit_code: i32, duration_ms: u64) -> Result<()> {
-        if let Some(block) = self.current_block.take() {
+    /// Finish the current command and persist it, returning the finished block.
+    /// An interrupted command is tagged `INTERRUPTED_TAG`; if it still reported
+    /// success or no status, it is recorded as exiting with `INTERRUPTED_EXIT_CODE`.
+    /// Failed writes are retried per `with_store_retry`, then the block waits
+    /// in `dead_letters` and is returned as `FinishedBlock::Deferred`; a
+    /// successful write also retries those.
+    pub async fn finish_command(&mut self, exit_code: i32, duration_ms: u64) -> Result<Option<FinishedBlock>> {
+        if let Some(mut block) = self.current_block.take() {
+            let mut exit_code = exit_code;
+            if std::mem::take(&mut self.interrupted) {
+                if exit_code <= 0 {
+                    exit_code = INTERRUPTED_EXIT_CODE;
+                }
+                block.tags.push(INTERRUPTED_TAG.to_string());
+            }
+            
             let finished_block = block
                 .with_exit_code(exit_code)
                 .with_duration(duration_ms);
             
-            self.store.store(finished_block).await?;
+            if !self.store_with_retry(&finished_block).await {
+                return Ok(Some(FinishedBlock::Deferred(finished_block)));
+            }
+            // Older blocks the store is now taking go out first
+            if !self.dead_letters.is_empty() {
+                self.retry_dead_letters().await;
+            }
+            // No subscribers is not an error
+            let _ = self.finished.send(finished_block.clone());
+            return Ok(Some(FinishedBlock::Stored(finished_block)));
         }
-        Ok(())
+        Ok(None)
+    }
+    
+    /// Stdout accumulated so far by the in-progress command
+    pub fn current_stdout(&self) -> Option<&str> {
+        self.current_block.as_ref().map(|block| block.stdout.as_str())
+    }
+    
+    /// Stderr accumulated so far by the in-progress command
+    pub fn current_stderr(&self) -> Option<&str> {
+        self.current_block.as_ref().map(|block| block.stderr.as_str())
+    }
+    
+    /// When the in-progress command started
+    pub fn current_started_at(&self) -> Option<DateTime<Utc>> {
+        self.current_block.as_ref().map(|block| block.timestamp)
+    }
+    
+    /// Like `finish_command`, also recording `grid` as the block's screen
+    /// snapshot when capture is enabled
+    pub async fn finish_command_with_screen(
+        &mut self,
+        exit_code: i32,
+        duration_ms: u64,
+        grid: &TextGrid,
+    ) -> Result<Option<FinishedBlock>> {
+        if self.capture_snapshots {
+            if let Some(block) = self.current_block.as_mut() {
+                block.screen_snapshot = Some(grid.to_string());
+            }
+        }
+        self.finish_command(exit_code, duration_ms).await
+    }
+    
+    pub async fn get_block(&self, id: &str) -> Result<Option<Block>> {
+        self.store.get(id).await
     }
     
     pub async fn search(&self, query: &str) -> Result<Vec<Block>> {
@@ -120,38 +516,186 @@ impl BlockDetector {
         self.store.get_failed(limit).await
     }
     
+    pub async fn get_by_exit_code(&self, code: i32, limit: i32) -> Result<Vec<Block>> {
+        self.store.get_by_exit_code(code, limit).await
+    }
+    
+    pub async fn get_signaled(&self, limit: i32) -> Result<Vec<Block>> {
+        self.store.get_signaled(limit).await
+    }
+    
+    /// Set `key` to `value` in a block's metadata. The running command's
+    /// block, and any block waiting in `dead_letters`, keeps it until it is
+    /// stored; any other id must already be stored.
+    pub async fn set_metadata(&mut self, id: &str, key: &str, value: &str) -> Result<()> {
+        let mut unstored = self.current_block.iter_mut().chain(self.dead_letters.iter_mut());
+        if let Some(block) = unstored.find(|block| block.id == id) {
+            block.metadata.insert(key.to_string(), value.to_string());
+            return Ok(());
+        }
+        self.store.set_metadata(id, key, value).await
+    }
+    
+    pub async fn get_by_metadata(&self, key: &str, value: Option<&str>, limit: i32) -> Result<Vec<Block>> {
+        self.store.get_by_metadata(key, value, limit).await
+    }
+    
     pub fn current_block(&self) -> Option<&Block> {
         self.current_block.as_ref()
     }
 }
 
 // Block storage with SQLite backend (Phase A Week 3)
-use sqlx::{sqlite::{SqlitePool, SqliteRow}, Pool, Sqlite, Row};
-use std::path::PathBuf;
+use sqlx::{sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow, SqliteSynchronous}, Pool, Sqlite, Row};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable naming the directory to keep `blocks.db` in,
+/// instead of the platform data directory
+pub const DATA_DIR_ENV: &str = "TERMIND_DATA_DIR";
+
+/// How long SQLite waits on a locked database before returning SQLITE_BUSY
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_millis(5000);
+
+/// How `BlockStore::search_with` interprets its query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchSyntax {
+    /// Every word must appear as whole tokens, or as a prefix when it ends in
+    /// `*`; other FTS operators and punctuation have no special meaning
+    #[default]
+    Literal,
+    /// SQLite FTS5 query syntax: `AND`/`OR`/`NOT`, `"phrases"`, `prefix*`, `NEAR(...)`
+    Fts,
+}
+
+/// Quote each word as an FTS5 string so operators and punctuation are plain
+/// text, keeping a trailing `*` as a prefix match
+fn literal_fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| {
+            let stem = word.trim_end_matches('*');
+            let prefix = if stem.len() < word.len() { "*" } else { "" };
+            format!("\"{}\"{}", stem.replace('"', "\"\""), prefix)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether SQLite's `message` rejects FTS5 `query` itself, rather than
+/// reporting a locked, corrupt or incomplete database
+fn is_fts_syntax_error(message: &str, query: &str) -> bool {
+    message.starts_with("fts5: syntax error")
+        || message.starts_with("unterminated string")
+        || message.starts_with("unknown special query")
+        // A column filter naming a column the index doesn't have
+        || message.strip_prefix("no such column: ").is_some_and(|column| query.contains(column))
+}
+
+/// Escape `LIKE` wildcards so `text` matches only itself
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Connection settings for `BlockStore`
+#[derive(Debug, Clone)]
+pub struct BlockStoreConfig {
+    /// Upper bound for acquiring a connection and for each query
+    pub timeout: Duration,
+    pub max_connections: u32,
+}
+
+impl Default for BlockStoreConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            max_connections: 4,
+        }
+    }
+}
+
+impl BlockStoreConfig {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    pub fn with_max_connections(mut self, max_connections: u32) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+}
 
 pub struct BlockStore {
     pool: Pool<Sqlite>,
+    config: BlockStoreConfig,
 }
 
 impl BlockStore {
+    /// Open the database in `$TERMIND_DATA_DIR`, or the platform data directory
     pub async fn new() -> Result<Self> {
         let db_path = Self::get_database_path()?;
-        
+        Self::open(&db_path).await
+    }
+    
+    /// Like `new`, but at `db_path` regardless of `TERMIND_DATA_DIR`
+    pub async fn new_at(db_path: impl AsRef<Path>) -> Result<Self> {
+        Self::open(db_path.as_ref()).await
+    }
+    
+    /// Open (creating if needed) the block database at `db_path`
+    pub async fn open(db_path: &Path) -> Result<Self> {
+        Self::open_with_config(db_path, BlockStoreConfig::default()).await
+    }
+    
+    pub async fn open_with_config(db_path: &Path, config: BlockStoreConfig) -> Result<Self> {
         // Ensure the directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
         
-        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
-        let pool = SqlitePool::connect(&database_url).await?;
+        // synchronous and busy_timeout are per-connection, so every pooled
+        // connection gets them; the journal mode is set once in initialize_schema
+        let connect_options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(SQLITE_BUSY_TIMEOUT);
+        let connect = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .acquire_timeout(config.timeout)
+            .connect_with(connect_options);
+        let pool = with_timeout(config.timeout, connect).await?;
         
-        let store = Self { pool };
+        let store = Self { pool, config };
         store.initialize_schema().await?;
         
         Ok(store)
     }
     
+    pub fn config(&self) -> &BlockStoreConfig {
+        &self.config
+    }
+    
     fn get_database_path() -> Result<PathBuf> {
+        Self::database_path_in(std::env::var_os(DATA_DIR_ENV).map(PathBuf::from))
+    }
+    
+    /// `blocks.db` in `data_dir` when given (and not empty), else under the
+    /// platform data directory
+    fn database_path_in(data_dir: Option<PathBuf>) -> Result<PathBuf> {
+        if let Some(dir) = data_dir.filter(|dir| !dir.as_os_str().is_empty()) {
+            return Ok(dir.join("blocks.db"));
+        }
+        
         let mut path = dirs::data_dir()
             .ok_or_else(|| crate::error::TermindError::Io(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
@@ -163,93 +707,85 @@ impl BlockStore {
     }
     
     async fn initialize_schema(&self) -> Result<()> {
-        // Create the main blocks table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS blocks (
-                id TEXT PRIMARY KEY,
-                timestamp TEXT NOT NULL,
-                cwd TEXT NOT NULL,
-                shell TEXT NOT NULL,
-                command TEXT NOT NULL,
-                args TEXT NOT NULL, -- JSON array
-                exit_code INTEGER,
-                duration_ms INTEGER,
-                stdout TEXT NOT NULL,
-                stderr TEXT NOT NULL,
-                tags TEXT NOT NULL  -- JSON array
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-        
-        // Create FTS virtual table for full-text search
-        sqlx::query(
-            r#"
-            CREATE VIRTUAL TABLE IF NOT EXISTS blocks_fts USING fts5(
-                id UNINDEXED,
-                command,
-                stdout,
-                stderr,
-                tags,
-                content='blocks',
-                content_rowid='rowid'
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-        
-        // Create triggers to keep FTS table in sync
-        sqlx::query(
-            r#"
-            CREATE TRIGGER IF NOT EXISTS blocks_ai AFTER INSERT ON blocks BEGIN
-              INSERT INTO blocks_fts(rowid, id, command, stdout, stderr, tags)
-              VALUES (new.rowid, new.id, new.command, new.stdout, new.stderr, new.tags);
-            END
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-        
-        sqlx::query(
-            r#"
-            CREATE TRIGGER IF NOT EXISTS blocks_ad AFTER DELETE ON blocks BEGIN
-              INSERT INTO blocks_fts(blocks_fts, rowid, id, command, stdout, stderr, tags)
-              VALUES('delete', old.rowid, old.id, old.command, old.stdout, old.stderr, old.tags);
-            END
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-        
-        sqlx::query(
-            r#"
-            CREATE TRIGGER IF NOT EXISTS blocks_au AFTER UPDATE ON blocks BEGIN
-              INSERT INTO blocks_fts(blocks_fts, rowid, id, command, stdout, stderr, tags)
-              VALUES('delete', old.rowid, old.id, old.command, old.stdout, old.stderr, old.tags);
-              INSERT INTO blocks_fts(rowid, id, command, stdout, stderr, tags)
-              VALUES (new.rowid, new.id, new.command, new.stdout, new.stderr, new.tags);
-            END
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+        // WAL lets the background writer and history queries run concurrently.
+        // It persists in the database file; in-memory databases don't support it.
+        if !self.is_in_memory().await? {
+            sqlx::query("PRAGMA journal_mode=WAL").execute(&self.pool).await?;
+        }
+        sqlx::query("PRAGMA synchronous=NORMAL").execute(&self.pool).await?;
+        sqlx::query(&format!("PRAGMA busy_timeout={}", SQLITE_BUSY_TIMEOUT.as_millis()))
+            .execute(&self.pool)
+            .await?;
         
+        migrations::run(&self.pool).await
+    }
+    
+    /// Schema version recorded in the database
+    pub async fn schema_version(&self) -> Result<i64> {
+        migrations::version(&self.pool).await
+    }
+    
+    /// Whether the database lives in memory rather than in a file
+    async fn is_in_memory(&self) -> Result<bool> {
+        let (file,): (String,) = sqlx::query_as("SELECT file FROM pragma_database_list WHERE name = 'main'")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(file.is_empty())
+    }
+    
+    /// Space the database's pages take up, free pages included; shrinks
+    /// only after `vacuum`
+    pub async fn database_size_bytes(&self) -> Result<u64> {
+        let fetch = sqlx::query_as("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()")
+            .fetch_one(&self.pool);
+        let (size,): (i64,) = with_timeout(self.config.timeout, fetch).await?;
+        Ok(size as u64)
+    }
+    
+    /// Number of stored blocks
+    pub async fn row_count(&self) -> Result<u64> {
+        let fetch = sqlx::query_as("SELECT COUNT(*) FROM blocks").fetch_one(&self.pool);
+        let (count,): (i64,) = with_timeout(self.config.timeout, fetch).await?;
+        Ok(count as u64)
+    }
+    
+    /// Delete blocks recorded before `cutoff`, returning how many were removed.
+    /// The file keeps its size until `vacuum`.
+    pub async fn prune_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let delete = sqlx::query("DELETE FROM blocks WHERE julianday(timestamp) < julianday(?)")
+            .bind(cutoff.to_rfc3339())
+            .execute(&self.pool);
+        let result = with_timeout(self.config.timeout, delete).await?;
+        Ok(result.rows_affected())
+    }
+    
+    /// Rebuild the database file to hand space freed by deletions back to
+    /// the filesystem. A no-op for in-memory databases. Not bounded by the
+    /// configured timeout, since it rewrites the whole file.
+    pub async fn vacuum(&self) -> Result<()> {
+        if self.is_in_memory().await? {
+            return Ok(());
+        }
+        sqlx::query("VACUUM").execute(&self.pool).await?;
         Ok(())
     }
     
     pub async fn store(&self, block: Block) -> Result<()> {
         let args_json = serde_json::to_string(&block.args)?;
         let tags_json = serde_json::to_string(&block.tags)?;
+        let metadata_json = serde_json::to_string(&block.metadata)?;
+        // Denormalized from the context so history can be filtered by repo or project
+        let context = block.context.as_ref();
+        let repo_root = context.and_then(|c| c.git.as_ref()).map(|git| git.repository_root.as_str());
+        let project_type = context.and_then(|c| c.project.as_ref()).map(|p| p.project_type.as_str());
         
-        sqlx::query(
+        let insert = sqlx::query(
             r#"
             INSERT INTO blocks (
                 id, timestamp, cwd, shell, command, args,
-                exit_code, duration_ms, stdout, stderr, tags
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                exit_code, duration_ms, stdout, stderr, tags, screen_snapshot,
+                stdout_plain, stderr_plain, repo_root, project_type, metadata
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&block.id)
@@ -263,17 +799,39 @@ impl BlockStore {
         .bind(&block.stdout)
         .bind(&block.stderr)
         .bind(tags_json)
-        .execute(&self.pool)
-        .await?;
+        .bind(&block.screen_snapshot)
+        // Indexed for search instead of the raw output
+        .bind(strip_ansi(&block.stdout))
+        .bind(strip_ansi(&block.stderr))
+        .bind(repo_root)
+        .bind(project_type)
+        .bind(metadata_json)
+        .execute(&self.pool);
+        with_timeout(self.config.timeout, insert).await?;
         
         Ok(())
     }
     
+    /// Search with `SearchSyntax::Literal`, so any user input is a valid query
     pub async fn search(&self, query: &str) -> Result<Vec<Block>> {
-        let rows = sqlx::query(
+        self.search_with(query, SearchSyntax::Literal).await
+    }
+    
+    /// Full-text search over commands, output and tags. With `SearchSyntax::Fts`
+    /// a malformed query is reported as `TermindError::Configuration`.
+    pub async fn search_with(&self, query: &str, syntax: SearchSyntax) -> Result<Vec<Block>> {
+        let query = match syntax {
+            SearchSyntax::Literal => literal_fts_query(query),
+            SearchSyntax::Fts => query.to_string(),
+        };
+        if query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let fetch = sqlx::query(
             r#"
             SELECT b.id, b.timestamp, b.cwd, b.shell, b.command, b.args,
-                   b.exit_code, b.duration_ms, b.stdout, b.stderr, b.tags
+                   b.exit_code, b.duration_ms, b.stdout, b.stderr, b.tags, b.screen_snapshot, b.metadata
             FROM blocks_fts fts
             JOIN blocks b ON b.rowid = fts.rowid
             WHERE blocks_fts MATCH ?
@@ -281,9 +839,20 @@ impl BlockStore {
             LIMIT 50
             "#,
         )
-        .bind(query)
-        .fetch_all(&self.pool)
-        .await?;
+        .bind(&query)
+        .fetch_all(&self.pool);
+        let rows = match with_timeout(self.config.timeout, fetch).await {
+            // SQLite rejects bad FTS syntax when the statement runs; any other
+            // database error is passed on as it is
+            Err(crate::error::TermindError::Database(sqlx::Error::Database(e))) if is_fts_syntax_error(e.message(), &query) => {
+                return Err(crate::error::TermindError::Configuration(format!(
+                    "invalid search query {:?}: {}",
+                    query,
+                    e.message()
+                )));
+            }
+            result => result?,
+        };
         
         let mut blocks = Vec::new();
         for row in rows {
@@ -294,19 +863,35 @@ impl BlockStore {
         Ok(blocks)
     }
     
+    pub async fn get(&self, id: &str) -> Result<Option<Block>> {
+        let fetch = sqlx::query(
+            r#"
+            SELECT id, timestamp, cwd, shell, command, args,
+                   exit_code, duration_ms, stdout, stderr, tags, screen_snapshot, metadata
+            FROM blocks
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool);
+        let row = with_timeout(self.config.timeout, fetch).await?;
+        
+        row.as_ref().map(Self::row_to_block).transpose()
+    }
+    
     pub async fn get_recent(&self, limit: i32) -> Result<Vec<Block>> {
-        let rows = sqlx::query(
+        let fetch = sqlx::query(
             r#"
             SELECT id, timestamp, cwd, shell, command, args,
-                   exit_code, duration_ms, stdout, stderr, tags
+                   exit_code, duration_ms, stdout, stderr, tags, screen_snapshot, metadata
             FROM blocks
             ORDER BY timestamp DESC
             LIMIT ?
             "#,
         )
         .bind(limit)
-        .fetch_all(&self.pool)
-        .await?;
+        .fetch_all(&self.pool);
+        let rows = with_timeout(self.config.timeout, fetch).await?;
         
         let mut blocks = Vec::new();
         for row in rows {
@@ -318,10 +903,10 @@ impl BlockStore {
     }
     
     pub async fn get_failed(&self, limit: i32) -> Result<Vec<Block>> {
-        let rows = sqlx::query(
+        let fetch = sqlx::query(
             r#"
             SELECT id, timestamp, cwd, shell, command, args,
-                   exit_code, duration_ms, stdout, stderr, tags
+                   exit_code, duration_ms, stdout, stderr, tags, screen_snapshot, metadata
             FROM blocks
             WHERE exit_code IS NOT NULL AND exit_code != 0
             ORDER BY timestamp DESC
@@ -329,8 +914,8 @@ impl BlockStore {
             "#,
         )
         .bind(limit)
-        .fetch_all(&self.pool)
-        .await?;
+        .fetch_all(&self.pool);
+        let rows = with_timeout(self.config.timeout, fetch).await?;
         
         let mut blocks = Vec::new();
         for row in rows {
@@ -341,13 +926,177 @@ impl BlockStore {
         Ok(blocks)
     }
     
+    /// Most recent commands that exited with exactly `code`
+    pub async fn get_by_exit_code(&self, code: i32, limit: i32) -> Result<Vec<Block>> {
+        let fetch = sqlx::query(
+            r#"
+            SELECT id, timestamp, cwd, shell, command, args,
+                   exit_code, duration_ms, stdout, stderr, tags, screen_snapshot, metadata
+            FROM blocks
+            WHERE exit_code = ?
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(code)
+        .bind(limit)
+        .fetch_all(&self.pool);
+        let rows = with_timeout(self.config.timeout, fetch).await?;
+        
+        rows.iter().map(Self::row_to_block).collect()
+    }
+    
+    /// Most recent commands killed by a signal, per `SIGNAL_EXIT_CODES`
+    pub async fn get_signaled(&self, limit: i32) -> Result<Vec<Block>> {
+        let fetch = sqlx::query(
+            r#"
+            SELECT id, timestamp, cwd, shell, command, args,
+                   exit_code, duration_ms, stdout, stderr, tags, screen_snapshot, metadata
+            FROM blocks
+            WHERE exit_code BETWEEN ? AND ?
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(SIGNAL_EXIT_CODES.start())
+        .bind(SIGNAL_EXIT_CODES.end())
+        .bind(limit)
+        .fetch_all(&self.pool);
+        let rows = with_timeout(self.config.timeout, fetch).await?;
+        
+        rows.iter().map(Self::row_to_block).collect()
+    }
+    
+    /// Set `key` to `value` in a stored block's metadata, replacing any
+    /// earlier value. `TermindError::BlockNotFound` if there is no block `id`.
+    pub async fn set_metadata(&self, id: &str, key: &str, value: &str) -> Result<()> {
+        // A JSON merge patch, so the key needs no escaping as a JSON path
+        let patch = serde_json::json!({ key: value }).to_string();
+        let update = sqlx::query("UPDATE blocks SET metadata = json_patch(metadata, ?) WHERE id = ?")
+            .bind(patch)
+            .bind(id)
+            .execute(&self.pool);
+        let result = with_timeout(self.config.timeout, update).await?;
+        if result.rows_affected() == 0 {
+            return Err(crate::error::TermindError::BlockNotFound(id.to_string()));
+        }
+        Ok(())
+    }
+    
+    /// Most recent commands whose metadata has `key`, set to `value` if given
+    pub async fn get_by_metadata(&self, key: &str, value: Option<&str>, limit: i32) -> Result<Vec<Block>> {
+        let fetch = sqlx::query(
+            r#"
+            SELECT id, timestamp, cwd, shell, command, args,
+                   exit_code, duration_ms, stdout, stderr, tags, screen_snapshot, metadata
+            FROM blocks
+            WHERE EXISTS (
+                SELECT 1 FROM json_each(blocks.metadata)
+                WHERE json_each.key = ? AND (? IS NULL OR json_each.value = ?)
+            )
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(key)
+        .bind(value)
+        .bind(value)
+        .bind(limit)
+        .fetch_all(&self.pool);
+        let rows = with_timeout(self.config.timeout, fetch).await?;
+        
+        rows.iter().map(Self::row_to_block).collect()
+    }
+    
+    /// Most recent commands run inside the git repository at `repo_root`
+    pub async fn get_by_repo(&self, repo_root: &str, limit: i32) -> Result<Vec<Block>> {
+        let fetch = sqlx::query(
+            r#"
+            SELECT id, timestamp, cwd, shell, command, args,
+                   exit_code, duration_ms, stdout, stderr, tags, screen_snapshot, metadata
+            FROM blocks
+            WHERE repo_root = ?
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(repo_root)
+        .bind(limit)
+        .fetch_all(&self.pool);
+        let rows = with_timeout(self.config.timeout, fetch).await?;
+        
+        rows.iter().map(Self::row_to_block).collect()
+    }
+    
+    /// Most recent commands run inside a project of the given type
+    pub async fn get_by_project_type(&self, project_type: ProjectType, limit: i32) -> Result<Vec<Block>> {
+        let fetch = sqlx::query(
+            r#"
+            SELECT id, timestamp, cwd, shell, command, args,
+                   exit_code, duration_ms, stdout, stderr, tags, screen_snapshot, metadata
+            FROM blocks
+            WHERE project_type = ?
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(project_type.as_str())
+        .bind(limit)
+        .fetch_all(&self.pool);
+        let rows = with_timeout(self.config.timeout, fetch).await?;
+        
+        rows.iter().map(Self::row_to_block).collect()
+    }
+    
+    /// Distinct past command lines starting with `prefix`, for fish-style
+    /// autosuggestions. Commands run often and recently rank first: each
+    /// scores its run count divided by one plus the days since its last run.
+    /// With `cwd`, only commands run in that directory count. As with
+    /// SQLite's `LIKE`, ASCII letters in `prefix` match either case.
+    pub async fn suggest(&self, prefix: &str, cwd: Option<&str>, limit: i32) -> Result<Vec<CommandSpec>> {
+        let fetch = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT command
+            FROM blocks
+            WHERE command LIKE ? || '%' ESCAPE '\'
+              AND (? IS NULL OR cwd = ?)
+            GROUP BY command
+            ORDER BY COUNT(*) / (1.0 + julianday('now') - julianday(MAX(timestamp))) DESC,
+                     MAX(timestamp) DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(escape_like(prefix))
+        .bind(cwd)
+        .bind(cwd)
+        .bind(limit)
+        .fetch_all(&self.pool);
+        let commands = with_timeout(self.config.timeout, fetch).await?;
+        Ok(commands.iter().map(|command| CommandSpec::parse(command)).collect())
+    }
+    
+    /// Export the blocks with the given ids, in order, as one markdown document
+    pub async fn export_markdown(&self, ids: &[&str]) -> Result<String> {
+        let mut sections = Vec::with_capacity(ids.len());
+        for id in ids {
+            let block = self
+                .get(id)
+                .await?
+                .ok_or_else(|| crate::error::TermindError::BlockNotFound(id.to_string()))?;
+            sections.push(block.to_markdown());
+        }
+        Ok(sections.join("\n---\n\n"))
+    }
+    
     fn row_to_block(row: &SqliteRow) -> Result<Block> {
         let args_json: String = row.try_get("args")?;
         let tags_json: String = row.try_get("tags")?;
+        let metadata_json: String = row.try_get("metadata")?;
         let timestamp_str: String = row.try_get("timestamp")?;
         
         let args: Vec<String> = serde_json::from_str(&args_json)?;
         let tags: Vec<String> = serde_json::from_str(&tags_json)?;
+        let metadata = serde_json::from_str(&metadata_json)?;
         let timestamp = chrono::DateTime::parse_from_rfc3339(&timestamp_str)?
             .with_timezone(&chrono::Utc);
         
@@ -364,14 +1113,55 @@ impl BlockStore {
             stderr: row.try_get("stderr")?,
             tags,
             context: None, // TODO: Add context storage to database
+            screen_snapshot: row.try_get("screen_snapshot")?,
+            metadata,
         })
     }
 }
 
+/// Run a database future, failing with a timed-out I/O error once `timeout` elapses
+async fn with_timeout<T>(
+    timeout: Duration,
+    future: impl Future<Output = std::result::Result<T, sqlx::Error>>,
+) -> Result<T> {
+    match tokio::time::timeout(timeout, future).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(sqlx::Error::Io(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("database operation timed out after {:?}", timeout),
+        )).into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tokio;
+    
+    /// A database file in the temp dir, removed along with its WAL and
+    /// shared-memory files when dropped
+    pub(crate) struct TempDb {
+        path: PathBuf,
+    }
+    
+    impl TempDb {
+        pub(crate) fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("termind-{}-{}.db", name, uuid::Uuid::new_v4()));
+            Self { path }
+        }
+        
+        pub(crate) fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+    
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+            }
+        }
+    }
 
     #[tokio::test]
     async fn test_block_creation() {
@@ -407,6 +1197,406 @@ mod tests {
         assert!(block.success());
     }
 
+    #[test]
+    fn test_parsed_args() {
+        let block = Block::new(r#"git commit -m "a b""#.to_string(), "/src".to_string(), "bash".to_string())
+            .with_parsed_args();
+        assert_eq!(block.args, vec!["commit", "-m", "a b"]);
+        
+        assert_eq!(split_command_line(r#"grep 'x y' dir\ name"#), vec!["grep", "x y", "dir name"]);
+        // Unterminated quote: plain whitespace split
+        assert_eq!(split_command_line(r#"echo "oops here"#), vec!["echo", "\"oops", "here"]);
+        assert!(split_command_line("   ").is_empty());
+    }
+    
+    #[test]
+    fn test_one_line_summary() {
+        let block = Block::new("echo 日本語\nexit 3".to_string(), "/".to_string(), "bash".to_string())
+            .with_exit_code(3);
+        assert_eq!(block.one_line_summary(40), "✗3 echo 日本語 …");
+        assert_eq!(block.one_line_summary(11), "✗3 echo 日…");
+        assert_eq!(block.one_line_summary(10), "✗3 echo …");
+    }
+    
+    #[test]
+    fn test_block_to_markdown() {
+        let block = Block::new("echo `date`".to_string(), "/tmp".to_string(), "bash".to_string())
+            .with_output("```\nfenced\n".to_string(), "oops\n".to_string())
+            .with_exit_code(1)
+            .with_duration(42);
+        let markdown = block.to_markdown();
+        
+        assert!(markdown.starts_with("```sh\n$ echo `date`\n```\n"));
+        assert!(markdown.contains("**Exit code:** 1 · **Duration:** 42 ms · **Directory:** `/tmp`"));
+        // Output containing a fence gets a longer one
+        assert!(markdown.contains("````\n```\nfenced\n````"));
+        assert!(markdown.contains("**stderr**\n\n```\noops\n```"));
+    }
+    
+    #[test]
+    fn test_block_to_markdown_truncates_output() {
+        let stdout: String = (0..MARKDOWN_OUTPUT_MAX_LINES + 5).map(|i| format!("line {}\n", i)).collect();
+        let block = Block::new("seq".to_string(), "/".to_string(), "bash".to_string())
+            .with_output(stdout, String::new());
+        let markdown = block.to_markdown();
+        
+        assert!(markdown.contains(&format!("line {}", MARKDOWN_OUTPUT_MAX_LINES - 1)));
+        assert!(!markdown.contains(&format!("line {}", MARKDOWN_OUTPUT_MAX_LINES)));
+        assert!(markdown.contains("... (5 more lines truncated)"));
+        assert!(markdown.contains("**Exit code:** unknown"));
+        assert!(!markdown.contains("**stderr**"));
+    }
+    
+    #[tokio::test]
+    async fn test_current_command_output() -> Result<()> {
+        let mut detector = BlockDetector::with_storage(MemoryBlockStore::new());
+        assert!(detector.current_stdout().is_none());
+        assert!(detector.current_started_at().is_none());
+        
+        let before = Utc::now();
+        detector.start_command("cargo test".to_string(), "/src".to_string(), "bash".to_string());
+        detector.add_output("running 3 tests\n", false);
+        detector.add_output("warning: unused\n", true);
+        detector.add_output("test a ... ok\n", false);
+        
+        assert_eq!(detector.current_stdout(), Some("running 3 tests\ntest a ... ok\n"));
+        assert_eq!(detector.current_stderr(), Some("warning: unused\n"));
+        assert!(detector.current_started_at().unwrap() >= before);
+        
+        detector.finish_command(0, 5).await?;
+        assert!(detector.current_stdout().is_none());
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_interrupted_command() -> Result<()> {
+        let mut detector = BlockDetector::with_storage(MemoryBlockStore::new());
+        
+        // Ctrl-C at an idle prompt does not carry over to the next command
+        detector.handle_signal(&SignalEvent::Interrupt);
+        detector.start_command("sleep 60".to_string(), "/tmp".to_string(), "bash".to_string());
+        detector.handle_signal(&SignalEvent::WindowChange);
+        assert!(!detector.interrupted);
+        
+        detector.handle_signal(&SignalEvent::Interrupt);
+        let block = detector.finish_command(0, 1200).await?.unwrap().into_block();
+        assert_eq!(block.exit_code, Some(INTERRUPTED_EXIT_CODE));
+        assert_eq!(block.tags, vec![INTERRUPTED_TAG.to_string()]);
+        
+        let stored = detector.get_block(&block.id).await?.unwrap();
+        assert_eq!(stored.exit_code, Some(INTERRUPTED_EXIT_CODE));
+        assert_eq!(stored.tags, vec![INTERRUPTED_TAG.to_string()]);
+        
+        // A status the command reported itself is kept, and the flag resets
+        detector.start_command("make".to_string(), "/src".to_string(), "bash".to_string());
+        detector.mark_interrupted();
+        let block = detector.finish_command(2, 300).await?.unwrap().into_block();
+        assert_eq!(block.exit_code, Some(2));
+        assert!(!detector.interrupted);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_subscribe_receives_finished_blocks() -> Result<()> {
+        let mut detector = BlockDetector::with_storage(MemoryBlockStore::new());
+        let mut receiver = detector.subscribe();
+        
+        for command in ["make", "make test"] {
+            detector.start_command(command.to_string(), "/src".to_string(), "bash".to_string());
+            detector.finish_command(0, 10).await?;
+        }
+        assert_eq!(receiver.recv().await.unwrap().command, "make");
+        assert_eq!(receiver.recv().await.unwrap().command, "make test");
+        assert!(matches!(receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+        
+        // A receiver that falls behind is told how many blocks it missed
+        for _ in 0..BLOCK_STREAM_CAPACITY + 2 {
+            detector.start_command("true".to_string(), "/".to_string(), "bash".to_string());
+            detector.finish_command(0, 1).await?;
+        }
+        assert!(matches!(receiver.recv().await, Err(broadcast::error::RecvError::Lagged(2))));
+        assert!(receiver.recv().await.is_ok());
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_command_hint_starts_block() -> Result<()> {
+        use crate::renderer::TerminalParser;
+        
+        let mut parser = TerminalParser::new(5, 40);
+        parser.parse(b"\x1b]7;file://host/src/app\x07\x1b]633;E;cargo test\\x3b echo done\x07");
+        
+        let mut detector = BlockDetector::with_storage(MemoryBlockStore::new());
+        let started: Vec<bool> = parser
+            .drain_events()
+            .iter()
+            .map(|event| detector.handle_terminal_event(event, "zsh"))
+            .collect();
+        assert_eq!(started, [false, true]);
+        
+        let block = detector.current_block().unwrap();
+        assert_eq!(block.command, "cargo test; echo done");
+        assert_eq!(block.cwd, "/src/app");
+        assert_eq!(block.shell, "zsh");
+        assert_eq!(block.args, ["test;", "echo", "done"]);
+        Ok(())
+    }
+    
+    /// Fails the next `failures` writes, then stores in memory
+    #[derive(Default)]
+    struct FlakyStore {
+        failures: std::sync::atomic::AtomicU32,
+        attempts: std::sync::atomic::AtomicU32,
+        inner: MemoryBlockStore,
+    }
+    
+    impl FlakyStore {
+        fn failing(failures: u32) -> Self {
+            Self { failures: failures.into(), ..Self::default() }
+        }
+    }
+    
+    impl BlockStorage for FlakyStore {
+        async fn store(&self, block: Block) -> Result<()> {
+            use std::sync::atomic::Ordering;
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                return Err(sqlx::Error::PoolTimedOut.into());
+            }
+            self.inner.store(block).await
+        }
+        
+        async fn get(&self, id: &str) -> Result<Option<Block>> {
+            self.inner.get(id).await
+        }
+        
+        async fn search(&self, query: &str) -> Result<Vec<Block>> {
+            self.inner.search(query).await
+        }
+        
+        async fn get_recent(&self, limit: i32) -> Result<Vec<Block>> {
+            self.inner.get_recent(limit).await
+        }
+        
+        async fn get_failed(&self, limit: i32) -> Result<Vec<Block>> {
+            self.inner.get_failed(limit).await
+        }
+        
+        async fn get_by_exit_code(&self, code: i32, limit: i32) -> Result<Vec<Block>> {
+            self.inner.get_by_exit_code(code, limit).await
+        }
+        
+        async fn get_signaled(&self, limit: i32) -> Result<Vec<Block>> {
+            self.inner.get_signaled(limit).await
+        }
+        
+        async fn set_metadata(&self, id: &str, key: &str, value: &str) -> Result<()> {
+            self.inner.set_metadata(id, key, value).await
+        }
+        
+        async fn get_by_metadata(&self, key: &str, value: Option<&str>, limit: i32) -> Result<Vec<Block>> {
+            self.inner.get_by_metadata(key, value, limit).await
+        }
+    }
+    
+    fn quick_retry() -> RetryConfig {
+        RetryConfig::builder()
+            .max_retries(3)
+            .base_delay(Duration::from_millis(1))
+            .max_delay(Duration::from_millis(5))
+            .build()
+            .unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_store_retried_until_it_succeeds() -> Result<()> {
+        let mut detector = BlockDetector::with_storage(FlakyStore::failing(2)).with_store_retry(quick_retry());
+        detector.start_command("make".to_string(), "/src".to_string(), "sh".to_string());
+        let finished = detector.finish_command(0, 10).await?.unwrap();
+        assert!(finished.is_stored());
+        let block = finished.into_block();
+        
+        assert_eq!(detector.storage().attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert!(detector.get_block(&block.id).await?.is_some());
+        assert!(detector.dead_letters().is_empty());
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_unstorable_block_waits_in_dead_letters() -> Result<()> {
+        let mut detector = BlockDetector::with_storage(FlakyStore::failing(3)).with_store_retry(quick_retry());
+        let mut finished = detector.subscribe();
+        detector.start_command("make".to_string(), "/src".to_string(), "sh".to_string());
+        let lost = match detector.finish_command(2, 10).await?.unwrap() {
+            FinishedBlock::Deferred(block) => block,
+            FinishedBlock::Stored(block) => panic!("{} should not have been stored", block.id),
+        };
+        assert_eq!(detector.dead_letters().len(), 1);
+        assert!(detector.get_block(&lost.id).await?.is_none());
+        assert!(finished.try_recv().is_err());
+        // Metadata set meanwhile is stored with the queued block
+        detector.set_metadata(&lost.id, "ticket", "JIRA-1").await?;
+        
+        // The next successful write also persists the queued block
+        detector.start_command("make test".to_string(), "/src".to_string(), "sh".to_string());
+        let next = detector.finish_command(0, 10).await?.unwrap();
+        assert!(next.is_stored());
+        assert!(detector.dead_letters().is_empty());
+        assert_eq!(detector.get_block(&lost.id).await?.unwrap().metadata["ticket"], "JIRA-1");
+        // Subscribers still see the blocks in the order they finished
+        assert_eq!(finished.try_recv().unwrap().id, lost.id);
+        assert_eq!(finished.try_recv().unwrap().id, next.block().id);
+        Ok(())
+    }
+    
+    #[test]
+    fn test_block_signal() {
+        let block = |code| Block::new("cmd".to_string(), "/".to_string(), "sh".to_string()).with_exit_code(code);
+        assert_eq!(block(INTERRUPTED_EXIT_CODE).signal(), Some(2));
+        assert_eq!(block(137).signal(), Some(9));
+        assert_eq!(block(1).signal(), None);
+        assert_eq!(block(128).signal(), None);
+        assert_eq!(block(255).signal(), None);
+        assert_eq!(Block::new("cmd".to_string(), "/".to_string(), "sh".to_string()).signal(), None);
+    }
+    
+    #[tokio::test]
+    async fn test_idle_closes_block() -> Result<()> {
+        let mut detector = BlockDetector::with_storage(MemoryBlockStore::new());
+        assert!(detector.handle_idle(IdleEvent::CommandLikelyDone).await?.is_none());
+        
+        detector.start_command("ls".to_string(), "/tmp".to_string(), "sh".to_string());
+        detector.add_output("a b c\n", false);
+        let block = detector.handle_idle(IdleEvent::CommandLikelyDone).await?.unwrap().into_block();
+        assert_eq!(block.exit_code, Some(0));
+        assert_eq!(block.tags, vec![IDLE_TAG.to_string()]);
+        assert!(detector.current_block().is_none());
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_screen_snapshot_round_trip() -> Result<()> {
+        let mut grid = TextGrid::new(3, 20);
+        grid.write_str("\x1b is not parsed\r\nPASS  2 tests");
+        
+        let db = TempDb::new("snapshot");
+        let mut detector = BlockDetector::with_storage(BlockStore::open(db.path()).await?).with_screen_snapshots(true);
+        detector.start_command("npm test".to_string(), "/app".to_string(), "bash".to_string());
+        let block = detector.finish_command_with_screen(0, 10, &grid).await?.unwrap().into_block();
+        assert_eq!(block.screen_snapshot.as_deref(), Some(grid.to_string().as_str()));
+        
+        let stored = detector.get_block(&block.id).await?.unwrap();
+        assert_eq!(stored.screen_snapshot, block.screen_snapshot);
+        
+        // Capture is opt-in
+        let mut detector = BlockDetector::with_storage(BlockStore::open(db.path()).await?);
+        detector.start_command("npm test".to_string(), "/app".to_string(), "bash".to_string());
+        let block = detector.finish_command_with_screen(0, 10, &grid).await?.unwrap().into_block();
+        assert!(block.screen_snapshot.is_none());
+        assert!(detector.get_block(&block.id).await?.unwrap().screen_snapshot.is_none());
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_prune_and_vacuum_reclaim_space() -> Result<()> {
+        let db = TempDb::new("vacuum");
+        let store = BlockStore::open(db.path()).await?;
+        
+        let output = "x".repeat(4096);
+        let old = Utc::now() - chrono::Duration::days(30);
+        for i in 0..200 {
+            let mut block = Block::new(format!("build {}", i), "/src".to_string(), "sh".to_string())
+                .with_output(output.clone(), String::new())
+                .with_exit_code(0);
+            block.timestamp = old;
+            store.store(block).await?;
+        }
+        let recent = Block::new("make".to_string(), "/src".to_string(), "sh".to_string()).with_exit_code(0);
+        store.store(recent.clone()).await?;
+        assert_eq!(store.row_count().await?, 201);
+        let full = store.database_size_bytes().await?;
+        assert!(full > 200 * 4096, "{} bytes", full);
+        
+        // Deleting leaves free pages behind; vacuum gives them back
+        assert_eq!(store.prune_before(Utc::now() - chrono::Duration::days(7)).await?, 200);
+        assert_eq!(store.row_count().await?, 1);
+        assert_eq!(store.database_size_bytes().await?, full);
+        store.vacuum().await?;
+        let vacuumed = store.database_size_bytes().await?;
+        assert!(vacuumed < full / 10, "{} -> {} bytes", full, vacuumed);
+        
+        // What is left is intact and still searchable
+        assert_eq!(store.get(&recent.id).await?.unwrap().command, "make");
+        assert_eq!(store.search("make").await?.len(), 1);
+        assert!(store.search("build").await?.is_empty());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_vacuum_skips_in_memory_database() -> Result<()> {
+        let config = BlockStoreConfig::default().with_max_connections(1);
+        let store = BlockStore::open_with_config(Path::new(":memory:"), config).await?;
+        assert!(store.is_in_memory().await?);
+        store.store(Block::new("ls".to_string(), "/".to_string(), "sh".to_string())).await?;
+        
+        store.vacuum().await?;
+        assert_eq!(store.row_count().await?, 1);
+        assert!(store.database_size_bytes().await? > 0);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_export_markdown() -> Result<()> {
+        let db = TempDb::new("export");
+        let store = BlockStore::open(db.path()).await?;
+        let first = Block::new("make".to_string(), "/src".to_string(), "zsh".to_string()).with_exit_code(0);
+        let second = Block::new("make test".to_string(), "/src".to_string(), "zsh".to_string()).with_exit_code(2);
+        store.store(first.clone()).await?;
+        store.store(second.clone()).await?;
+        
+        let document = store.export_markdown(&[&first.id, &second.id]).await?;
+        let make = document.find("$ make\n").unwrap();
+        let make_test = document.find("$ make test\n").unwrap();
+        assert!(make < make_test);
+        assert!(document.contains("\n---\n"));
+        
+        let missing = store.export_markdown(&["missing-id"]).await;
+        assert!(matches!(missing, Err(crate::error::TermindError::BlockNotFound(_))));
+        Ok(())
+    }
+    
+    #[test]
+    fn test_data_dir_override() -> Result<()> {
+        let dir = std::env::temp_dir().join("termind-data");
+        assert_eq!(BlockStore::database_path_in(Some(dir.clone()))?, dir.join("blocks.db"));
+        
+        // Unset or empty falls back to the platform data directory
+        let default = BlockStore::database_path_in(None)?;
+        assert!(default.ends_with("termind/blocks.db"));
+        assert_eq!(BlockStore::database_path_in(Some(PathBuf::new()))?, default);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_new_at_opens_given_file() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("termind-new-at-{}", uuid::Uuid::new_v4()));
+        let db_path = dir.join("nested").join("history.db");
+        
+        let store = BlockStore::new_at(&db_path).await?;
+        let block = Block::new("make".to_string(), "/src".to_string(), "zsh".to_string());
+        store.store(block.clone()).await?;
+        assert!(db_path.is_file());
+        drop(store);
+        
+        // Reopening the same file sees the stored block
+        let reopened = BlockStore::new_at(&db_path).await?;
+        assert!(reopened.get(&block.id).await?.is_some());
+        
+        let _ = std::fs::remove_dir_all(&dir);
+        Ok(())
+    }
+    
     #[tokio::test]
     async fn test_block_store_creation() -> Result<()> {
         let _store = BlockStore::new().await?;
@@ -415,7 +1605,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_store_and_retrieve_block() -> Result<()> {
-        let store = BlockStore::new().await?;
+        let db = TempDb::new("retrieve");
+        let store = BlockStore::open(db.path()).await?;
         
         let block = Block::new(
             "pwd".to_string(),
@@ -447,9 +1638,127 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_search_ignores_escape_sequences() -> Result<()> {
+        let db = TempDb::new("plain");
+        let store = BlockStore::open(db.path()).await?;
+        
+        let raw = "\x1b[01;34mdocs\x1b[0m  \x1b[01;32mbuild.sh\x1b[0m\n";
+        let block = Block::new("ls --color".to_string(), "/tmp".to_string(), "bash".to_string())
+            .with_output(raw.to_string(), String::new());
+        store.store(block.clone()).await?;
+        
+        // "34mdocs" would be the indexed token without stripping
+        let found = store.search("docs").await?;
+        assert_eq!(found.len(), 1);
+        // The raw output is kept for rendering
+        assert_eq!(found[0].stdout, raw);
+        
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_repo_and_project_type() -> Result<()> {
+        use crate::blocks::context::{GitContext, GitStatus, ProjectContext};
+        
+        let db = TempDb::new("repo");
+        let store = BlockStore::open(db.path()).await?;
+        
+        let base = ExecutionContext::capture().await?;
+        let in_repo = |root: &str, project_type: ProjectType| {
+            let mut context = base.clone();
+            context.git = Some(GitContext {
+                repository_root: root.to_string(),
+                current_branch: "main".to_string(),
+                head_commit: "abc123".to_string(),
+                status: GitStatus { clean: true, ahead: 0, behind: 0, untracked: 0, modified: 0, staged: 0 },
+                remote_origin: None,
+                uncommitted_changes: false,
+                staged_files: Vec::new(),
+                modified_files: Vec::new(),
+            });
+            context.project = Some(ProjectContext {
+                project_type,
+                project_root: root.to_string(),
+                config_files: Vec::new(),
+                dependencies: Vec::new(),
+                virtual_env: None,
+                package_manager: None,
+            });
+            context
+        };
+        
+        let block = |command: &str| Block::new(command.to_string(), "/src".to_string(), "bash".to_string());
+        let build = block("cargo build").with_context(in_repo("/src/app", ProjectType::Rust));
+        let install = block("npm install").with_context(in_repo("/src/web", ProjectType::Node));
+        let test = block("cargo test").with_context(in_repo("/src/app", ProjectType::Rust));
+        let no_context = block("ls");
+        for b in [&build, &install, &test, &no_context] {
+            store.store(b.clone()).await?;
+        }
+        
+        let app: Vec<String> = store.get_by_repo("/src/app", 10).await?.into_iter().map(|b| b.id).collect();
+        assert_eq!(app.len(), 2);
+        assert!(app.contains(&build.id) && app.contains(&test.id));
+        assert_eq!(store.get_by_repo("/src/app", 1).await?.len(), 1);
+        assert!(store.get_by_repo("/elsewhere", 10).await?.is_empty());
+        
+        let node = store.get_by_project_type(ProjectType::Node, 10).await?;
+        assert_eq!(node.len(), 1);
+        assert_eq!(node[0].command, "npm install");
+        assert!(store.get_by_project_type(ProjectType::Go, 10).await?.is_empty());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_suggest_ranks_by_frequency_and_recency() -> Result<()> {
+        let db = TempDb::new("suggest");
+        let store = BlockStore::open(db.path()).await?;
+        
+        let run = |command: &str, cwd: &str, days_ago: i64| {
+            let mut block = Block::new(command.to_string(), cwd.to_string(), "bash".to_string());
+            block.timestamp = Utc::now() - chrono::Duration::days(days_ago);
+            block
+        };
+        let history = [
+            // Run three times, but five days ago: 3 / 6
+            run("cargo build", "/src/app", 5),
+            run("cargo build", "/src/app", 6),
+            run("cargo build", "/src/app", 7),
+            // Once, just now: 1
+            run("cargo test", "/src/app", 0),
+            // Twice, just now: 2
+            run("cargo check", "/src/web", 0),
+            run("cargo check", "/src/web", 0),
+            run("git status", "/src/app", 0),
+            run("cargo_wrapper --all", "/src/app", 0),
+        ];
+        for block in history {
+            store.store(block).await?;
+        }
+        
+        let commands = |specs: Vec<CommandSpec>| specs.into_iter().map(|spec| spec.command).collect::<Vec<String>>();
+        assert_eq!(
+            commands(store.suggest("cargo ", None, 10).await?),
+            ["cargo check", "cargo test", "cargo build"]
+        );
+        assert_eq!(commands(store.suggest("cargo ", None, 1).await?), ["cargo check"]);
+        assert_eq!(commands(store.suggest("cargo ", Some("/src/app"), 10).await?), ["cargo test", "cargo build"]);
+        // `_` is matched literally rather than as a wildcard
+        assert_eq!(
+            store.suggest("cargo_", None, 10).await?,
+            [CommandSpec { command: "cargo_wrapper --all".to_string(), args: vec!["--all".to_string()] }]
+        );
+        assert!(store.suggest("npm", None, 10).await?.is_empty());
+        
+        Ok(())
+    }
+    
     #[tokio::test]
     async fn test_block_detector() -> Result<()> {
-        let mut detector = BlockDetector::new().await?;
+        let db = TempDb::new("detector");
+        let mut detector = BlockDetector::with_storage(BlockStore::open(db.path()).await?);
         
         // Start a command
         detector.start_command(
@@ -493,4 +1802,136 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_held_connection_times_out() -> Result<()> {
+        let db = TempDb::new("timeout");
+        let config = BlockStoreConfig::default()
+            .with_timeout(Duration::from_millis(200))
+            .with_max_connections(1);
+        let store = BlockStore::open_with_config(db.path(), config).await?;
+        
+        // Hold the only connection so the query cannot acquire one
+        let held = store.pool.acquire().await?;
+        let started = std::time::Instant::now();
+        let result = store.get_recent(10).await;
+        
+        assert!(matches!(result, Err(crate::error::TermindError::Database(_))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        
+        drop(held);
+        assert!(store.get_recent(10).await.is_ok());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_concurrency_pragmas_applied() -> Result<()> {
+        let db = TempDb::new("pragmas");
+        let store = BlockStore::open(db.path()).await?;
+        
+        let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode").fetch_one(&store.pool).await?;
+        assert_eq!(journal_mode.to_lowercase(), "wal");
+        
+        // NORMAL is reported as 1
+        let (synchronous,): (i64,) = sqlx::query_as("PRAGMA synchronous").fetch_one(&store.pool).await?;
+        assert_eq!(synchronous, 1);
+        
+        let (busy_timeout,): (i64,) = sqlx::query_as("PRAGMA busy_timeout").fetch_one(&store.pool).await?;
+        assert_eq!(busy_timeout, 5000);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_migrates_unversioned_database() -> Result<()> {
+        let db = TempDb::new("migrate");
+        
+        // Schema as written before snapshots and versioning existed
+        {
+            let options = SqliteConnectOptions::new().filename(db.path()).create_if_missing(true);
+            let pool = SqlitePoolOptions::new().connect_with(options).await?;
+            sqlx::query(
+                "CREATE TABLE blocks (id TEXT PRIMARY KEY, timestamp TEXT NOT NULL, cwd TEXT NOT NULL, \
+                 shell TEXT NOT NULL, command TEXT NOT NULL, args TEXT NOT NULL, exit_code INTEGER, \
+                 duration_ms INTEGER, stdout TEXT NOT NULL, stderr TEXT NOT NULL, tags TEXT NOT NULL)",
+            )
+            .execute(&pool)
+            .await?;
+            sqlx::query(
+                "INSERT INTO blocks VALUES ('old-1', '2024-01-01T00:00:00+00:00', '/tmp', 'bash', \
+                 'cargo build', '[]', 0, 12, 'Finished', '', '[]')",
+            )
+            .execute(&pool)
+            .await?;
+            pool.close().await;
+        }
+        
+        let store = BlockStore::open(db.path()).await?;
+        assert_eq!(store.schema_version().await?, SCHEMA_VERSION);
+        
+        let (has_snapshot,): (bool,) = sqlx::query_as(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('blocks') WHERE name = 'screen_snapshot'",
+        )
+        .fetch_one(&store.pool)
+        .await?;
+        assert!(has_snapshot);
+        
+        let recent = store.get_recent(10).await?;
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].command, "cargo build");
+        assert_eq!(recent[0].screen_snapshot, None);
+        assert_eq!(store.search("cargo").await?.len(), 1);
+        drop(store);
+        
+        // Reopening is a no-op
+        let store = BlockStore::open(db.path()).await?;
+        assert_eq!(store.schema_version().await?, SCHEMA_VERSION);
+        assert_eq!(store.get_recent(10).await?.len(), 1);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_search_query_syntax() -> Result<()> {
+        let db = TempDb::new("fts");
+        let store = BlockStore::open(db.path()).await?;
+        
+        let block = Block::new("echo \"a OR b\" (x)".to_string(), "/tmp".to_string(), "bash".to_string())
+            .with_output("a OR b (x)\n".to_string(), String::new());
+        store.store(block).await?;
+        store.store(Block::new("ls".to_string(), "/tmp".to_string(), "bash".to_string())).await?;
+        
+        // Operators, quotes and unbalanced parentheses are plain words in literal mode
+        assert_eq!(store.search("a OR b (").await?.len(), 1);
+        assert_eq!(store.search("\"a").await?.len(), 1);
+        assert_eq!(store.search("NOT ls").await?.len(), 0);
+        assert!(store.search("   ").await?.is_empty());
+        
+        // FTS syntax works when asked for, and malformed syntax is a clear error
+        assert_eq!(store.search_with("echo OR ls", SearchSyntax::Fts).await?.len(), 2);
+        let result = store.search_with("foo (", SearchSyntax::Fts).await;
+        match result {
+            Err(crate::error::TermindError::Configuration(message)) => {
+                assert!(message.starts_with("invalid search query"), "{}", message);
+            }
+            other => panic!("expected a configuration error, got {:?}", other.map(|b| b.len())),
+        }
+        assert!(matches!(
+            store.search_with("command:echo nope:x", SearchSyntax::Fts).await,
+            Err(crate::error::TermindError::Configuration(_))
+        ));
+        assert!(matches!(
+            store.search_with("\"echo", SearchSyntax::Fts).await,
+            Err(crate::error::TermindError::Configuration(_))
+        ));
+        
+        // A broken database is not blamed on the query
+        sqlx::query("DROP TABLE blocks_fts").execute(&store.pool).await?;
+        assert!(matches!(
+            store.search_with("echo", SearchSyntax::Fts).await,
+            Err(crate::error::TermindError::Database(_))
+        ));
+        
+        Ok(())
+    }
 }
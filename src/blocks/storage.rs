@@ -0,0 +1,313 @@
+// Where finished blocks are persisted: SQLite by default, or any other backend
+
+use std::future::Future;
+use std::sync::Mutex;
+
+use crate::blocks::{Block, BlockStore};
+use crate::error::{Result, TermindError};
+use crate::text::strip_ansi;
+
+/// Most results `search` returns, matching the SQLite backend
+const SEARCH_LIMIT: usize = 50;
+
+/// Persistence behind `BlockDetector`. `BlockStore` (SQLite) is the default;
+/// `MemoryBlockStore` keeps blocks for the life of the process.
+pub trait BlockStorage: Send + Sync {
+    fn store(&self, block: Block) -> impl Future<Output = Result<()>> + Send;
+    
+    fn get(&self, id: &str) -> impl Future<Output = Result<Option<Block>>> + Send;
+    
+    /// Blocks whose command, output or tags contain every word of `query`,
+    /// taken literally: as whole tokens, or a token prefix for a word ending in `*`
+    fn search(&self, query: &str) -> impl Future<Output = Result<Vec<Block>>> + Send;
+    
+    /// Up to `limit` blocks, newest first
+    fn get_recent(&self, limit: i32) -> impl Future<Output = Result<Vec<Block>>> + Send;
+    
+    /// Up to `limit` blocks with a nonzero exit code, newest first
+    fn get_failed(&self, limit: i32) -> impl Future<Output = Result<Vec<Block>>> + Send;
+    
+    /// Up to `limit` blocks that exited with `code`, newest first
+    fn get_by_exit_code(&self, code: i32, limit: i32) -> impl Future<Output = Result<Vec<Block>>> + Send;
+    
+    /// Up to `limit` blocks killed by a signal (`SIGNAL_EXIT_CODES`), newest first
+    fn get_signaled(&self, limit: i32) -> impl Future<Output = Result<Vec<Block>>> + Send;
+    
+    /// Set `key` to `value` in stored block `id`'s metadata;
+    /// `TermindError::BlockNotFound` if there is no such block
+    fn set_metadata(&self, id: &str, key: &str, value: &str) -> impl Future<Output = Result<()>> + Send;
+    
+    /// Up to `limit` blocks whose metadata has `key`, set to `value` if
+    /// given, newest first
+    fn get_by_metadata(&self, key: &str, value: Option<&str>, limit: i32) -> impl Future<Output = Result<Vec<Block>>> + Send;
+}
+
+impl BlockStorage for BlockStore {
+    async fn store(&self, block: Block) -> Result<()> {
+        BlockStore::store(self, block).await
+    }
+    
+    async fn get(&self, id: &str) -> Result<Option<Block>> {
+        BlockStore::get(self, id).await
+    }
+    
+    async fn search(&self, query: &str) -> Result<Vec<Block>> {
+        BlockStore::search(self, query).await
+    }
+    
+    async fn get_recent(&self, limit: i32) -> Result<Vec<Block>> {
+        BlockStore::get_recent(self, limit).await
+    }
+    
+    async fn get_failed(&self, limit: i32) -> Result<Vec<Block>> {
+        BlockStore::get_failed(self, limit).await
+    }
+    
+    async fn get_by_exit_code(&self, code: i32, limit: i32) -> Result<Vec<Block>> {
+        BlockStore::get_by_exit_code(self, code, limit).await
+    }
+    
+    async fn get_signaled(&self, limit: i32) -> Result<Vec<Block>> {
+        BlockStore::get_signaled(self, limit).await
+    }
+    
+    async fn set_metadata(&self, id: &str, key: &str, value: &str) -> Result<()> {
+        BlockStore::set_metadata(self, id, key, value).await
+    }
+    
+    async fn get_by_metadata(&self, key: &str, value: Option<&str>, limit: i32) -> Result<Vec<Block>> {
+        BlockStore::get_by_metadata(self, key, value, limit).await
+    }
+}
+
+/// Lowercased runs of letters and digits, as FTS5's default tokenizer splits text
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// One word of a literal search, matched like its FTS5 phrase: its tokens
+/// in a row, the last one as a prefix if the word ends in `*`
+struct QueryWord {
+    tokens: Vec<String>,
+    prefix: bool,
+}
+
+impl QueryWord {
+    fn new(word: &str) -> Self {
+        let stem = word.trim_end_matches('*');
+        Self {
+            tokens: tokens(stem),
+            prefix: stem.len() < word.len(),
+        }
+    }
+    
+    fn matches(&self, field: &[String]) -> bool {
+        let last = self.tokens.len() - 1;
+        field.windows(self.tokens.len()).any(|window| {
+            window.iter().zip(&self.tokens).enumerate().all(|(i, (token, wanted))| {
+                if self.prefix && i == last {
+                    token.starts_with(wanted.as_str())
+                } else {
+                    token == wanted
+                }
+            })
+        })
+    }
+}
+
+/// Blocks kept in memory only, for tests and sessions that shouldn't leave
+/// a history behind
+#[derive(Debug, Default)]
+pub struct MemoryBlockStore {
+    blocks: Mutex<Vec<Block>>,
+}
+
+impl MemoryBlockStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Blocks matching `keep`, newest first, at most `limit` of them
+    fn newest(&self, limit: usize, keep: impl Fn(&Block) -> bool) -> Vec<Block> {
+        let blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        let mut matching: Vec<Block> = blocks.iter().filter(|block| keep(block)).cloned().collect();
+        // Stable, so blocks with equal timestamps stay newest-stored first after the reverse
+        matching.reverse();
+        matching.sort_by_key(|block| std::cmp::Reverse(block.timestamp));
+        matching.truncate(limit);
+        matching
+    }
+}
+
+impl BlockStorage for MemoryBlockStore {
+    async fn store(&self, block: Block) -> Result<()> {
+        let mut blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        blocks.retain(|stored| stored.id != block.id);
+        blocks.push(block);
+        Ok(())
+    }
+    
+    async fn get(&self, id: &str) -> Result<Option<Block>> {
+        let blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(blocks.iter().find(|block| block.id == id).cloned())
+    }
+    
+    async fn search(&self, query: &str) -> Result<Vec<Block>> {
+        // Like FTS5, words without any token are left out
+        let words: Vec<QueryWord> = query.split_whitespace().map(QueryWord::new).filter(|word| !word.tokens.is_empty()).collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        Ok(self.newest(SEARCH_LIMIT, |block| {
+            // Searched like the SQLite index: output without escape sequences
+            let fields = [
+                tokens(&block.command),
+                tokens(&strip_ansi(&block.stdout)),
+                tokens(&strip_ansi(&block.stderr)),
+                tokens(&block.tags.join(" ")),
+            ];
+            words.iter().all(|word| fields.iter().any(|field| word.matches(field)))
+        }))
+    }
+    
+    async fn get_recent(&self, limit: i32) -> Result<Vec<Block>> {
+        Ok(self.newest(limit.max(0) as usize, |_| true))
+    }
+    
+    async fn get_failed(&self, limit: i32) -> Result<Vec<Block>> {
+        Ok(self.newest(limit.max(0) as usize, |block| block.exit_code.is_some_and(|code| code != 0)))
+    }
+    
+    async fn get_by_exit_code(&self, code: i32, limit: i32) -> Result<Vec<Block>> {
+        Ok(self.newest(limit.max(0) as usize, |block| block.exit_code == Some(code)))
+    }
+    
+    async fn get_signaled(&self, limit: i32) -> Result<Vec<Block>> {
+        Ok(self.newest(limit.max(0) as usize, |block| block.signal().is_some()))
+    }
+    
+    async fn set_metadata(&self, id: &str, key: &str, value: &str) -> Result<()> {
+        let mut blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        let block = blocks
+            .iter_mut()
+            .find(|block| block.id == id)
+            .ok_or_else(|| TermindError::BlockNotFound(id.to_string()))?;
+        block.metadata.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+    
+    async fn get_by_metadata(&self, key: &str, value: Option<&str>, limit: i32) -> Result<Vec<Block>> {
+        Ok(self.newest(limit.max(0) as usize, |block| {
+            block.metadata.get(key).is_some_and(|stored| value.is_none_or(|value| stored == value))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::tests::TempDb;
+    use crate::blocks::{BlockDetector, INTERRUPTED_TAG};
+    
+    /// The same detector behaviour, whichever backend stores the blocks
+    async fn detector_suite<S: BlockStorage>(mut detector: BlockDetector<S>) -> Result<()> {
+        let commands = [
+            ("cargo build", "\x1b[32mCompiling\x1b[0m termind", 0, false),
+            ("cargo test", "test result: FAILED", 101, false),
+            ("sleep 60", "", 0, true),
+        ];
+        let mut ids = Vec::new();
+        for (command, stdout, exit_code, interrupted) in commands {
+            detector.start_command(command.to_string(), "/src".to_string(), "bash".to_string());
+            detector.add_output(stdout, false);
+            if interrupted {
+                detector.mark_interrupted();
+            }
+            ids.push(detector.finish_command(exit_code, 100).await?.unwrap().into_block().id);
+        }
+        let [build, test, sleep] = <[String; 3]>::try_from(ids).unwrap();
+        
+        let stored = detector.get_block(&build).await?.unwrap();
+        assert_eq!(stored.command, "cargo build");
+        assert_eq!(stored.exit_code, Some(0));
+        assert!(detector.get_block("missing").await?.is_none());
+        
+        let recent: Vec<String> = detector.get_recent(2).await?.into_iter().map(|b| b.id).collect();
+        assert_eq!(recent, [sleep.clone(), test.clone()]);
+        
+        let failed: Vec<String> = detector.get_failed(10).await?.into_iter().map(|b| b.id).collect();
+        assert_eq!(failed, [sleep.clone(), test.clone()]);
+        let interrupted = detector.get_block(&sleep).await?.unwrap();
+        assert_eq!(interrupted.tags, [INTERRUPTED_TAG]);
+        
+        // Escape sequences don't split words, and every word must match
+        let hits: Vec<String> = detector.search("compiling termind").await?.into_iter().map(|b| b.id).collect();
+        assert_eq!(hits, std::slice::from_ref(&build));
+        assert_eq!(detector.search("FAILED").await?.len(), 1);
+        assert!(detector.search("compiling FAILED").await?.is_empty());
+        assert!(detector.search("   ").await?.is_empty());
+        // Words match whole tokens, or a prefix with a trailing `*`; one
+        // with punctuation matches its tokens in a row
+        assert!(detector.search("compil").await?.is_empty());
+        let hits: Vec<String> = detector.search("compil*").await?.into_iter().map(|b| b.id).collect();
+        assert_eq!(hits, std::slice::from_ref(&build));
+        assert_eq!(detector.search("result:").await?.len(), 1);
+        assert_eq!(detector.search("result-failed").await?.len(), 1);
+        assert!(detector.search("failed-result").await?.is_empty());
+        
+        // Exit codes filter exactly; only 129..=192 count as signals
+        let mut by_code = Vec::new();
+        for (command, exit_code) in [("kill -9 $$", 137), ("ssh nowhere", 255), ("false", 1)] {
+            detector.start_command(command.to_string(), "/src".to_string(), "bash".to_string());
+            by_code.push(detector.finish_command(exit_code, 100).await?.unwrap().into_block().id);
+        }
+        let ids = |blocks: Vec<Block>| blocks.into_iter().map(|b| b.id).collect::<Vec<String>>();
+        assert_eq!(ids(detector.get_by_exit_code(130, 10).await?), std::slice::from_ref(&sleep));
+        assert_eq!(ids(detector.get_by_exit_code(101, 10).await?), [test]);
+        assert_eq!(ids(detector.get_by_exit_code(255, 10).await?), [by_code[1].clone()]);
+        assert!(detector.get_by_exit_code(2, 10).await?.is_empty());
+        assert_eq!(ids(detector.get_signaled(10).await?), [by_code[0].clone(), sleep.clone()]);
+        assert_eq!(ids(detector.get_signaled(1).await?), [by_code[0].clone()]);
+        
+        // Metadata set while the command runs is stored with it; later
+        // changes go straight to the store
+        detector.start_command("deploy".to_string(), "/src".to_string(), "bash".to_string());
+        let running = detector.current_block().unwrap().id.clone();
+        detector.set_metadata(&running, "ticket", "JIRA-123").await?;
+        detector.finish_command(0, 100).await?;
+        detector.set_metadata(&running, "ai_explained", "true").await?;
+        detector.set_metadata(&by_code[2], "ticket", "JIRA-7").await?;
+        
+        let stored = detector.get_block(&running).await?.unwrap();
+        assert_eq!(stored.metadata.len(), 2);
+        assert_eq!(stored.metadata["ticket"], "JIRA-123");
+        assert_eq!(stored.metadata["ai_explained"], "true");
+        assert_eq!(ids(detector.get_by_metadata("ticket", Some("JIRA-123"), 10).await?), std::slice::from_ref(&running));
+        assert_eq!(ids(detector.get_by_metadata("ticket", None, 10).await?), [running.clone(), by_code[2].clone()]);
+        assert!(detector.get_by_metadata("ticket", Some("JIRA-999"), 10).await?.is_empty());
+        assert!(detector.get_block(&build).await?.unwrap().metadata.is_empty());
+        // Not full-text indexed
+        assert!(detector.search("JIRA").await?.is_empty());
+        assert!(matches!(
+            detector.set_metadata("missing", "ticket", "x").await,
+            Err(TermindError::BlockNotFound(_))
+        ));
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_detector_with_sqlite_store() -> Result<()> {
+        let db = TempDb::new("storage");
+        let store = BlockStore::open(db.path()).await?;
+        detector_suite(BlockDetector::with_storage(store)).await
+    }
+    
+    #[tokio::test]
+    async fn test_detector_with_memory_store() -> Result<()> {
+        detector_suite(BlockDetector::with_storage(MemoryBlockStore::new())).await
+    }
+}
@@ -0,0 +1,263 @@
+// User configuration: defaults, overridden by the config file, overridden by CLI flags
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::error::{Result, TermindError};
+use crate::pty::{DEFAULT_IDLE_TIMEOUT, DEFAULT_OUTPUT_BUDGET};
+use crate::renderer::{BellConfig, FontSizeLimits, KeyBinding, Keybindings, RendererChoice};
+
+/// Settings read from `config.toml`. Every key is optional; missing keys
+/// keep their defaults and unknown keys are rejected so typos surface.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Shell to run instead of $SHELL or the detected default
+    pub shell: Option<String>,
+    pub renderer: RendererChoice,
+    /// Smallest font size zooming may reach, in pixels
+    pub min_font_size: f32,
+    /// Largest font size zooming may reach, in pixels, at most `MAX_FONT_SIZE`
+    pub max_font_size: f32,
+    /// Quiet period after a submitted command before it is assumed done,
+    /// for shells without OSC 133 markers
+    pub idle_timeout_ms: u64,
+    /// Bytes of PTY output parsed before input and rendering get a turn
+    pub output_budget_bytes: usize,
+    /// Return to the bottom when output arrives while scrolled up
+    pub scroll_on_output: bool,
+    /// Keep lines scrolled off the alternate screen in scrollback
+    pub alternate_scrollback: bool,
+    /// Copy the selection to the clipboard when the mouse button is released
+    pub copy_on_select: bool,
+    /// Font files tried in order for glyphs the primary font lacks
+    pub font_fallbacks: Vec<PathBuf>,
+    /// `[[keybindings]]` entries replacing the default for their key
+    pub keybindings: Vec<KeyBinding>,
+    /// What BEL does: flash, beep, both or nothing
+    pub bell: BellConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let font_limits = FontSizeLimits::default();
+        Self {
+            shell: None,
+            renderer: RendererChoice::default(),
+            min_font_size: font_limits.min,
+            max_font_size: font_limits.max,
+            idle_timeout_ms: DEFAULT_IDLE_TIMEOUT.as_millis() as u64,
+            output_budget_bytes: DEFAULT_OUTPUT_BUDGET,
+            scroll_on_output: true,
+            alternate_scrollback: false,
+            copy_on_select: true,
+            font_fallbacks: Vec::new(),
+            keybindings: Vec::new(),
+            bell: BellConfig::default(),
+        }
+    }
+}
+
+/// Values given on the command line; `None` leaves the configured value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOverrides {
+    pub shell: Option<String>,
+    pub renderer: Option<RendererChoice>,
+    pub min_font_size: Option<f32>,
+    pub max_font_size: Option<f32>,
+    pub idle_timeout_ms: Option<u64>,
+}
+
+impl Config {
+    /// Load `config_path()`, or the defaults when there is no config file
+    pub fn load() -> Result<Self> {
+        match Self::config_path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+    
+    /// Load the config file at `path`, or the defaults if it doesn't exist.
+    /// A file that can't be read or parsed is a `TermindError::Configuration`.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(TermindError::Configuration(format!("cannot read {}: {}", path.display(), e)));
+            }
+        };
+        Self::parse(&contents)
+            .map_err(|e| TermindError::Configuration(format!("{}: {}", path.display(), e)))
+    }
+    
+    /// Parse config file contents
+    pub fn parse(contents: &str) -> std::result::Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+    
+    /// `$XDG_CONFIG_HOME/termind/config.toml`, falling back to
+    /// `~/.config/termind/config.toml`
+    pub fn config_path() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| dirs::home_dir().map(|home| home.join(".config")))?;
+        Some(config_home.join("termind").join("config.toml"))
+    }
+    
+    /// This config with every flag given on the command line taking precedence
+    pub fn merge(mut self, overrides: ConfigOverrides) -> Self {
+        if let Some(shell) = overrides.shell {
+            self.shell = Some(shell);
+        }
+        if let Some(renderer) = overrides.renderer {
+            self.renderer = renderer;
+        }
+        if let Some(size) = overrides.min_font_size {
+            self.min_font_size = size;
+        }
+        if let Some(size) = overrides.max_font_size {
+            self.max_font_size = size;
+        }
+        if let Some(timeout) = overrides.idle_timeout_ms {
+            self.idle_timeout_ms = timeout;
+        }
+        self
+    }
+    
+    pub fn font_size_limits(&self) -> FontSizeLimits {
+        FontSizeLimits::new(self.min_font_size, self.max_font_size)
+    }
+    
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_millis(self.idle_timeout_ms)
+    }
+    
+    /// The default key bindings with the configured ones layered on top
+    pub fn keybindings(&self) -> Keybindings {
+        Keybindings::default().with_overrides(&self.keybindings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_sample_config() {
+        let config = Config::parse(
+            r#"
+            shell = "/bin/zsh"
+            renderer = "software"
+            max_font_size = 40.0
+            scroll_on_output = false
+            output_budget_bytes = 16384
+            font_fallbacks = ["/usr/share/fonts/NotoSansSymbols2.ttf"]
+            "#,
+        )
+        .unwrap();
+        
+        assert_eq!(config.shell.as_deref(), Some("/bin/zsh"));
+        assert_eq!(config.renderer, RendererChoice::Software);
+        assert_eq!(config.font_size_limits(), FontSizeLimits::new(8.0, 40.0));
+        assert!(!config.scroll_on_output);
+        assert_eq!(config.output_budget_bytes, 16 * 1024);
+        assert_eq!(config.font_fallbacks, vec![PathBuf::from("/usr/share/fonts/NotoSansSymbols2.ttf")]);
+        // Keys left out keep their defaults
+        assert_eq!(config.idle_timeout(), DEFAULT_IDLE_TIMEOUT);
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        // Sizes no renderer can draw are capped
+        let huge = Config::parse("max_font_size = 1000.0").unwrap();
+        assert_eq!(huge.font_size_limits().max, crate::renderer::MAX_FONT_SIZE);
+        
+        assert!(Config::parse("font_szie = 12.0").is_err());
+        assert!(Config::parse(r#"renderer = "vulkan""#).is_err());
+    }
+    
+    #[test]
+    fn test_parse_keybindings() {
+        use crate::renderer::KeyAction;
+        use winit::keyboard::{KeyCode, ModifiersState};
+        
+        let config = Config::parse(
+            r#"
+            [[keybindings]]
+            key = "Ctrl+Shift+K"
+            action = { send_bytes = "clear\r" }
+            
+            [[keybindings]]
+            key = "Escape"
+            action = "passthrough"
+            
+            [[keybindings]]
+            key = "Ctrl+Q"
+            action = "quit"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.keybindings.len(), 3);
+        
+        let bindings = config.keybindings();
+        let ctrl = ModifiersState::CONTROL;
+        assert_eq!(
+            bindings.resolve(KeyCode::KeyK, ctrl | ModifiersState::SHIFT),
+            Some(&KeyAction::SendBytes(b"clear\r".to_vec()))
+        );
+        assert_eq!(bindings.resolve(KeyCode::KeyQ, ctrl), Some(&KeyAction::Quit));
+        assert_eq!(bindings.resolve(KeyCode::Escape, ModifiersState::empty()), None);
+        assert_eq!(bindings.resolve(KeyCode::Minus, ctrl), Some(&KeyAction::ZoomOut));
+        
+        assert!(Config::parse("[[keybindings]]\nkey = \"Hyper+K\"\naction = \"quit\"").is_err());
+        assert!(Config::parse("[[keybindings]]\nkey = \"K\"\naction = \"explode\"").is_err());
+    }
+    
+    #[test]
+    fn test_parse_bell() {
+        use crate::renderer::BellMode;
+        
+        assert_eq!(Config::default().bell.mode, BellMode::Visual);
+        let config = Config::parse("[bell]\nmode = \"both\"").unwrap();
+        assert_eq!(config.bell.mode, BellMode::Both);
+        assert_eq!(Config::parse("[bell]\nmode = \"none\"").unwrap().bell.mode, BellMode::None);
+        
+        assert!(Config::parse("[bell]\nmode = \"loud\"").is_err());
+        assert!(Config::parse("[bell]\nvolume = 3").is_err());
+    }
+    
+    #[test]
+    fn test_cli_overrides_file() {
+        let file = Config::parse(
+            r#"
+            renderer = "gpu"
+            min_font_size = 10.0
+            idle_timeout_ms = 3000
+            "#,
+        )
+        .unwrap();
+        
+        let merged = file.clone().merge(ConfigOverrides {
+            renderer: Some(RendererChoice::Software),
+            idle_timeout_ms: Some(500),
+            ..ConfigOverrides::default()
+        });
+        assert_eq!(merged.renderer, RendererChoice::Software);
+        assert_eq!(merged.idle_timeout_ms, 500);
+        // Flags not given leave the file's values alone
+        assert_eq!(merged.min_font_size, 10.0);
+        assert_eq!(file.clone().merge(ConfigOverrides::default()), file);
+    }
+    
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let path = std::env::temp_dir().join(format!("termind-no-config-{}.toml", uuid::Uuid::new_v4()));
+        assert_eq!(Config::load_from(&path).unwrap(), Config::default());
+        
+        std::fs::write(&path, "renderer = 3").unwrap();
+        let error = Config::load_from(&path).unwrap_err();
+        assert!(matches!(error, TermindError::Configuration(ref message) if message.contains(&*path.to_string_lossy())));
+        let _ = std::fs::remove_file(&path);
+    }
+}
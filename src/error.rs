@@ -26,6 +26,12 @@ pub enum TermindError {
     #[error("DateTime parsing error: {0}")]
     DateTime(#[from] chrono::ParseError),
     
+    #[error("Block not found: {0}")]
+    BlockNotFound(String),
+    
+    #[error("Clipboard error: {0}")]
+    Clipboard(String),
+    
 }
 
 pub type Result<T> = std::result::Result<T, TermindError>;
@@ -0,0 +1,219 @@
+// Shell integration - snippets that make the shell emit OSC 133 prompt markers
+//
+// Markers emitted (FinalTerm / OSC 133 semantic prompts):
+//   A      - prompt start
+//   B      - prompt end, command input starts
+//   C      - command output starts
+//   D;code - command finished with exit code
+//
+// The zsh and bash snippets also report, as VS Code does with OSC 633:
+//   E;cmd     - the command line about to run, before C
+//   P;Cwd=dir - the working directory, before each prompt
+// Inside a parameter `\` is written `\\` and `;` or newlines as `\xHH`.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Shell::Zsh => "zsh",
+            Shell::Bash => "bash",
+            Shell::Fish => "fish",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "zsh" => Ok(Shell::Zsh),
+            "bash" => Ok(Shell::Bash),
+            "fish" => Ok(Shell::Fish),
+            other => Err(format!("Unsupported shell '{}' (expected zsh, bash or fish)", other)),
+        }
+    }
+}
+
+const ZSH_HOOK: &str = r#"# Termind shell integration (zsh)
+__termind_escape() {
+  local s=${1//\\/\\\\}
+  s=${s//;/\\x3b}
+  printf '%s' "${s//$'\n'/\\x0a}"
+}
+__termind_precmd() {
+  local ret=$?
+  if [[ -n "$__termind_running" ]]; then
+    printf '\033]133;D;%s\007' "$ret"
+  fi
+  __termind_running=
+  printf '\033]633;P;Cwd=%s\007' "$(__termind_escape "$PWD")"
+  printf '\033]133;A\007'
+}
+__termind_preexec() {
+  __termind_running=1
+  printf '\033]633;E;%s\007' "$(__termind_escape "$1")"
+  printf '\033]133;C\007'
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd __termind_precmd
+add-zsh-hook preexec __termind_preexec
+PS1="$PS1"$'%{\033]133;B\007%}'
+"#;
+
+const BASH_HOOK: &str = r#"# Termind shell integration (bash)
+__termind_escape() {
+  local s=${1//\\/\\\\}
+  s=${s//;/\\x3b}
+  printf '%s' "${s//$'\n'/\\x0a}"
+}
+__termind_prompt_command() {
+  local ret=$?
+  __termind_in_prompt=1
+  if [[ -n "$__termind_running" ]]; then
+    printf '\033]133;D;%s\007' "$ret"
+  fi
+  __termind_running=
+  printf '\033]633;P;Cwd=%s\007' "$(__termind_escape "$PWD")"
+  printf '\033]133;A\007'
+}
+__termind_prompt_done() {
+  __termind_in_prompt=
+}
+__termind_preexec() {
+  [[ -n "$COMP_LINE" ]] && return
+  [[ -n "$__termind_in_prompt" ]] && return
+  [[ "$BASH_COMMAND" == __termind_prompt_command* ]] && return
+  if [[ -z "$__termind_running" ]]; then
+    __termind_running=1
+    local cmd
+    cmd=$(HISTTIMEFORMAT= history 1 | sed 's/^ *[0-9]* *//')
+    printf '\033]633;E;%s\007' "$(__termind_escape "$cmd")"
+    printf '\033]133;C\007'
+  fi
+}
+# The user's own PROMPT_COMMAND runs in between; the guard keeps it from
+# looking like a command to the DEBUG trap
+PROMPT_COMMAND="__termind_prompt_command${PROMPT_COMMAND:+;$PROMPT_COMMAND};__termind_prompt_done"
+PS1="$PS1"'\[\033]133;B\007\]'
+trap '__termind_preexec' DEBUG
+"#;
+
+const FISH_HOOK: &str = r#"# Termind shell integration (fish)
+function __termind_prompt_start --on-event fish_prompt
+    printf '\033]133;A\007'
+end
+function __termind_preexec --on-event fish_preexec
+    printf '\033]133;C\007'
+end
+function __termind_postexec --on-event fish_postexec
+    printf '\033]133;D;%s\007' $status
+end
+functions -c fish_prompt __termind_original_prompt
+function fish_prompt
+    __termind_original_prompt
+    printf '\033]133;B\007'
+end
+"#;
+
+/// Snippet for `shell` that emits OSC 133 A/B/C/D markers (and, for zsh and
+/// bash, OSC 633 E/P), meant to be sourced from the user's shell startup file
+pub fn shell_hook(shell: Shell) -> String {
+    match shell {
+        Shell::Zsh => ZSH_HOOK,
+        Shell::Bash => BASH_HOOK,
+        Shell::Fish => FISH_HOOK,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_has_markers(snippet: &str) {
+        for marker in ["133;A", "133;B", "133;C", "133;D;%s"] {
+            assert!(snippet.contains(&format!("\\033]{}\\007", marker)), "missing {}", marker);
+        }
+    }
+
+    #[test]
+    fn test_zsh_hook() {
+        let hook = shell_hook(Shell::Zsh);
+        assert_has_markers(&hook);
+        assert!(hook.contains("add-zsh-hook precmd"));
+        assert!(hook.contains("\"$ret\""));
+        assert!(hook.contains("\\033]633;E;%s\\007"));
+        assert!(hook.contains("\\033]633;P;Cwd=%s\\007"));
+    }
+
+    #[test]
+    fn test_bash_hook() {
+        let hook = shell_hook(Shell::Bash);
+        assert_has_markers(&hook);
+        assert!(hook.contains("\\033]633;E;%s\\007"));
+        assert!(hook.contains("\\033]633;P;Cwd=%s\\007"));
+    }
+
+    #[test]
+    fn test_bash_hook_with_existing_prompt_command() {
+        let rcfile = std::env::temp_dir().join(format!("termind-bashrc-{}", uuid::Uuid::new_v4()));
+        let rc = format!("PROMPT_COMMAND='__user_prompt=1; true'\n{}", shell_hook(Shell::Bash));
+        std::fs::write(&rcfile, rc).unwrap();
+
+        // Two commands around an empty line, then `exit`, which is a command too
+        let mut child = std::process::Command::new("bash")
+            .arg("--rcfile")
+            .arg(&rcfile)
+            .arg("-i")
+            .env("HOME", std::env::temp_dir())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        {
+            use std::io::Write;
+            let mut stdin = child.stdin.take().unwrap();
+            stdin.write_all(b"echo one\n\necho 'a;b'\nexit\n").unwrap();
+        }
+        let output = child.wait_with_output().unwrap();
+        let _ = std::fs::remove_file(&rcfile);
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let starts = stdout.matches("\x1b]133;C\x07").count();
+        let ends = stdout.matches("\x1b]133;D;0\x07").count();
+        // The empty line must not record a block of its own
+        assert_eq!((starts, ends), (3, 2), "{:?}", stdout);
+        // Each command line is reported before its output starts
+        assert!(stdout.contains("\x1b]633;E;echo one\x07\x1b]133;C\x07"), "{:?}", stdout);
+        assert!(stdout.contains("\x1b]633;E;echo 'a\\x3bb'\x07"), "{:?}", stdout);
+        assert!(stdout.contains("\x1b]633;P;Cwd="), "{:?}", stdout);
+    }
+
+    #[test]
+    fn test_fish_hook() {
+        let hook = shell_hook(Shell::Fish);
+        assert_has_markers(&hook);
+        assert!(hook.contains("--on-event fish_postexec"));
+        assert!(hook.contains("$status"));
+    }
+
+    #[test]
+    fn test_shell_from_str() {
+        assert_eq!("zsh".parse::<Shell>(), Ok(Shell::Zsh));
+        assert_eq!("BASH".parse::<Shell>(), Ok(Shell::Bash));
+        assert_eq!("fish".parse::<Shell>(), Ok(Shell::Fish));
+        assert!("tcsh".parse::<Shell>().is_err());
+    }
+}
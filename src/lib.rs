@@ -10,14 +10,24 @@
 //! - **Text Grid**: Terminal screen state representation
 //! - **Block Detection**: Command block identification and storage (Week 3)
 //! - **GPU Renderer**: Hardware-accelerated terminal rendering (stub)
+//! - **Headless Session**: Run and replay commands without a window
+//! - **Shell Integration**: OSC 133 prompt marker snippets for zsh/bash/fish
+//! - **Configuration**: `config.toml` settings merged with command-line flags
 
 pub mod error;
 pub mod pty;
 pub mod renderer;
 pub mod blocks;
+pub mod session;
+pub mod integration;
+pub mod text;
+pub mod config;
+pub mod logging;
 
 // Re-export commonly used types
 pub use error::{Result, TermindError};
 pub use pty::{PtyHost, SignalHandler, ProcessManager};
-pub use renderer::{TextGrid, TerminalParser, colors};
+pub use renderer::{TextGrid, TerminalParser, TerminalEmulator, colors};
 pub use blocks::BlockDetector;
+pub use session::HeadlessSession;
+pub use config::Config;
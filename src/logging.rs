@@ -0,0 +1,68 @@
+// Tracing setup for the binaries: to stdout, or to a daily-rotated log file
+
+use std::path::Path;
+
+use tracing::Level;
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
+
+use crate::error::{Result, TermindError};
+
+/// Install the global tracing subscriber. With `log_file`, logs go to that
+/// file (rotated daily, so the date is appended to its name) instead of
+/// stdout; keep the returned guard alive until exit so buffered lines are flushed.
+pub fn init(level: Level, log_file: Option<&Path>) -> Result<Option<WorkerGuard>> {
+    let Some(path) = log_file else {
+        tracing_subscriber::fmt().with_max_level(level).with_target(false).init();
+        return Ok(None);
+    };
+    
+    let (writer, guard) = file_writer(path)?;
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .with_ansi(false)
+        .with_writer(writer)
+        .init();
+    Ok(Some(guard))
+}
+
+/// Non-blocking writer appending to `path`, rotated daily
+pub fn file_writer(path: &Path) -> Result<(NonBlocking, WorkerGuard)> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| TermindError::Configuration(format!("log file {} has no file name", path.display())))?;
+    let directory = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    std::fs::create_dir_all(directory)?;
+    
+    let appender = tracing_appender::rolling::daily(directory, file_name);
+    Ok(tracing_appender::non_blocking(appender))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_file_writer_creates_log() {
+        let dir = std::env::temp_dir().join(format!("termind-log-{}", uuid::Uuid::new_v4()));
+        let (writer, guard) = file_writer(&dir.join("nested").join("termind.log")).unwrap();
+        
+        let subscriber = tracing_subscriber::fmt().with_ansi(false).with_writer(writer).finish();
+        tracing::subscriber::with_default(subscriber, || tracing::info!("hello from the log test"));
+        // Flushes the background writer
+        drop(guard);
+        
+        let logs: Vec<_> = std::fs::read_dir(dir.join("nested")).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].file_name().unwrap().to_string_lossy().starts_with("termind.log."));
+        let contents = std::fs::read_to_string(&logs[0]).unwrap();
+        assert!(contents.contains("hello from the log test"), "{:?}", contents);
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_file_writer_needs_file_name() {
+        assert!(matches!(file_writer(Path::new("/")), Err(TermindError::Configuration(_))));
+    }
+}
@@ -1,33 +1,274 @@
+use clap::{Args, Parser, Subcommand};
+use std::io::Write;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tracing::{info, warn};
 use winit::{
     event::{Event, WindowEvent, KeyEvent, ElementState},
     event_loop::{ControlFlow, EventLoop},
-    keyboard::{PhysicalKey, KeyCode},
+    keyboard::{PhysicalKey, KeyCode, ModifiersState},
     window::WindowBuilder,
 };
 use softbuffer::{Context, Surface};
 use std::num::NonZeroU32;
 
-use termind::renderer::{TextGrid, TerminalParser};
+use termind::config::{Config, ConfigOverrides};
+use termind::renderer::{KeyAction, RendererChoice, TextGrid, TerminalParser};
 use termind::renderer::software::SoftwareRenderer;
+use termind::integration::{self, Shell};
+use termind::blocks::{Block, BlockStore, SearchSyntax};
+use termind::pty::{Debouncer, PtyHost, SpawnOptions, RESIZE_DEBOUNCE};
+use termind::session::HeadlessSession;
+use termind::text::truncate_display;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+/// Half-period of the cursor blink animation
+const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(530);
+
+#[derive(Parser)]
+#[command(name = "termind", version = "0.3.0", about = "Privacy-first, AI-powered terminal")]
+struct Cli {
+    /// Print the shell integration snippet for SHELL (zsh, bash, fish) and exit
+    #[arg(long, value_name = "SHELL")]
+    print_integration: Option<Shell>,
+    
+    /// Shell to run instead of $SHELL or the detected default
+    #[arg(long, value_name = "PATH")]
+    shell: Option<String>,
+    
+    /// Smallest font size Ctrl-- may zoom out to [default: 8]
+    #[arg(long, value_name = "PX")]
+    min_font_size: Option<f32>,
+    
+    /// Largest font size Ctrl-+ may zoom in to [default: 32]
+    #[arg(long, value_name = "PX")]
+    max_font_size: Option<f32>,
+    
+    /// Renderer backend: auto, gpu or software. This binary only renders in
+    /// software, so `gpu` is an error. [default: auto]
+    #[arg(long, value_name = "BACKEND")]
+    renderer: Option<RendererChoice>,
+    
+    /// Write logs to PATH (rotated daily) instead of stdout
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+    
+    /// Run COMMAND through the shell without opening a window, print its
+    /// output and exit with its exit code
+    #[arg(long, value_name = "COMMAND")]
+    exec: Option<String>,
+    
+    /// With --exec, print the recorded block as JSON instead of the output
+    #[arg(long, requires = "exec")]
+    json: bool,
+    
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Query stored command history
+    Blocks(BlocksArgs),
+}
+
+#[derive(Args)]
+struct BlocksArgs {
+    /// Print results as JSON instead of a table
+    #[arg(long, global = true)]
+    json: bool,
+    
+    /// Block database to read instead of the default one
+    #[arg(long, value_name = "PATH", global = true)]
+    db: Option<PathBuf>,
+    
+    #[command(subcommand)]
+    query: BlocksQuery,
+}
+
+#[derive(Subcommand)]
+enum BlocksQuery {
+    /// Most recent commands
+    Recent {
+        #[arg(long, default_value_t = 20)]
+        limit: i32,
+    },
+    /// Full-text search over commands and their output
+    Search {
+        query: String,
+        
+        /// Interpret QUERY as SQLite FTS5 syntax (AND/OR/NOT, "phrases", prefix*)
+        #[arg(long)]
+        fts: bool,
+    },
+    /// Most recent commands that exited with a non-zero code
+    Failed {
+        #[arg(long, default_value_t = 20)]
+        limit: i32,
+    },
+    /// Delete old blocks, then compact the database file
+    Gc {
+        /// Keep blocks from the last DAYS days
+        #[arg(long, value_name = "DAYS", default_value_t = 90)]
+        older_than_days: u32,
+    },
+}
+
+/// What `termind blocks gc` did
+#[derive(serde::Serialize)]
+struct GcReport {
+    removed: u64,
+    remaining: u64,
+    size_before: u64,
+    size_after: u64,
+}
+
+async fn run_blocks_command(args: BlocksArgs) -> Result<()> {
+    let store = match &args.db {
+        Some(path) => BlockStore::open(path).await?,
+        None => BlockStore::new().await?,
+    };
+    
+    let blocks = match &args.query {
+        BlocksQuery::Recent { limit } => store.get_recent(*limit).await?,
+        BlocksQuery::Search { query, fts } => {
+            let syntax = if *fts { SearchSyntax::Fts } else { SearchSyntax::Literal };
+            store.search_with(query, syntax).await?
+        }
+        BlocksQuery::Failed { limit } => store.get_failed(*limit).await?,
+        BlocksQuery::Gc { older_than_days } => return run_blocks_gc(&store, *older_than_days, args.json).await,
+    };
+    
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&blocks)?);
+    } else {
+        print_blocks_table(&blocks);
+    }
+    
+    Ok(())
+}
+
+/// Prune blocks older than `older_than_days`, vacuum, and report what changed
+async fn run_blocks_gc(store: &BlockStore, older_than_days: u32, json: bool) -> Result<()> {
+    let size_before = store.database_size_bytes().await?;
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(older_than_days.into());
+    let removed = store.prune_before(cutoff).await?;
+    store.vacuum().await?;
+    let report = GcReport {
+        removed,
+        remaining: store.row_count().await?,
+        size_before,
+        size_after: store.database_size_bytes().await?,
+    };
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!(
+            "Removed {} blocks older than {} days, {} left; database {} -> {} bytes",
+            report.removed, older_than_days, report.remaining, report.size_before, report.size_after
+        );
+    }
+    Ok(())
+}
+
+/// Run `command` in the current directory as a recorded block and return
+/// the exit code for this process
+async fn run_exec_command(command: &str, shell: Option<String>, json: bool) -> Result<i32> {
+    let mut session = HeadlessSession::new().await?;
+    if let Some(shell) = shell {
+        session = session.with_shell(shell);
+    }
+    let block = session.run(command, &std::env::current_dir()?).await?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&block)?);
+    } else {
+        let mut stdout = std::io::stdout();
+        stdout.write_all(block.stdout.as_bytes())?;
+        stdout.flush()?;
+        let mut stderr = std::io::stderr();
+        stderr.write_all(block.stderr.as_bytes())?;
+        stderr.flush()?;
+    }
+    
+    // Killed by a signal (-1) or otherwise unrepresentable: plain failure
+    Ok(block.exit_code.filter(|code| (0..=255).contains(code)).unwrap_or(1))
+}
+
+/// Commands longer than this are cut in the table view
+const COMMAND_COLUMN_WIDTH: usize = 60;
+
+fn print_blocks_table(blocks: &[Block]) {
+    println!("{:<8}  {:<19}  {:>4}  {:>8}  COMMAND", "ID", "TIME", "EXIT", "DURATION");
+    for block in blocks {
+        let exit = block.exit_code.map_or("-".to_string(), |code| code.to_string());
+        let duration = block.duration_ms.map_or("-".to_string(), |ms| format!("{}ms", ms));
+        println!(
+            "{:<8}  {:<19}  {:>4}  {:>8}  {}",
+            block.id.get(..8).unwrap_or(&block.id),
+            block.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            exit,
+            duration,
+            truncate_display(&block.command.replace('\n', " "), COMMAND_COLUMN_WIDTH),
+        );
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    
+    if let Some(shell) = cli.print_integration {
+        print!("{}", integration::shell_hook(shell));
+        return Ok(());
+    }
+    
+    if let Some(Command::Blocks(args)) = cli.command {
+        return run_blocks_command(args).await;
+    }
+    
+    if let Some(command) = &cli.exec {
+        let shell = Config::load()?.merge(ConfigOverrides {
+            shell: cli.shell.clone(),
+            ..ConfigOverrides::default()
+        }).shell;
+        let code = run_exec_command(command, shell, cli.json).await?;
+        std::process::exit(code);
+    }
+    
     // Initialize logging
-    tracing_subscriber::fmt().init();
+    let _log_guard = termind::logging::init(tracing::Level::INFO, cli.log_file.as_deref())?;
 
     info!("🚀 Starting Termind v0.3.0 - Software Rendering");
     info!("📋 Initializing components...");
 
+    // Flags given on the command line win over config.toml
+    let config = Config::load()?.merge(ConfigOverrides {
+        shell: cli.shell,
+        renderer: cli.renderer,
+        min_font_size: cli.min_font_size,
+        max_font_size: cli.max_font_size,
+        ..ConfigOverrides::default()
+    });
+
     // Set up terminal dimensions
     let terminal_cols = 80;
     let terminal_rows = 24;
     info!("📏 Terminal size: {}x{}", terminal_cols, terminal_rows);
 
+    // Resolve the shell up front so a bad --shell fails before the window opens
+    let spawn_options = SpawnOptions::default().with_shell(config.shell.clone());
+    let shell = PtyHost::resolve_shell(&spawn_options)?;
+    info!("🐚 Shell: {}", shell);
+    
+    if config.renderer == RendererChoice::Gpu {
+        return Err("renderer gpu: this build has no GPU renderer; use auto or software".into());
+    }
+
     // Initialize PTY host (we'll create a mock for now)
     info!("🐚 Creating terminal components...");
     let pty_host = Arc::new(Mutex::new(MockPtyHost::new()));
@@ -35,7 +276,10 @@ async fn main() -> Result<()> {
     info!("✅ Shell components created");
 
     // Initialize text grid and parser
-    let text_grid = Arc::new(Mutex::new(TextGrid::new(terminal_rows, terminal_cols)));
+    let mut grid = TextGrid::new(terminal_rows, terminal_cols);
+    grid.set_scroll_on_output(config.scroll_on_output);
+    grid.set_alternate_scrollback(config.alternate_scrollback);
+    let text_grid = Arc::new(Mutex::new(grid));
     let parser = Arc::new(Mutex::new(TerminalParser::new(terminal_rows, terminal_cols)));
 
     // Add some test data to the grid
@@ -69,6 +313,8 @@ async fn main() -> Result<()> {
     // Initialize software renderer
     let size = window.inner_size();
     let mut software_renderer = SoftwareRenderer::new(size)?;
+    software_renderer.set_font_size_limits(config.font_size_limits())?;
+    software_renderer.set_fallback_fonts(&config.font_fallbacks)?;
 
     // Initialize softbuffer
     let context = Context::new(&window).unwrap();
@@ -77,6 +323,15 @@ async fn main() -> Result<()> {
     // Store window ID for redraw requests
     let window_id = window.id();
 
+    // Grid reflows are deferred until the window stops resizing
+    let mut pending_resize = Debouncer::new(RESIZE_DEBOUNCE);
+    
+    let mut cursor_blink_on = true;
+    let mut last_blink_toggle = Instant::now();
+    
+    let mut modifiers = ModifiersState::empty();
+    let keybindings = config.keybindings();
+
     info!("✅ Software renderer initialized");
     info!("🔄 Starting event loop - press Escape to quit");
 
@@ -86,6 +341,23 @@ async fn main() -> Result<()> {
 
         match event {
             Event::AboutToWait => {
+                if let Some(new_size) = pending_resize.poll(Instant::now()) {
+                    let geometry = software_renderer.geometry();
+                    let (rows, cols) = (geometry.rows as u16, geometry.cols as u16);
+                    info!("📐 Resizing grid to {}x{} for {:?}", cols, rows, new_size);
+                    match text_grid.try_lock() {
+                        Ok(mut grid) => grid.resize(rows, cols),
+                        // Retry on the next iteration
+                        Err(_) => pending_resize.push(new_size, Instant::now()),
+                    }
+                }
+                
+                if last_blink_toggle.elapsed() >= CURSOR_BLINK_INTERVAL {
+                    cursor_blink_on = !cursor_blink_on;
+                    last_blink_toggle = Instant::now();
+                    software_renderer.set_cursor_blink_phase(cursor_blink_on);
+                }
+                
                 // Render frame
                 if let Ok(text_grid_locked) = text_grid.try_lock() {
                     if let Ok(pixel_buffer) = software_renderer.render_frame(&*text_grid_locked) {
@@ -94,11 +366,8 @@ async fn main() -> Result<()> {
                             // Convert our RGBA buffer to the format softbuffer expects
                             for (i, &pixel) in pixel_buffer.iter().enumerate() {
                                 if i < buffer.len() {
-                                    // Convert from RGBA to RGB format that softbuffer expects
-                                    let r = ((pixel >> 16) & 0xFF) as u32;
-                                    let g = ((pixel >> 8) & 0xFF) as u32;
-                                    let b = (pixel & 0xFF) as u32;
-                                    buffer[i] = (r << 16) | (g << 8) | b;
+                                    // softbuffer expects 0RGB, so drop the alpha byte
+                                    buffer[i] = pixel & 0x00FF_FFFF;
                                 }
                             }
                             
@@ -134,11 +403,48 @@ async fn main() -> Result<()> {
                 },
                 ..
             } => {
-                match keycode {
-                    KeyCode::Escape => {
-                        info!("🚪 Escape pressed, exiting...");
+                match keybindings.resolve(keycode, modifiers) {
+                    Some(KeyAction::Quit) => {
+                        info!("🚪 Quit key pressed, exiting...");
                         elwt.exit();
+                        return;
                     }
+                    Some(action @ (KeyAction::ZoomIn | KeyAction::ZoomOut | KeyAction::ZoomReset)) => {
+                        let zoom = action.zoom().expect("zoom action");
+                        match software_renderer.set_font_size(zoom.apply(software_renderer.font_size())) {
+                            // The grid follows the new cell size like it does a window resize
+                            Ok(_) => pending_resize.push(software_renderer.size(), Instant::now()),
+                            Err(e) => warn!("Failed to change font size: {}", e),
+                        }
+                        return;
+                    }
+                    Some(action @ (KeyAction::ScrollUp | KeyAction::ScrollDown)) => {
+                        if let Ok(mut grid) = text_grid.try_lock() {
+                            let page = grid.rows as usize;
+                            if *action == KeyAction::ScrollUp {
+                                grid.scroll_viewport_up(page);
+                            } else {
+                                grid.scroll_viewport_down(page);
+                            }
+                        }
+                        return;
+                    }
+                    Some(KeyAction::SendBytes(bytes)) => {
+                        let pty_host = pty_host.clone();
+                        let bytes = bytes.clone();
+                        tokio::spawn(async move {
+                            pty_host.lock().await.write(&bytes);
+                        });
+                        return;
+                    }
+                    Some(action) => {
+                        info!("No clipboard in this window, ignoring {:?}", action);
+                        return;
+                    }
+                    None => {}
+                }
+                
+                match keycode {
                     KeyCode::Enter => {
                         // Add a response line
                         let grid = text_grid.clone();
@@ -158,6 +464,28 @@ async fn main() -> Result<()> {
                 }
             }
 
+            Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(new_modifiers),
+                ..
+            } => {
+                modifiers = new_modifiers.state();
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::Focused(focused),
+                ..
+            } => {
+                // Forward focus changes to applications that asked for them (DECSET 1004)
+                let parser = parser.clone();
+                let pty_host = pty_host.clone();
+                tokio::spawn(async move {
+                    let report = parser.lock().await.focus_report_bytes(focused);
+                    if let Some(bytes) = report {
+                        pty_host.lock().await.write(&bytes);
+                    }
+                });
+            }
+
             Event::WindowEvent {
                 event: WindowEvent::Resized(new_size),
                 ..
@@ -171,6 +499,7 @@ async fn main() -> Result<()> {
                 if let Err(e) = software_renderer.resize(new_size) {
                     warn!("Failed to resize software renderer: {}", e);
                 }
+                pending_resize.push(new_size, Instant::now());
             }
 
             _ => {}
@@ -187,4 +516,8 @@ impl MockPtyHost {
     fn new() -> Self {
         Self
     }
+    
+    fn write(&mut self, data: &[u8]) {
+        info!("Mock PTY write: {:?}", String::from_utf8_lossy(data));
+    }
 }
@@ -8,15 +8,18 @@
 
 use clap::Parser;
 use tokio::time::{sleep, Duration};
-use tracing::{info, error, warn};
+use tracing::{debug, info, error, warn};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 
 use winit::{
-    event::{Event, WindowEvent, KeyEvent, ElementState},
+    event::{Event, WindowEvent, KeyEvent, ElementState, MouseButton},
     event_loop::{EventLoop, ControlFlow},
-    window::WindowBuilder,
-    keyboard::{KeyCode, PhysicalKey},
+    window::{Window, WindowBuilder},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
 };
 
 // Use termind library components
@@ -24,7 +27,89 @@ use termind::{
     Result,
     TextGrid, TerminalParser,
     BlockDetector, PtyHost,
+    blocks::FinishedBlock,
+    config::{Config, ConfigOverrides},
+    pty::{Debouncer, IdleDetector, OutputPump, RawTap, SignalEvent, SpawnOptions, Utf8Reassembler, RESIZE_DEBOUNCE},
+    renderer::{ActiveRenderer, Bell, CellGeometry, FontSizeLimits, KeyAction, Keybindings, RenderError, Renderer, RendererChoice, TerminalEvent},
+    renderer::{system_clipboard, SelectionClipboard},
+    renderer::gpu::GpuRenderer,
+    renderer::software::SoftwareRenderer,
 };
+use std::time::Instant;
+
+/// Software renderer with the softbuffer surface its frames are copied to
+struct SoftwareSurface {
+    renderer: SoftwareRenderer,
+    surface: softbuffer::Surface<Rc<Window>, Rc<Window>>,
+}
+
+impl SoftwareSurface {
+    fn new(window: &Rc<Window>) -> std::result::Result<Self, RenderError> {
+        let renderer = SoftwareRenderer::new(window.inner_size())?;
+        let context = softbuffer::Context::new(window.clone())
+            .map_err(|e| RenderError::Window(format!("Failed to create softbuffer context: {}", e)))?;
+        let mut surface = softbuffer::Surface::new(&context, window.clone())
+            .map_err(|e| RenderError::Window(format!("Failed to create softbuffer surface: {}", e)))?;
+        let size = window.inner_size();
+        if let (Some(width), Some(height)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) {
+            surface.resize(width, height)
+                .map_err(|e| RenderError::Window(format!("Failed to size softbuffer surface: {}", e)))?;
+        }
+        Ok(Self { renderer, surface })
+    }
+}
+
+impl Renderer for SoftwareSurface {
+    fn render_frame(&mut self, grid: &TextGrid) -> std::result::Result<(), RenderError> {
+        let frame = SoftwareRenderer::render_frame(&mut self.renderer, grid)?;
+        let mut buffer = self.surface.buffer_mut()
+            .map_err(|e| RenderError::RenderFailed(format!("Failed to get surface buffer: {}", e)))?;
+        for (dst, &pixel) in buffer.iter_mut().zip(frame) {
+            // softbuffer expects 0RGB, so drop the alpha byte
+            *dst = pixel & 0x00FF_FFFF;
+        }
+        buffer.present()
+            .map_err(|e| RenderError::RenderFailed(format!("Failed to present buffer: {}", e)))
+    }
+    
+    fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) -> std::result::Result<(), RenderError> {
+        if let (Some(width), Some(height)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) {
+            self.surface.resize(width, height)
+                .map_err(|e| RenderError::Window(format!("Failed to resize softbuffer surface: {}", e)))?;
+        }
+        self.renderer.resize(size)
+    }
+    
+    fn cell_size(&self) -> (u32, u32) {
+        self.renderer.cell_size()
+    }
+    
+    fn geometry(&self) -> CellGeometry {
+        self.renderer.geometry()
+    }
+    
+    fn font_size(&self) -> f32 {
+        self.renderer.font_size()
+    }
+    
+    fn set_font_size(&mut self, font_size: f32) -> std::result::Result<f32, RenderError> {
+        self.renderer.set_font_size(font_size)
+    }
+    
+    fn set_font_size_limits(&mut self, limits: FontSizeLimits) -> std::result::Result<f32, RenderError> {
+        self.renderer.set_font_size_limits(limits)
+    }
+    
+    fn set_fallback_fonts(&mut self, paths: &[PathBuf]) -> std::result::Result<(), RenderError> {
+        self.renderer.set_fallback_fonts(paths)
+    }
+    
+    fn set_bell_flash(&mut self, intensity: f32) {
+        self.renderer.set_bell_flash(intensity)
+    }
+}
+
+type AppRenderer = ActiveRenderer<GpuRenderer, SoftwareSurface>;
 
 #[derive(Parser)]
 #[command(name = "termind", version = "0.3.0", author, about = "Privacy-first, AI-powered terminal")]
@@ -40,6 +125,31 @@ struct Cli {
     /// Terminal height (default: 24)
     #[arg(short = 't', long, default_value = "24")]
     height: u16,
+    
+    /// Smallest font size Ctrl-- may zoom out to [default: 8]
+    #[arg(long, value_name = "PX")]
+    min_font_size: Option<f32>,
+    
+    /// Largest font size Ctrl-+ may zoom in to [default: 32]
+    #[arg(long, value_name = "PX")]
+    max_font_size: Option<f32>,
+    
+    /// Quiet period after a command is submitted before it is assumed done
+    /// (used when the shell does not emit OSC 133 markers) [default: 1500]
+    #[arg(long, value_name = "MS")]
+    idle_timeout_ms: Option<u64>,
+    
+    /// Renderer backend: auto (GPU, falling back to software), gpu, or software [default: auto]
+    #[arg(long, value_name = "BACKEND")]
+    renderer: Option<RendererChoice>,
+    
+    /// Write logs to PATH (rotated daily) instead of stdout
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+    
+    /// On exit, write the most recent raw PTY output to PATH (for bug reports)
+    #[arg(long, value_name = "PATH")]
+    dump_pty_output: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -53,10 +163,7 @@ async fn main() -> Result<()> {
         tracing::Level::INFO
     };
     
-    tracing_subscriber::fmt()
-        .with_max_level(log_level)
-        .with_target(false)
-        .init();
+    let _log_guard = termind::logging::init(log_level, cli.log_file.as_deref())?;
 
     info!("🚀 Starting Termind v0.3.0 - Phase A Week 3");
     
@@ -78,17 +185,29 @@ async fn main() -> Result<()> {
 async fn run_terminal(cli: &Cli) -> Result<()> {
     info!("📋 Initializing Phase A components...");
     
+    // Flags given on the command line win over config.toml
+    let config = Config::load()?.merge(ConfigOverrides {
+        renderer: cli.renderer,
+        min_font_size: cli.min_font_size,
+        max_font_size: cli.max_font_size,
+        idle_timeout_ms: cli.idle_timeout_ms,
+        ..ConfigOverrides::default()
+    });
+    
     // Initialize core components
     let text_grid = TextGrid::new(cli.height, cli.width);
-    let parser = TerminalParser::new(cli.height, cli.width);
-    let _block_detector = BlockDetector::new().await?;
+    let mut parser = TerminalParser::new(cli.height, cli.width);
+    parser.grid_mut().set_scroll_on_output(config.scroll_on_output);
+    parser.grid_mut().set_alternate_scrollback(config.alternate_scrollback);
+    let block_detector = BlockDetector::new().await?;
     
     info!("🔧 Components initialized successfully");
     info!("📏 Terminal size: {}x{}", cli.width, cli.height);
     
     // Spawn the shell with PTY
     info!("🐚 Spawning shell...");
-    let mut pty_host = PtyHost::spawn_shell().await
+    let spawn_options = SpawnOptions::default().with_shell(config.shell.clone());
+    let mut pty_host = PtyHost::spawn_with_options(spawn_options).await
         .map_err(|e| termind::TermindError::Pty(format!("Failed to spawn shell: {}", e)))?;
     
     info!("✅ Shell spawned successfully: {}", pty_host.shell_path());
@@ -103,28 +222,44 @@ async fn run_terminal(cli: &Cli) -> Result<()> {
     let parser = Arc::new(Mutex::new(parser));
     let text_grid = Arc::new(Mutex::new(text_grid));
     
+    // Recent raw output, kept for --dump-pty-output
+    let raw_tap = Arc::new(Mutex::new(RawTap::default()));
+    
     // Start GUI window
     info!("🪟 Opening terminal window...");
-    run_gui_terminal(cli, pty_host, parser, text_grid).await
+    let result = run_gui_terminal(cli, &config, pty_host, parser, text_grid, block_detector, raw_tap.clone()).await;
+    
+    if let Some(path) = &cli.dump_pty_output {
+        let dump = raw_tap.lock().await.dump();
+        match std::fs::write(path, &dump) {
+            Ok(()) => info!("📝 Wrote {} bytes of raw PTY output to {}", dump.len(), path.display()),
+            Err(e) => warn!("⚠️ Failed to write raw PTY output to {}: {}", path.display(), e),
+        }
+    }
+    
+    result
 }
 
 async fn run_gui_terminal(
     cli: &Cli,
+    config: &Config,
     pty_host: Arc<Mutex<PtyHost>>,
     parser: Arc<Mutex<TerminalParser>>,
     text_grid: Arc<Mutex<TextGrid>>,
+    mut block_detector: BlockDetector,
+    raw_tap: Arc<Mutex<RawTap>>,
 ) -> Result<()> {
     let event_loop = EventLoop::new()
         .map_err(|e| termind::TermindError::Configuration(format!("Failed to create event loop: {}", e)))?;
     
-    let window = WindowBuilder::new()
+    let window = Rc::new(WindowBuilder::new()
         .with_title("Termind - Privacy-first AI Terminal")
         .with_inner_size(winit::dpi::LogicalSize::new(
             (cli.width as f64) * 7.8, // More accurate based on 13pt monospace font
             (cli.height as f64) * 16.0, // Based on 16pt line height
         ))
         .build(&event_loop)
-        .map_err(|e| termind::TermindError::Configuration(format!("Failed to create window: {}", e)))?;
+        .map_err(|e| termind::TermindError::Configuration(format!("Failed to create window: {}", e)))?);
     
     info!("✅ Terminal window opened successfully");
     info!("🔄 Starting GUI event loop - terminal is now interactive!");
@@ -135,13 +270,31 @@ async fn run_gui_terminal(
     let parser_reader = parser.clone();
     let text_grid_reader = text_grid.clone();
     
+    // Infers command completion from PTY silence; fed by both the reader and key input
+    let idle = Arc::new(Mutex::new(IdleDetector::new(config.idle_timeout())));
+    let idle_reader = idle.clone();
+    // Rung by the reader, drawn by the event loop
+    let bell = Arc::new(Mutex::new(Bell::new(config.bell)));
+    let bell_reader = bell.clone();
+    let output_budget = config.output_budget_bytes.max(1);
+    let shell_path = pty_host.lock().await.shell_path().to_string();
+    // Ctrl+C pressed in the window, for the block it interrupts
+    let (signal_tx, mut signal_rx) = mpsc::unbounded_channel();
+    
     // Spawn background task to continuously read from PTY
     let reader_handle = tokio::spawn(async move {
         let mut status_counter = 0;
+        let mut utf8 = Utf8Reassembler::new();
         loop {
+            while let Ok(event) = signal_rx.try_recv() {
+                block_detector.handle_signal(&event);
+            }
+            
             let data = {
+                // Gather everything already waiting, up to the budget, so a
+                // flood is parsed and copied to the grid once per burst
                 let mut pty = pty_host_reader.lock().await;
-                match pty.try_read().await {
+                match pty.read_all_available(output_budget).await {
                     Ok(data) => data,
                     Err(e) => {
                         error!("❌ Error reading from PTY: {}", e);
@@ -151,51 +304,83 @@ async fn run_gui_terminal(
             };
             
             if !data.is_empty() {
+                raw_tap.lock().await.push(&data);
+                idle_reader.lock().await.on_output(Instant::now());
+                
                 // Debug: Show what data we received from the PTY
-                let data_str = String::from_utf8_lossy(&data);
+                let data_str = utf8.push(&data);
+                block_detector.add_output(&data_str, false);
                 if !data_str.trim().is_empty() && data_str.len() < 100 {
-                    info!("📝 PTY data: {:?}", data_str);
+                    debug!("📝 PTY data: {:?}", data_str);
                 } else if !data.is_empty() {
-                    info!("📝 PTY data: {} bytes", data.len());
+                    debug!("📝 PTY data: {} bytes", data.len());
+                }
+                
+                // Parse in budget-sized chunks, letting key input through in between
+                let mut pump = OutputPump::new(&data, output_budget);
+                while let Some(chunk) = pump.next_chunk().await {
+                    parser_reader.lock().await.parse(chunk);
                 }
                 
-                // Parse the data and update grid
-                {
+                // Copy updated grid from parser to our shared grid
+                let events = {
                     let mut parser = parser_reader.lock().await;
-                    parser.parse(&data);
-                    
-                    // Copy updated grid from parser to our shared grid
                     let parser_grid = parser.grid();
                     let mut text_grid = text_grid_reader.lock().await;
                     
-                    let mut cells_copied = 0;
-                    // Update the shared grid with parser data
-                    for row in 0..parser_grid.rows.min(text_grid.rows) {
-                        if let Some(parser_row) = parser_grid.row(row) {
-                            for col in 0..parser_row.len().min(text_grid.cols as usize) {
-                                // Copy cell data from parser to display grid
-                                if let Some(parser_cell) = parser_grid.cell_at(row, col as u16) {
-                                    // Update the text grid with the parser's cell data
-                                    text_grid.set_cell(row, col as u16, parser_cell);
-                                    if parser_cell.ch != '\0' && parser_cell.ch != ' ' {
-                                        cells_copied += 1;
-                                    }
-                                }
+                    text_grid.copy_from(parser_grid);
+                    
+                    parser.drain_events()
+                };
+                
+                for event in events {
+                    match event {
+                        // Answer terminal queries (size reports etc.)
+                        TerminalEvent::Response(bytes) => {
+                            let mut pty = pty_host_reader.lock().await;
+                            if let Err(e) = pty.write(&bytes).await {
+                                warn!("⚠️ Failed to write query response to PTY: {}", e);
+                            }
+                        }
+                        // No OS notification or clipboard backend yet; surface them in the log
+                        TerminalEvent::Notify { title, body } => {
+                            info!("🔔 Notification: {} {}", title.unwrap_or_default(), body);
+                        }
+                        TerminalEvent::Clipboard { selection, text } => {
+                            info!("📋 Clipboard write to '{}': {} bytes", selection, text.len());
+                        }
+                        TerminalEvent::Bell => bell_reader.lock().await.ring(Instant::now()),
+                        TerminalEvent::TitleChanged(title) => info!("🪟 Title: {}", title),
+                        TerminalEvent::CwdChanged(path) => info!("📁 Directory: {}", path.display()),
+                        // Captured as a block as soon as the shell announces it
+                        event @ TerminalEvent::CommandStarted { .. } => {
+                            if block_detector.handle_terminal_event(&event, &shell_path) {
+                                debug!("🧱 Command started: {:?}", block_detector.current_block().map(|b| &b.command));
                             }
                         }
-                    }
-                    if cells_copied > 0 {
-                        info!("🔄 Copied {} non-empty cells to display grid", cells_copied);
                     }
                 }
                 
                 // Request a redraw to update the GUI with new content
                 // Note: We can't directly request redraw from this task since we don't have window access
                 // The GUI will continuously poll and redraw
+                
+                // Don't start on the next burst before other tasks get a turn
+                tokio::task::yield_now().await;
             } else {
                 // No data available, sleep a bit
                 sleep(Duration::from_millis(10)).await;
                 
+                if let Some(event) = idle_reader.lock().await.poll(Instant::now()) {
+                    info!("⏱️ Output quiet, command likely done");
+                    match block_detector.handle_idle(event).await {
+                        Ok(Some(FinishedBlock::Stored(block))) => info!("🧱 Stored block for `{}`", block.command),
+                        Ok(Some(FinishedBlock::Deferred(block))) => warn!("⚠️ Block for `{}` not stored yet, will retry", block.command),
+                        Ok(None) => {}
+                        Err(e) => warn!("⚠️ Failed to store block: {}", e),
+                    }
+                }
+                
                 // Periodic status updates
                 status_counter += 1;
                 if status_counter % 500 == 0 { // Every ~5 seconds
@@ -206,45 +391,119 @@ async fn run_gui_terminal(
         }
     });
     
-    // Initialize GPU renderer before entering synchronous event loop
-    let gpu_renderer = termind::renderer::gpu::GpuRenderer::new(&window).await
-        .map_err(|e| termind::TermindError::Configuration(format!("Failed to create GPU renderer: {}", e)))?;
+    // Initialize the renderer before entering the synchronous event loop;
+    // with --renderer auto, fall back to software rendering when no GPU is usable
+    let mut renderer = AppRenderer::select_with(config.renderer, || GpuRenderer::new(&window), || SoftwareSurface::new(&window))
+        .await
+        .map_err(|e| termind::TermindError::Configuration(format!("Failed to create renderer: {}", e)))?;
+    renderer.set_font_size_limits(config.font_size_limits())
+        .map_err(|e| termind::TermindError::Configuration(format!("Failed to set font size: {}", e)))?;
+    renderer.set_fallback_fonts(&config.font_fallbacks)
+        .map_err(|e| termind::TermindError::Configuration(format!("Failed to load fallback fonts: {}", e)))?;
+    
+    if renderer.is_gpu() {
+        info!("🎮 GPU renderer initialized successfully");
+    } else {
+        info!("🖥️  Software renderer initialized");
+    }
     
-    info!("🎮 GPU renderer initialized successfully");
+    let (cell_width, cell_height) = renderer.cell_size();
+    parser.lock().await.set_cell_pixel_size(cell_width, cell_height);
     
     // Run the GUI event loop (blocking, synchronous)
-    let result = run_event_loop(event_loop, window, pty_host, parser, text_grid, gpu_renderer);
+    let result = run_event_loop(event_loop, window, pty_host, parser, text_grid, idle, bell, signal_tx, renderer, config.copy_on_select, config.keybindings());
     
     info!("🧹 Terminal session ended");
     result
 }
 
+/// Write `bytes` to the shell from a background task, noting the input for idle detection
+fn send_to_pty(pty_host: &Arc<Mutex<PtyHost>>, idle: &Arc<Mutex<IdleDetector>>, bytes: Vec<u8>) {
+    let pty_host = pty_host.clone();
+    let idle = idle.clone();
+    tokio::task::spawn(async move {
+        idle.lock().await.on_input(&bytes, Instant::now());
+        let mut pty = pty_host.lock().await;
+        if let Err(e) = pty.write(&bytes).await {
+            warn!("⚠️ Failed to write to PTY: {}", e);
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
 fn run_event_loop(
     event_loop: EventLoop<()>,
-    window: winit::window::Window,
+    window: Rc<Window>,
     pty_host: Arc<Mutex<PtyHost>>,
     parser: Arc<Mutex<TerminalParser>>,
     text_grid: Arc<Mutex<TextGrid>>,
-    mut gpu_renderer: termind::renderer::gpu::GpuRenderer,
+    idle: Arc<Mutex<IdleDetector>>,
+    bell: Arc<Mutex<Bell>>,
+    signals: mpsc::UnboundedSender<SignalEvent>,
+    mut renderer: AppRenderer,
+    copy_on_select: bool,
+    keybindings: Keybindings,
 ) -> Result<()> {
     
     // Store window ID for comparison in event loop
     let window_id = window.id();
     
+    // PTY and parser resizes are deferred until the window stops resizing
+    let mut pending_resize = Debouncer::new(RESIZE_DEBOUNCE);
+    
+    let mut modifiers = ModifiersState::empty();
+    
+    // Mouse selection: the cell under the pointer, and where the left button went down
+    let mut selection_clipboard = match system_clipboard() {
+        Ok(clipboard) => Some(SelectionClipboard::new(clipboard).with_copy_on_select(copy_on_select)),
+        Err(e) => {
+            warn!("📋 Clipboard unavailable, selections won't be copied: {}", e);
+            None
+        }
+    };
+    let mut pointer_cell = None;
+    let mut drag_origin = None;
+    
     event_loop.run(move |event, elwt| {
         elwt.set_control_flow(ControlFlow::Poll);
         
         match event {
             Event::AboutToWait => {
-                // Render the terminal using GPU renderer
+                if pending_resize.poll(Instant::now()).is_some() {
+                    let geometry = renderer.geometry();
+                    let (rows, cols) = (geometry.rows as u16, geometry.cols as u16);
+                    let pty_host = pty_host.clone();
+                    let parser = parser.clone();
+                    let text_grid = text_grid.clone();
+                    tokio::task::spawn(async move {
+                        if let Err(e) = pty_host.lock().await.resize(rows, cols) {
+                            warn!("⚠️ Failed to resize PTY: {}", e);
+                        }
+                        let mut parser = parser.lock().await;
+                        parser.resize(rows, cols);
+                        // Zooming changes the cell size
+                        parser.set_cell_pixel_size(geometry.cell_width, geometry.cell_height);
+                        
+                        // The displayed grid follows, so the new rows and columns are drawn
+                        let mut text_grid = text_grid.lock().await;
+                        text_grid.resize(rows, cols);
+                        text_grid.copy_from(parser.grid());
+                    });
+                }
+                
+                if let Ok(bell) = bell.try_lock() {
+                    renderer.set_bell_flash(bell.flash_intensity(Instant::now()));
+                }
+                
+                // Render the terminal with whichever renderer is active
                 if let Ok(text_grid_locked) = text_grid.try_lock() {
-                    if let Err(e) = gpu_renderer.render_frame(&*text_grid_locked) {
+                    if let Err(e) = renderer.render_frame(&text_grid_locked) {
                         warn!("Failed to render terminal: {}", e);
                     }
                 } else {
                     // If we can't lock the text grid, create a simple grid
                     let simple_grid = TextGrid::new(24, 80);
-                    if let Err(e) = gpu_renderer.render_frame(&simple_grid) {
+                    if let Err(e) = renderer.render_frame(&simple_grid) {
                         warn!("Failed to render terminal: {}", e);
                     }
                 }
@@ -274,35 +533,120 @@ fn run_event_loop(
                     ..
                 },
             } if event_window_id == window_id => {
-                // Handle keyboard input
-                match keycode {
-                    KeyCode::Escape => {
-                        info!("🚪 Escape pressed, exiting...");
+                // Bound keys are handled here; everything else goes to the PTY
+                let Some(action) = keybindings.resolve(keycode, modifiers) else {
+                    // The shell gets ^C like any key; the running block is marked interrupted
+                    if keycode == KeyCode::KeyC && modifiers == ModifiersState::CONTROL {
+                        let _ = signals.send(SignalEvent::Interrupt);
+                    }
+                    if let Some(text) = text {
+                        send_to_pty(&pty_host, &idle, text.as_bytes().to_vec());
+                    }
+                    return;
+                };
+                
+                match action {
+                    KeyAction::Quit => {
+                        info!("🚪 Quit key pressed, exiting...");
                         elwt.exit();
                     }
-                    KeyCode::Enter => {
-                        // Send carriage return + line feed
-                        let pty_host = pty_host.clone();
-                        tokio::task::spawn(async move {
-                            let mut pty = pty_host.lock().await;
-                            if let Err(e) = pty.write(b"\r").await {
-                                warn!("⚠️ Failed to write to PTY: {}", e);
+                    KeyAction::Copy => {
+                        if let (Some(bridge), Ok(grid)) = (selection_clipboard.as_mut(), text_grid.try_lock()) {
+                            if let Err(e) = bridge.copy(&grid) {
+                                warn!("⚠️ Failed to copy selection: {}", e);
+                            }
+                        }
+                    }
+                    KeyAction::Paste => {
+                        match selection_clipboard.as_mut().map(SelectionClipboard::paste) {
+                            Some(Ok(text)) => send_to_pty(&pty_host, &idle, text.into_bytes()),
+                            Some(Err(e)) => warn!("⚠️ Failed to paste: {}", e),
+                            None => {}
+                        }
+                    }
+                    KeyAction::ZoomIn | KeyAction::ZoomOut | KeyAction::ZoomReset => {
+                        let zoom = action.zoom().expect("zoom action");
+                        match renderer.set_font_size(zoom.apply(renderer.font_size())) {
+                            // The PTY and grid follow the new cell size like they do a window resize
+                            Ok(_) => pending_resize.push(window.inner_size(), Instant::now()),
+                            Err(e) => warn!("Failed to change font size: {}", e),
+                        }
+                    }
+                    KeyAction::ScrollUp | KeyAction::ScrollDown => {
+                        if let Ok(mut grid) = text_grid.try_lock() {
+                            let page = grid.rows as usize;
+                            if *action == KeyAction::ScrollUp {
+                                grid.scroll_viewport_up(page);
+                            } else {
+                                grid.scroll_viewport_down(page);
                             }
-                        });
-                        elwt.set_control_flow(ControlFlow::Poll);
+                        }
                     }
-                    _ => {
-                        // Forward other keys to the PTY
-                        if let Some(text) = text {
-                            let pty_host = pty_host.clone();
-                            let text = text.to_string();
-                            tokio::task::spawn(async move {
-                                let mut pty = pty_host.lock().await;
-                                if let Err(e) = pty.write(text.as_bytes()).await {
-                                    warn!("⚠️ Failed to write to PTY: {}", e);
-                                }
-                            });
-                            elwt.set_control_flow(ControlFlow::Poll);
+                    KeyAction::SendBytes(bytes) => send_to_pty(&pty_host, &idle, bytes.clone()),
+                    KeyAction::Passthrough => {}
+                }
+            }
+            
+            Event::WindowEvent {
+                window_id: event_window_id,
+                event: WindowEvent::ModifiersChanged(new_modifiers),
+            } if event_window_id == window_id => {
+                modifiers = new_modifiers.state();
+            }
+            
+            Event::WindowEvent {
+                window_id: event_window_id,
+                event: WindowEvent::Focused(focused),
+            } if event_window_id == window_id => {
+                // Forward focus changes to applications that asked for them (DECSET 1004)
+                let parser = parser.clone();
+                let pty_host = pty_host.clone();
+                tokio::task::spawn(async move {
+                    let report = parser.lock().await.focus_report_bytes(focused);
+                    if let Some(bytes) = report {
+                        if let Err(e) = pty_host.lock().await.write(&bytes).await {
+                            warn!("⚠️ Failed to report focus to PTY: {}", e);
+                        }
+                    }
+                });
+            }
+            
+            Event::WindowEvent {
+                window_id: event_window_id,
+                event: WindowEvent::CursorMoved { position, .. },
+            } if event_window_id == window_id => {
+                pointer_cell = renderer.geometry().cell_at_pixel(position.x as u32, position.y as u32);
+                if let (Some(origin), Some((row, col))) = (drag_origin, pointer_cell) {
+                    if let Ok(mut grid) = text_grid.try_lock() {
+                        // A click without moving off the cell selects nothing
+                        if grid.selection().is_none() && (row, col) != origin {
+                            grid.start_selection(origin.0, origin.1);
+                        }
+                        grid.extend_selection(row, col);
+                    }
+                }
+            }
+            
+            Event::WindowEvent {
+                window_id: event_window_id,
+                event: WindowEvent::MouseInput { state, button: MouseButton::Left, .. },
+            } if event_window_id == window_id => {
+                let Ok(mut grid) = text_grid.try_lock() else {
+                    return;
+                };
+                match state {
+                    ElementState::Pressed => {
+                        grid.clear_selection();
+                        drag_origin = pointer_cell;
+                    }
+                    ElementState::Released => {
+                        drag_origin = None;
+                        if let Some(bridge) = selection_clipboard.as_mut() {
+                            match bridge.on_mouse_up(&grid) {
+                                Ok(true) => debug!("📋 Copied selection"),
+                                Ok(false) => {}
+                                Err(e) => warn!("⚠️ Failed to copy selection: {}", e),
+                            }
                         }
                     }
                 }
@@ -314,10 +658,10 @@ fn run_event_loop(
             } if event_window_id == window_id => {
                 info!("📏 Window resized to {:?}", size);
                 
-                // Resize the GPU renderer
-                if let Err(e) = gpu_renderer.resize(size) {
-                    warn!("Failed to resize GPU renderer: {}", e);
+                if let Err(e) = renderer.resize(size) {
+                    warn!("Failed to resize renderer: {}", e);
                 }
+                pending_resize.push(size, Instant::now());
                 
                 elwt.set_control_flow(ControlFlow::Poll);
             }
@@ -326,9 +670,8 @@ fn run_event_loop(
                 window_id: event_window_id,
                 event: WindowEvent::RedrawRequested,
             } if event_window_id == window_id => {
-                // Render using GPU
                 if let Ok(text_grid_locked) = text_grid.try_lock() {
-                    if let Err(e) = gpu_renderer.render_frame(&*text_grid_locked) {
+                    if let Err(e) = renderer.render_frame(&text_grid_locked) {
                         warn!("Failed to render terminal: {}", e);
                     }
                 }
@@ -342,7 +685,7 @@ fn run_event_loop(
     Ok(())
 }
 
-// All rendering is now handled by the GPU renderer
+// Rendering goes through the GPU renderer, or the software one as a fallback
 
 
 
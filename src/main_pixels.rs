@@ -11,7 +11,7 @@ use winit::{
 };
 use pixels::{Pixels, SurfaceTexture};
 
-use termind::pty::PtyHost;
+use termind::pty::{PtyHost, Utf8Reassembler};
 use termind::renderer::{TextGrid, TerminalParser};
 use termind::renderer::software::SoftwareRenderer;
 use termind::storage::BlockDetector;
@@ -82,6 +82,7 @@ async fn main() -> Result<()> {
     // Spawn background task to continuously read from PTY
     let _reader_handle = tokio::spawn(async move {
         let mut status_counter = 0;
+        let mut utf8 = Utf8Reassembler::new();
         loop {
             let data = {
                 let mut pty = pty_host_reader.lock().await;
@@ -96,7 +97,7 @@ async fn main() -> Result<()> {
 
             if !data.is_empty() {
                 // Debug: Show what data we received from the PTY
-                let data_str = String::from_utf8_lossy(&data);
+                let data_str = utf8.push(&data);
                 if !data_str.trim().is_empty() && data_str.len() < 100 {
                     info!("📝 PTY data: {:?}", data_str);
                 } else if !data.is_empty() {
@@ -112,25 +113,7 @@ async fn main() -> Result<()> {
                     let parser_grid = parser.grid();
                     let mut text_grid = text_grid_reader.lock().await;
 
-                    let mut cells_copied = 0;
-                    // Update the shared grid with parser data
-                    for row in 0..parser_grid.rows.min(text_grid.rows) {
-                        if let Some(parser_row) = parser_grid.row(row) {
-                            for col in 0..parser_row.len().min(text_grid.cols as usize) {
-                                // Copy cell data from parser to display grid
-                                if let Some(parser_cell) = parser_grid.cell_at(row, col as u16) {
-                                    // Update the text grid with the parser's cell data
-                                    text_grid.set_cell(row, col as u16, parser_cell);
-                                    if parser_cell.ch != '\0' && parser_cell.ch != ' ' {
-                                        cells_copied += 1;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    if cells_copied > 0 {
-                        info!("🔄 Copied {} non-empty cells to display grid", cells_copied);
-                    }
+                    text_grid.copy_from(parser_grid);
                 }
             } else {
                 // No data available, sleep a bit
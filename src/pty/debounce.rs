@@ -0,0 +1,73 @@
+// Coalescing of bursty requests, e.g. window resizes during a drag
+// Forwarding every `Resized` event would spam SIGWINCH and reflow the grid
+// hundreds of times per second; only the last size after a quiet period matters.
+
+use std::time::{Duration, Instant};
+
+/// Quiet period after the last resize before it is applied
+pub const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Keeps the latest pushed value and releases it once no new value has
+/// arrived for the quiet period
+#[derive(Debug)]
+pub struct Debouncer<T> {
+    quiet_period: Duration,
+    pending: Option<(T, Instant)>,
+}
+
+impl<T> Debouncer<T> {
+    pub fn new(quiet_period: Duration) -> Self {
+        Self {
+            quiet_period,
+            pending: None,
+        }
+    }
+
+    /// Record a new value at `now`, replacing any pending one
+    pub fn push(&mut self, value: T, now: Instant) {
+        self.pending = Some((value, now));
+    }
+
+    /// Take the pending value if the quiet period has passed since it was pushed
+    pub fn poll(&mut self, now: Instant) -> Option<T> {
+        match &self.pending {
+            Some((_, pushed_at)) if now.duration_since(*pushed_at) >= self.quiet_period => {
+                self.pending.take().map(|(value, _)| value)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_applies_only_last_value() {
+        let mut debouncer = Debouncer::new(RESIZE_DEBOUNCE);
+        let start = Instant::now();
+
+        // A drag delivering a new size every 5ms
+        let mut applied = Vec::new();
+        for step in 0..20u32 {
+            let now = start + Duration::from_millis(step as u64 * 5);
+            debouncer.push((80 + step, 24), now);
+            applied.extend(debouncer.poll(now));
+        }
+        assert!(applied.is_empty());
+        assert!(debouncer.is_pending());
+
+        let last_push = start + Duration::from_millis(95);
+        assert_eq!(debouncer.poll(last_push + Duration::from_millis(49)), None);
+        assert_eq!(debouncer.poll(last_push + RESIZE_DEBOUNCE), Some((99, 24)));
+
+        // Nothing left once applied
+        assert_eq!(debouncer.poll(last_push + Duration::from_secs(1)), None);
+        assert!(!debouncer.is_pending());
+    }
+}
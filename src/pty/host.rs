@@ -1,6 +1,7 @@
 use std::env;
 use std::ffi::CString;
 use std::io;
+use std::os::unix::fs::PermissionsExt;
 use std::os::unix::io::{AsRawFd, FromRawFd};
 use std::path::Path;
 
@@ -30,18 +31,83 @@ pub enum PtyError {
     EnvironmentSetup,
 }
 
+/// Default size of the buffer filled by each PTY read
+pub const DEFAULT_READ_BUFFER_SIZE: usize = 4096;
+
+/// Options controlling the environment of a spawned shell
+#[derive(Debug, Clone)]
+pub struct SpawnOptions {
+    /// Value for `TERM`; `None` keeps the inherited value
+    pub term: Option<String>,
+    /// Value for `COLORTERM`; `truecolor` advertises 24-bit color support
+    pub colorterm: Option<String>,
+    /// Shell to run instead of the detected one
+    pub shell: Option<String>,
+    /// Size of the buffer each PTY read fills
+    pub read_buffer_size: usize,
+}
+
+impl Default for SpawnOptions {
+    fn default() -> Self {
+        Self {
+            term: Some("xterm-256color".to_string()),
+            colorterm: Some("truecolor".to_string()),
+            shell: None,
+            read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+        }
+    }
+}
+
+impl SpawnOptions {
+    pub fn with_term(mut self, term: Option<String>) -> Self {
+        self.term = term;
+        self
+    }
+    
+    pub fn with_colorterm(mut self, colorterm: Option<String>) -> Self {
+        self.colorterm = colorterm;
+        self
+    }
+    
+    pub fn with_shell(mut self, shell: Option<String>) -> Self {
+        self.shell = shell;
+        self
+    }
+    
+    pub fn with_read_buffer_size(mut self, size: usize) -> Self {
+        self.read_buffer_size = size.max(1);
+        self
+    }
+}
+
 pub struct PtyHost {
     master: PtyMaster,
     child_pid: Pid,
     reader: tokio::fs::File,
     writer: tokio::fs::File,
     shell_path: String,
+    // Reused across reads instead of allocating per call
+    read_buffer: Vec<u8>,
+    // Read from the PTY by `fill` but not consumed yet; reads return these first
+    pending: Vec<u8>,
 }
 
 impl PtyHost {
-    /// Spawn a new shell process with PTY
+    /// Spawn a new shell process with a PTY sized to `rows` x `cols`
+    pub async fn new(rows: u16, cols: u16) -> Result<Self, PtyError> {
+        let mut host = Self::spawn_shell().await?;
+        host.resize(rows, cols)?;
+        Ok(host)
+    }
+    
+    /// Spawn a new shell process with PTY, leaving the kernel's default size
     pub async fn spawn_shell() -> Result<Self, PtyError> {
-        let shell_path = Self::detect_shell()?;
+        Self::spawn_with_options(SpawnOptions::default()).await
+    }
+    
+    /// Spawn a new shell process with PTY using the given environment options
+    pub async fn spawn_with_options(options: SpawnOptions) -> Result<Self, PtyError> {
+        let shell_path = Self::resolve_shell(&options)?;
         info!("Spawning shell: {}", shell_path);
         
         // Create PTY master/slave pair
@@ -56,11 +122,11 @@ impl PtyHost {
         match unsafe { unistd::fork()? } {
             ForkResult::Parent { child } => {
                 info!("Forked child process: {}", child);
-                Self::setup_parent(master, child, shell_path).await
+                Self::setup_parent(master, child, shell_path, options.read_buffer_size).await
             }
             ForkResult::Child => {
                 // This code runs in the child process
-                Self::setup_child(&slave_name, &shell_path).await
+                Self::setup_child(&slave_name, &shell_path, &options).await
             }
         }
     }
@@ -69,7 +135,8 @@ impl PtyHost {
     async fn setup_parent(
         master: PtyMaster, 
         child_pid: Pid, 
-        shell_path: String
+        shell_path: String,
+        read_buffer_size: usize,
     ) -> Result<Self, PtyError> {
         let master_fd = master.as_raw_fd();
         
@@ -89,11 +156,13 @@ impl PtyHost {
             reader,
             writer,
             shell_path,
+            read_buffer: vec![0u8; read_buffer_size],
+            pending: Vec::new(),
         })
     }
     
     /// Setup child process to run the shell
-    async fn setup_child(slave_name: &str, shell_path: &str) -> Result<Self, PtyError> {
+    async fn setup_child(slave_name: &str, shell_path: &str, options: &SpawnOptions) -> Result<Self, PtyError> {
         // This function never returns in the child process
         // It either execs successfully or exits with error
         
@@ -138,7 +207,12 @@ impl PtyHost {
         }
         
         // Setup environment
-        env::set_var("TERM", "xterm-256color");
+        if let Some(term) = &options.term {
+            env::set_var("TERM", term);
+        }
+        if let Some(colorterm) = &options.colorterm {
+            env::set_var("COLORTERM", colorterm);
+        }
         if let Some(home) = dirs::home_dir() {
             env::set_var("HOME", home);
         }
@@ -161,6 +235,23 @@ impl PtyHost {
         }
     }
     
+    /// Shell to spawn for `options`: the override if set, otherwise the detected shell.
+    /// An override must be an existing executable file.
+    pub fn resolve_shell(options: &SpawnOptions) -> Result<String, PtyError> {
+        let Some(shell) = &options.shell else {
+            return Self::detect_shell();
+        };
+        
+        let is_executable = std::fs::metadata(shell)
+            .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false);
+        if !is_executable {
+            return Err(PtyError::ShellNotFound(shell.clone()));
+        }
+        
+        Ok(shell.clone())
+    }
+    
     /// Detect the user's preferred shell
     fn detect_shell() -> Result<String, PtyError> {
         // Try SHELL environment variable first
@@ -217,36 +308,65 @@ impl PtyHost {
         Ok(())
     }
     
-    /// Read data from PTY (non-blocking)
+    /// Read whatever output is available (non-blocking) into the buffer
+    /// `peek` shows, without consuming it. Returns how many bytes were added.
+    pub async fn fill(&mut self) -> Result<usize, PtyError> {
+        let pending = std::mem::take(&mut self.pending);
+        let chunk = self.try_read().await;
+        self.pending = pending;
+        let chunk = chunk?;
+        self.pending.extend_from_slice(&chunk);
+        Ok(chunk.len())
+    }
+    
+    /// Output read by `fill` and not yet consumed
+    pub fn peek(&self) -> &[u8] {
+        &self.pending
+    }
+    
+    /// Drop the first `n` peeked bytes (all of them if fewer are buffered)
+    pub fn consume(&mut self, n: usize) {
+        self.pending.drain(..n.min(self.pending.len()));
+    }
+    
+    /// Read data from PTY (non-blocking); bytes left by `fill` come first
     pub async fn try_read(&mut self) -> Result<Vec<u8>, PtyError> {
-        let mut buffer = vec![0u8; 4096];
-        
+        if !self.pending.is_empty() {
+            return Ok(std::mem::take(&mut self.pending));
+        }
         // Use a timeout for non-blocking behavior
-        match tokio::time::timeout(std::time::Duration::from_millis(1), self.reader.read(&mut buffer)).await {
-            Ok(Ok(0)) => Ok(Vec::new()),
-            Ok(Ok(n)) => {
-                buffer.truncate(n);
-                Ok(buffer)
-            }
+        match tokio::time::timeout(std::time::Duration::from_millis(1), self.reader.read(&mut self.read_buffer)).await {
+            Ok(Ok(n)) => Ok(self.read_buffer[..n].to_vec()),
             Ok(Err(e)) => Err(PtyError::Io(e)),
             Err(_) => Ok(Vec::new()), // Timeout = no data available
         }
     }
     
-    /// Read data from PTY (blocking)
+    /// Read data from PTY (blocking); bytes left by `fill` come first
     pub async fn read(&mut self) -> Result<Vec<u8>, PtyError> {
-        let mut buffer = vec![0u8; 4096];
-        
-        match self.reader.read(&mut buffer).await {
-            Ok(0) => Ok(Vec::new()), // EOF
-            Ok(n) => {
-                buffer.truncate(n);
-                Ok(buffer)
-            }
+        if !self.pending.is_empty() {
+            return Ok(std::mem::take(&mut self.pending));
+        }
+        match self.reader.read(&mut self.read_buffer).await {
+            Ok(n) => Ok(self.read_buffer[..n].to_vec()), // Empty on EOF
             Err(e) => Err(PtyError::Io(e)),
         }
     }
     
+    /// Drain everything currently available (non-blocking), up to roughly
+    /// `max_bytes`, so bursts of output reach the parser in one chunk
+    pub async fn read_all_available(&mut self, max_bytes: usize) -> Result<Vec<u8>, PtyError> {
+        let mut output = Vec::new();
+        while output.len() < max_bytes {
+            let chunk = self.try_read().await?;
+            if chunk.is_empty() {
+                break;
+            }
+            output.extend_from_slice(&chunk);
+        }
+        Ok(output)
+    }
+    
     /// Write data to PTY
     pub async fn write(&mut self, data: &[u8]) -> Result<(), PtyError> {
         self.writer.write_all(data).await?;
@@ -254,6 +374,21 @@ impl PtyHost {
         Ok(())
     }
     
+    /// Send Ctrl-D: end of file for a program reading a line from the terminal
+    pub async fn send_eof(&mut self) -> Result<(), PtyError> {
+        self.write(b"\x04").await
+    }
+    
+    /// Send Ctrl-C; the line discipline turns it into SIGINT for the foreground job
+    pub async fn send_interrupt(&mut self) -> Result<(), PtyError> {
+        self.write(b"\x03").await
+    }
+    
+    /// Send Ctrl-Z; the line discipline turns it into SIGTSTP for the foreground job
+    pub async fn send_suspend(&mut self) -> Result<(), PtyError> {
+        self.write(b"\x1a").await
+    }
+    
     /// Get child process ID
     pub fn child_pid(&self) -> Pid {
         self.child_pid
@@ -287,9 +422,169 @@ mod tests {
         assert!(Path::new(&shell).exists());
     }
     
+    #[test]
+    fn test_shell_override() {
+        let options = SpawnOptions::default().with_shell(Some("/bin/sh".to_string()));
+        assert_eq!(PtyHost::resolve_shell(&options).unwrap(), "/bin/sh");
+    }
+    
+    #[test]
+    fn test_shell_override_missing() {
+        let options = SpawnOptions::default().with_shell(Some("/no/such/shell".to_string()));
+        let result = PtyHost::resolve_shell(&options);
+        assert!(matches!(result, Err(PtyError::ShellNotFound(path)) if path == "/no/such/shell"));
+    }
+    
+    #[test]
+    fn test_shell_fallback_detection() {
+        let shell = PtyHost::resolve_shell(&SpawnOptions::default()).unwrap();
+        assert_eq!(shell, PtyHost::detect_shell().unwrap());
+    }
+    
     #[tokio::test]
     async fn test_pty_spawn() {
         let pty = PtyHost::spawn_shell().await;
         assert!(pty.is_ok());
     }
+    
+    #[tokio::test]
+    async fn test_new_with_size() {
+        let mut pty = PtyHost::new(24, 80).await.unwrap();
+        assert!(pty.resize(40, 120).is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_read_all_available_coalesces_bursts() {
+        let mut pty = PtyHost::spawn_shell().await.unwrap();
+        pty.write(b"seq 1 20000; echo seq-$((1+1))-done\n").await.unwrap();
+        
+        let mut output = Vec::new();
+        let mut chunks = Vec::new();
+        for _ in 0..1000 {
+            let chunk = pty.read_all_available(256 * 1024).await.unwrap();
+            if !chunk.is_empty() {
+                chunks.push(chunk.len());
+                output.extend_from_slice(&chunk);
+            }
+            if String::from_utf8_lossy(&output).contains("seq-2-done") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        
+        assert!(String::from_utf8_lossy(&output).contains("seq-2-done"));
+        // seq 1 20000 alone prints ~108KB
+        assert!(output.len() > 100_000);
+        assert!(chunks.len() < output.len() / DEFAULT_READ_BUFFER_SIZE, "chunks: {:?}", chunks);
+        assert!(chunks.iter().any(|&len| len > DEFAULT_READ_BUFFER_SIZE));
+        
+        // Let the shell exit so no read is left pending on the PTY
+        pty.write(b"exit\n").await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_peek_then_consume() {
+        let options = SpawnOptions::default().with_shell(Some("/bin/cat".to_string()));
+        let mut pty = PtyHost::spawn_with_options(options).await.unwrap();
+        pty.write(b"hello peek\n").await.unwrap();
+        
+        // The terminal's echo of the line, then cat's copy of it
+        let expected = b"hello peek\r\nhello peek\r\n";
+        for _ in 0..500 {
+            pty.fill().await.unwrap();
+            if pty.peek().len() >= expected.len() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        // The output stays buffered however often it is peeked
+        assert_eq!(pty.peek(), expected);
+        assert_eq!(pty.peek(), expected);
+        
+        pty.consume(18);
+        assert_eq!(pty.peek(), b"peek\r\n");
+        // A read takes what is left before anything new
+        assert_eq!(pty.try_read().await.unwrap(), b"peek\r\n");
+        assert!(pty.peek().is_empty());
+        pty.consume(10);
+        assert!(pty.peek().is_empty());
+        
+        pty.send_eof().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_send_eof_ends_cat() {
+        use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+        
+        let options = SpawnOptions::default().with_shell(Some("/bin/cat".to_string()));
+        let mut pty = PtyHost::spawn_with_options(options).await.unwrap();
+        pty.write(b"hello\n").await.unwrap();
+        pty.send_eof().await.unwrap();
+        
+        let mut status = WaitStatus::StillAlive;
+        for _ in 0..500 {
+            // Keep the PTY drained so cat never blocks writing its echo
+            pty.try_read().await.unwrap_or_default();
+            status = waitpid(pty.child_pid(), Some(WaitPidFlag::WNOHANG)).unwrap();
+            if status != WaitStatus::StillAlive {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(status, WaitStatus::Exited(pty.child_pid(), 0));
+    }
+    
+    #[tokio::test]
+    async fn test_focus_reports_follow_application_mode() {
+        use crate::renderer::TerminalParser;
+        
+        let options = SpawnOptions::default().with_shell(Some("/bin/cat".to_string()));
+        let mut pty = PtyHost::spawn_with_options(options).await.unwrap();
+        let mut parser = TerminalParser::new(24, 80);
+        // Nothing is sent until the application asks for focus events
+        assert_eq!(parser.focus_report_bytes(true), None);
+        
+        // cat plays the application: its copy of the line turns focus reporting on
+        pty.write(b"\x1b[?1004h\n").await.unwrap();
+        for _ in 0..500 {
+            parser.parse(&pty.try_read().await.unwrap());
+            if parser.modes().focus_reporting {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        
+        let report = parser.focus_report_bytes(true).expect("focus reporting enabled");
+        pty.write(&report).await.unwrap();
+        pty.write(b"\n").await.unwrap();
+        let mut output = Vec::new();
+        for _ in 0..500 {
+            output.extend_from_slice(&pty.try_read().await.unwrap());
+            if output.windows(3).any(|window| window == b"\x1b[I") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        // The application read the focus-in report
+        assert!(output.windows(3).any(|window| window == b"\x1b[I"), "unexpected output: {:?}", output);
+        
+        pty.send_eof().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_colorterm_propagated() {
+        let mut pty = PtyHost::spawn_shell().await.unwrap();
+        pty.write(b"echo \"ct=$COLORTERM\"\n").await.unwrap();
+        
+        // The echoed input shows "$COLORTERM"; only the expansion contains the value
+        let mut output = String::new();
+        for _ in 0..500 {
+            output.push_str(&String::from_utf8_lossy(&pty.try_read().await.unwrap()));
+            if output.contains("ct=truecolor") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(output.contains("ct=truecolor"), "unexpected output: {:?}", output);
+    }
 }
@@ -0,0 +1,113 @@
+// Command completion inferred from PTY silence, for shells without OSC 133 markers
+
+use std::time::{Duration, Instant};
+
+/// How long output must stay quiet after a submitted command before it is
+/// considered done
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleEvent {
+    /// Output has been quiet for the idle timeout since the last command was submitted
+    CommandLikelyDone,
+}
+
+/// Watches PTY traffic: input ending a line arms it, each output chunk
+/// restarts the countdown, and `poll` fires once when the countdown runs out
+#[derive(Debug)]
+pub struct IdleDetector {
+    timeout: Duration,
+    // Last input or output while a command may be running
+    last_activity: Option<Instant>,
+}
+
+impl IdleDetector {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_activity: None,
+        }
+    }
+    
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    
+    /// Record bytes written to the PTY. Only a line ending (the user
+    /// submitting a command) arms the detector; keystrokes while typing don't.
+    pub fn on_input(&mut self, data: &[u8], now: Instant) {
+        if data.iter().any(|&b| b == b'\r' || b == b'\n') {
+            self.last_activity = Some(now);
+        }
+    }
+    
+    /// Record output read from the PTY, cancelling a countdown in progress
+    pub fn on_output(&mut self, now: Instant) {
+        if let Some(last) = self.last_activity.as_mut() {
+            *last = now;
+        }
+    }
+    
+    /// `CommandLikelyDone` once per submitted command, when output has been
+    /// quiet for the timeout
+    pub fn poll(&mut self, now: Instant) -> Option<IdleEvent> {
+        let last = self.last_activity?;
+        if now.duration_since(last) < self.timeout {
+            return None;
+        }
+        self.last_activity = None;
+        Some(IdleEvent::CommandLikelyDone)
+    }
+    
+    /// Whether a submitted command is still being watched
+    pub fn is_armed(&self) -> bool {
+        self.last_activity.is_some()
+    }
+}
+
+impl Default for IdleDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDLE_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_fires_once_after_output_goes_quiet() {
+        let mut detector = IdleDetector::new(Duration::from_millis(500));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        
+        detector.on_input(b"make\r", at(0));
+        // Bursts of output keep pushing the deadline back
+        for ms in [100, 300, 700, 1100] {
+            detector.on_output(at(ms));
+            assert_eq!(detector.poll(at(ms + 400)), None);
+        }
+        
+        assert_eq!(detector.poll(at(1600)), Some(IdleEvent::CommandLikelyDone));
+        assert_eq!(detector.poll(at(5000)), None);
+        assert!(!detector.is_armed());
+        
+        // Output with no command running (e.g. a background job) is ignored
+        detector.on_output(at(6000));
+        assert_eq!(detector.poll(at(9000)), None);
+    }
+    
+    #[test]
+    fn test_only_submitted_commands_arm() {
+        let mut detector = IdleDetector::default();
+        let start = Instant::now();
+        
+        // Typing without pressing Enter
+        detector.on_input(b"git sta", start);
+        assert_eq!(detector.poll(start + DEFAULT_IDLE_TIMEOUT * 2), None);
+        
+        // A command with no output at all still completes
+        detector.on_input(b"tus\r", start);
+        assert_eq!(detector.poll(start + DEFAULT_IDLE_TIMEOUT), Some(IdleEvent::CommandLikelyDone));
+    }
+}
@@ -1,8 +1,9 @@
 use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
 use nix::unistd::Pid;
 use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::time::sleep;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExitStatus {
@@ -79,37 +80,45 @@ impl ProcessManager {
         }
     }
     
-    /// Wait for the child process to exit (blocking)
+    /// Wait for the child process to exit, woken by SIGCHLD rather than polling.
+    /// Only this child is reaped; SIGCHLD from other children just triggers a re-check.
     pub async fn wait_for_exit(&self) -> Result<ExitStatus, nix::Error> {
+        // Listen before the first check so an exit in between isn't missed
+        let mut sigchld = match signal(SignalKind::child()) {
+            Ok(sigchld) => sigchld,
+            Err(e) => {
+                warn!("Cannot listen for SIGCHLD ({}), polling child {} instead", e, self.child_pid);
+                return self.poll_for_exit().await;
+            }
+        };
+        
         loop {
             match self.try_wait()? {
                 ExitStatus::Running => {
-                    sleep(Duration::from_millis(10)).await;
-                    continue;
+                    sigchld.recv().await;
                 }
                 status => return Ok(status),
             }
         }
     }
     
-    /// Wait for the child process to exit with a timeout
-    pub async fn wait_for_exit_timeout(&self, timeout: Duration) -> Result<Option<ExitStatus>, nix::Error> {
-        let start = std::time::Instant::now();
-        
+    async fn poll_for_exit(&self) -> Result<ExitStatus, nix::Error> {
         loop {
             match self.try_wait()? {
-                ExitStatus::Running => {
-                    if start.elapsed() >= timeout {
-                        return Ok(None);
-                    }
-                    sleep(Duration::from_millis(10)).await;
-                    continue;
-                }
-                status => return Ok(Some(status)),
+                ExitStatus::Running => sleep(Duration::from_millis(10)).await,
+                status => return Ok(status),
             }
         }
     }
     
+    /// Wait for the child process to exit with a timeout
+    pub async fn wait_for_exit_timeout(&self, timeout: Duration) -> Result<Option<ExitStatus>, nix::Error> {
+        match tokio::time::timeout(timeout, self.wait_for_exit()).await {
+            Ok(status) => status.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+    
     /// Check if the child process is still running
     pub fn is_running(&self) -> Result<bool, nix::Error> {
         match self.try_wait()? {
@@ -136,6 +145,50 @@ mod tests {
         assert_eq!(manager.pid(), pid);
     }
     
+    /// A `/bin/sh -c script` child and its pid, for a `ProcessManager` to reap
+    fn spawn_sh(script: &str) -> (std::process::Child, Pid) {
+        let child = std::process::Command::new("/bin/sh").arg("-c").arg(script).spawn().unwrap();
+        let pid = Pid::from_raw(child.id() as i32);
+        (child, pid)
+    }
+    
+    /// Make sure `child` is reaped; one its manager already waited for
+    /// leaves nothing to collect
+    fn reap(mut child: std::process::Child) {
+        let _ = child.wait();
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_exit_wakes_on_sigchld() {
+        let (child, pid) = spawn_sh("exit 3");
+        let manager = ProcessManager::new(pid);
+        let start = std::time::Instant::now();
+        let status = manager.wait_for_exit_timeout(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(status, Some(ExitStatus::Code(3)));
+        assert!(start.elapsed() < Duration::from_millis(500), "took {:?}", start.elapsed());
+        reap(child);
+        
+        // Another child exiting first wakes the wait but isn't reaped by it
+        let (other_child, other_pid) = spawn_sh("exit 7");
+        let (child, pid) = spawn_sh("sleep 0.2");
+        let other = ProcessManager::new(other_pid);
+        let manager = ProcessManager::new(pid);
+        let start = std::time::Instant::now();
+        assert_eq!(manager.wait_for_exit().await.unwrap(), ExitStatus::Code(0));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(200) && elapsed < Duration::from_millis(700), "took {:?}", elapsed);
+        assert_eq!(other.wait_for_exit().await.unwrap(), ExitStatus::Code(7));
+        reap(child);
+        reap(other_child);
+        
+        let (child, pid) = spawn_sh("sleep 5");
+        let running = ProcessManager::new(pid);
+        assert_eq!(running.wait_for_exit_timeout(Duration::from_millis(50)).await.unwrap(), None);
+        nix::sys::signal::kill(running.pid(), nix::sys::signal::Signal::SIGKILL).unwrap();
+        assert_eq!(running.wait_for_exit().await.unwrap(), ExitStatus::Signal(9));
+        reap(child);
+    }
+    
     #[test]
     fn test_exit_status_methods() {
         assert!(ExitStatus::Code(0).success());
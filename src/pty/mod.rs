@@ -2,8 +2,18 @@ pub mod host;
 pub mod signals;
 pub mod lifecycle;
 pub mod recovery;
+pub mod utf8;
+pub mod debounce;
+pub mod tap;
+pub mod idle;
+pub mod pump;
 
-pub use host::{PtyHost, PtyError};
+pub use host::{PtyHost, PtyError, SpawnOptions, DEFAULT_READ_BUFFER_SIZE};
 pub use signals::{SignalHandler, SignalEvent};
 pub use lifecycle::{ProcessManager, ExitStatus};
-pub use recovery::{ResilientPtyHost, RetryConfig, ConnectionStats};
+pub use recovery::{ResilientPtyHost, RetryConfig, RetryConfigBuilder, ConnectionStats};
+pub use utf8::Utf8Reassembler;
+pub use debounce::{Debouncer, RESIZE_DEBOUNCE};
+pub use tap::{RawTap, RAW_TAP_CAPACITY};
+pub use idle::{IdleDetector, IdleEvent, DEFAULT_IDLE_TIMEOUT};
+pub use pump::{OutputPump, DEFAULT_OUTPUT_BUDGET};
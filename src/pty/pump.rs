@@ -0,0 +1,92 @@
+// Bounded processing of PTY output bursts
+// A command like `cat bigfile` can keep the reader busy parsing for long
+// stretches; handing its output over in budget-sized chunks with a yield
+// between them keeps key input and rendering serviced.
+
+/// Bytes of output processed before yielding to other tasks
+pub const DEFAULT_OUTPUT_BUDGET: usize = 64 * 1024;
+
+/// Splits one burst of output into chunks of at most `budget` bytes,
+/// yielding to the async runtime before each chunk after the first
+#[derive(Debug)]
+pub struct OutputPump<'a> {
+    remaining: &'a [u8],
+    budget: usize,
+    started: bool,
+}
+
+impl<'a> OutputPump<'a> {
+    pub fn new(data: &'a [u8], budget: usize) -> Self {
+        Self {
+            remaining: data,
+            budget: budget.max(1),
+            started: false,
+        }
+    }
+    
+    /// The next chunk to process, or `None` once the burst is done
+    pub async fn next_chunk(&mut self) -> Option<&'a [u8]> {
+        if self.remaining.is_empty() {
+            return None;
+        }
+        if self.started {
+            tokio::task::yield_now().await;
+        }
+        self.started = true;
+        
+        let (chunk, rest) = self.remaining.split_at(self.budget.min(self.remaining.len()));
+        self.remaining = rest;
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_huge_read_is_processed_in_bounded_chunks() {
+        // Stands in for the event loop: counts how often it gets to run
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                }
+            }
+        });
+        
+        let data = vec![b'x'; 10 * 1024 * 1024 + 5];
+        let mut pump = OutputPump::new(&data, DEFAULT_OUTPUT_BUDGET);
+        let mut sizes = Vec::new();
+        let mut ticks_seen = Vec::new();
+        while let Some(chunk) = pump.next_chunk().await {
+            sizes.push(chunk.len());
+            ticks_seen.push(ticks.load(Ordering::SeqCst));
+        }
+        ticker.abort();
+        
+        assert_eq!(sizes.len(), 161);
+        assert!(sizes[..160].iter().all(|&size| size == DEFAULT_OUTPUT_BUDGET));
+        assert_eq!(sizes[160], 5);
+        assert_eq!(sizes.iter().sum::<usize>(), data.len());
+        // The other task ran between every pair of chunks
+        assert!(ticks_seen.windows(2).all(|pair| pair[1] > pair[0]), "{:?}", ticks_seen);
+    }
+    
+    #[tokio::test]
+    async fn test_small_read_is_one_chunk() {
+        let mut pump = OutputPump::new(b"ls\r\n", DEFAULT_OUTPUT_BUDGET);
+        assert_eq!(pump.next_chunk().await, Some(&b"ls\r\n"[..]));
+        assert_eq!(pump.next_chunk().await, None);
+        
+        assert_eq!(OutputPump::new(b"", 16).next_chunk().await, None);
+        // A zero budget still makes progress
+        let mut pump = OutputPump::new(b"ab", 0);
+        assert_eq!(pump.next_chunk().await, Some(&b"a"[..]));
+    }
+}
@@ -1,6 +1,7 @@
 // PTY Recovery and Error Handling - Phase A Enhancement
 // Provides resilient PTY operations with automatic recovery
 
+use crate::error::TermindError;
 use crate::pty::{PtyHost, PtyError};
 use crate::renderer::TerminalParser;
 use std::time::{Duration, Instant};
@@ -12,9 +13,22 @@ pub struct ResilientPtyHost {
     parser: TerminalParser,
     retry_config: RetryConfig,
     last_failure: Option<Instant>,
+    last_success: Option<Instant>,
     consecutive_failures: u32,
+    total_reads: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+    successful_recoveries: u32,
+    ever_connected: bool,
 }
 
+/// Retry/backoff settings for PTY recovery.
+///
+/// Valid ranges (enforced by [`RetryConfigBuilder`]):
+/// - `max_retries` >= 1
+/// - `base_delay` > 0 and `base_delay` <= `max_delay`
+/// - `backoff_multiplier` finite and >= 1.0
+/// - `failure_threshold` >= 1
 #[derive(Clone)]
 pub struct RetryConfig {
     pub max_retries: u32,
@@ -36,6 +50,84 @@ impl Default for RetryConfig {
     }
 }
 
+impl RetryConfig {
+    pub fn builder() -> RetryConfigBuilder {
+        RetryConfigBuilder::default()
+    }
+
+    /// Backoff before retry number `attempt + 1`, capped at `max_delay`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay_ms = (self.base_delay.as_millis() as f64 *
+                       self.backoff_multiplier.powi(attempt as i32)) as u64;
+        Duration::from_millis(delay_ms).min(self.max_delay)
+    }
+}
+
+/// Builder for [`RetryConfig`] that validates the settings on `build`
+#[derive(Clone, Default)]
+pub struct RetryConfigBuilder {
+    config: RetryConfig,
+}
+
+impl RetryConfigBuilder {
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.config.max_retries = max_retries;
+        self
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.config.base_delay = base_delay;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.config.max_delay = max_delay;
+        self
+    }
+
+    pub fn backoff_multiplier(mut self, backoff_multiplier: f64) -> Self {
+        self.config.backoff_multiplier = backoff_multiplier;
+        self
+    }
+
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.config.failure_threshold = failure_threshold;
+        self
+    }
+
+    pub fn build(self) -> crate::error::Result<RetryConfig> {
+        let config = self.config;
+
+        if config.max_retries == 0 {
+            return Err(invalid_retry_config("max_retries must be at least 1"));
+        }
+        if config.base_delay.is_zero() {
+            return Err(invalid_retry_config("base_delay must be greater than zero"));
+        }
+        if config.base_delay > config.max_delay {
+            return Err(invalid_retry_config(&format!(
+                "base_delay ({:?}) must not exceed max_delay ({:?})",
+                config.base_delay, config.max_delay
+            )));
+        }
+        if !config.backoff_multiplier.is_finite() || config.backoff_multiplier < 1.0 {
+            return Err(invalid_retry_config(&format!(
+                "backoff_multiplier must be a finite value >= 1.0, got {}",
+                config.backoff_multiplier
+            )));
+        }
+        if config.failure_threshold == 0 {
+            return Err(invalid_retry_config("failure_threshold must be at least 1"));
+        }
+
+        Ok(config)
+    }
+}
+
+fn invalid_retry_config(reason: &str) -> TermindError {
+    TermindError::Configuration(format!("Invalid retry config: {}", reason))
+}
+
 #[derive(Debug)]
 pub enum RecoveryAction {
     Retry,
@@ -50,7 +142,13 @@ impl ResilientPtyHost {
             parser: TerminalParser::new(rows, cols),
             retry_config: RetryConfig::default(),
             last_failure: None,
+            last_success: None,
             consecutive_failures: 0,
+            total_reads: 0,
+            bytes_read: 0,
+            bytes_written: 0,
+            successful_recoveries: 0,
+            ever_connected: false,
         }
     }
 
@@ -72,13 +170,17 @@ impl ResilientPtyHost {
             match PtyHost::spawn_shell().await {
                 Ok(pty) => {
                     self.pty = Some(pty);
-                    self.consecutive_failures = 0;
+                    if self.ever_connected {
+                        self.successful_recoveries += 1;
+                    }
+                    self.ever_connected = true;
+                    self.record_success();
                     info!("PTY successfully initialized on attempt {}", attempt + 1);
                     return Ok(());
                 }
                 Err(e) => {
                     attempt += 1;
-                    self.consecutive_failures += 1;
+                    self.record_failure();
                     warn!("PTY initialization failed (attempt {}): {}", attempt, e);
 
                     if attempt < self.retry_config.max_retries {
@@ -114,10 +216,13 @@ impl ResilientPtyHost {
                         if attempt > 0 {
                             info!("Write succeeded after {} retries", attempt);
                         }
+                        self.bytes_written += data.len() as u64;
+                        self.record_success();
                         return Ok(());
                     }
                     Err(e) => {
                         warn!("Write failed (attempt {}): {}", attempt + 1, e);
+                        self.record_failure();
                         
                         match self.determine_recovery_action(&e) {
                             RecoveryAction::Retry => {
@@ -159,6 +264,9 @@ impl ResilientPtyHost {
         if let Some(ref mut pty) = self.pty {
             match pty.try_read().await {
                 Ok(data) => {
+                    self.total_reads += 1;
+                    self.bytes_read += data.len() as u64;
+                    self.record_success();
                     if !data.is_empty() {
                         // Process through parser for validation
                         self.parser.parse(&data);
@@ -168,6 +276,7 @@ impl ResilientPtyHost {
                 }
                 Err(e) => {
                     warn!("Read error: {}", e);
+                    self.record_failure();
                     
                     match self.determine_recovery_action(&e) {
                         RecoveryAction::Recreate => {
@@ -231,6 +340,11 @@ impl ResilientPtyHost {
             is_connected: self.is_connected(),
             consecutive_failures: self.consecutive_failures,
             last_failure: self.last_failure,
+            total_reads: self.total_reads,
+            bytes_read: self.bytes_read,
+            bytes_written: self.bytes_written,
+            successful_recoveries: self.successful_recoveries,
+            time_since_last_success: self.last_success.map(|t| t.elapsed()),
         }
     }
 
@@ -242,6 +356,16 @@ impl ResilientPtyHost {
         }
     }
 
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.last_success = Some(Instant::now());
+    }
+
+    fn record_failure(&mut self) {
+        self.consecutive_failures += 1;
+        self.last_failure = Some(Instant::now());
+    }
+
     fn determine_recovery_action(&self, error: &PtyError) -> RecoveryAction {
         match error {
             PtyError::Io(io_err) => {
@@ -273,9 +397,7 @@ impl ResilientPtyHost {
     }
 
     fn calculate_delay(&self, attempt: u32) -> Duration {
-        let delay_ms = (self.retry_config.base_delay.as_millis() as f64 * 
-                       self.retry_config.backoff_multiplier.powi(attempt as i32)) as u64;
-        Duration::from_millis(delay_ms).min(self.retry_config.max_delay)
+        self.retry_config.delay(attempt)
     }
 }
 
@@ -293,6 +415,13 @@ pub struct ConnectionStats {
     pub is_connected: bool,
     pub consecutive_failures: u32,
     pub last_failure: Option<Instant>,
+    /// Number of completed read calls, including ones that returned no data
+    pub total_reads: u64,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    /// Reconnections after a previously established PTY was lost
+    pub successful_recoveries: u32,
+    pub time_since_last_success: Option<Duration>,
 }
 
 #[cfg(test)]
@@ -327,4 +456,84 @@ mod tests {
         assert!(result.is_ok(), "Should reconnect automatically");
         assert!(resilient_pty.is_connected());
     }
+    
+    #[test]
+    fn test_retry_config_builder_valid() {
+        let config = RetryConfig::builder()
+            .max_retries(3)
+            .base_delay(Duration::from_millis(50))
+            .max_delay(Duration::from_secs(1))
+            .backoff_multiplier(1.5)
+            .failure_threshold(2)
+            .build()
+            .expect("valid config should build");
+        
+        assert_eq!(config.max_retries, 3);
+        assert_eq!(config.base_delay, Duration::from_millis(50));
+        assert_eq!(config.max_delay, Duration::from_secs(1));
+        assert_eq!(config.backoff_multiplier, 1.5);
+        assert_eq!(config.failure_threshold, 2);
+        
+        // Defaults are valid as-is
+        assert!(RetryConfig::builder().build().is_ok());
+    }
+    
+    #[test]
+    fn test_retry_config_builder_rejects_invalid() {
+        assert!(RetryConfig::builder().max_retries(0).build().is_err());
+        assert!(RetryConfig::builder().base_delay(Duration::ZERO).build().is_err());
+        assert!(RetryConfig::builder().backoff_multiplier(0.5).build().is_err());
+        assert!(RetryConfig::builder().backoff_multiplier(f64::NAN).build().is_err());
+        assert!(RetryConfig::builder().failure_threshold(0).build().is_err());
+        assert!(RetryConfig::builder()
+            .base_delay(Duration::from_secs(10))
+            .max_delay(Duration::from_secs(1))
+            .build()
+            .is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_connection_stats_counters() {
+        let mut resilient_pty = ResilientPtyHost::new(24, 80);
+        let stats = resilient_pty.connection_stats();
+        assert_eq!(stats.total_reads, 0);
+        assert!(stats.time_since_last_success.is_none());
+        
+        resilient_pty.write_resilient(b"echo stats\n").await.unwrap();
+        resilient_pty.read_resilient().await.unwrap();
+        resilient_pty.read_resilient().await.unwrap();
+        
+        let stats = resilient_pty.connection_stats();
+        assert_eq!(stats.bytes_written, 11);
+        assert_eq!(stats.total_reads, 2);
+        assert!(stats.time_since_last_success.is_some());
+        assert_eq!(stats.successful_recoveries, 0);
+        
+        // Reconnecting after a lost connection counts as a recovery
+        resilient_pty.disconnect();
+        resilient_pty.write_resilient(b"\n").await.unwrap();
+        assert_eq!(resilient_pty.connection_stats().successful_recoveries, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_last_failure_recorded() {
+        let mut resilient_pty = ResilientPtyHost::new(24, 80);
+        resilient_pty.write_resilient(b"exit\n").await.unwrap();
+        assert!(resilient_pty.connection_stats().last_failure.is_none());
+        
+        // Once the shell exits, reading the PTY master fails
+        let mut failed = false;
+        for _ in 0..500 {
+            if resilient_pty.read_resilient().await.is_err() {
+                failed = true;
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        
+        assert!(failed, "Reading from an exited shell should fail");
+        let stats = resilient_pty.connection_stats();
+        assert!(stats.last_failure.is_some());
+        assert!(stats.consecutive_failures >= 1);
+    }
 }
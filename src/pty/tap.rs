@@ -0,0 +1,90 @@
+// Recent raw PTY output kept for diagnosing rendering and parsing bugs
+// Only the newest bytes are retained, so memory stays bounded at any throughput.
+
+use std::collections::VecDeque;
+
+/// Default amount of raw output kept by `RawTap`
+pub const RAW_TAP_CAPACITY: usize = 64 * 1024;
+
+/// Fixed-size ring buffer of the most recent bytes read from the PTY
+#[derive(Debug)]
+pub struct RawTap {
+    capacity: usize,
+    buffer: VecDeque<u8>,
+}
+
+impl RawTap {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            buffer: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Append a chunk, dropping the oldest bytes beyond capacity
+    pub fn push(&mut self, chunk: &[u8]) {
+        // Only the tail of an oversized chunk can survive
+        let chunk = &chunk[chunk.len().saturating_sub(self.capacity)..];
+        let overflow = (self.buffer.len() + chunk.len()).saturating_sub(self.capacity);
+        self.buffer.drain(..overflow);
+        self.buffer.extend(chunk);
+    }
+
+    /// The retained bytes, oldest first
+    pub fn dump(&self) -> Vec<u8> {
+        self.buffer.iter().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl Default for RawTap {
+    fn default() -> Self {
+        Self::new(RAW_TAP_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_most_recent_bytes() {
+        let mut tap = RawTap::new(8);
+        tap.push(b"abcdef");
+        assert_eq!(tap.dump(), b"abcdef");
+
+        tap.push(b"ghijkl");
+        assert_eq!(tap.dump(), b"efghijkl");
+        assert_eq!(tap.len(), 8);
+    }
+
+    #[test]
+    fn test_oversized_chunk() {
+        let mut tap = RawTap::new(4);
+        tap.push(b"xy");
+        tap.push(b"0123456789");
+        assert_eq!(tap.dump(), b"6789");
+
+        // Memory stays bounded however much is written
+        for _ in 0..1000 {
+            tap.push(&[b'z'; 37]);
+        }
+        assert_eq!(tap.len(), 4);
+        assert!(tap.buffer.capacity() < 64);
+
+        let mut empty = RawTap::new(0);
+        empty.push(b"anything");
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+// UTF-8 reassembly for PTY output read in fixed-size chunks
+// A multibyte character may be split across two reads; decoding each chunk on its
+// own would replace both halves with U+FFFD.
+
+/// Buffers incomplete trailing UTF-8 sequences between chunks
+#[derive(Debug, Default)]
+pub struct Utf8Reassembler {
+    pending: Vec<u8>,
+}
+
+impl Utf8Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode a chunk, holding back any incomplete sequence at its end.
+    /// Invalid bytes are replaced with U+FFFD as in `String::from_utf8_lossy`.
+    pub fn push(&mut self, chunk: &[u8]) -> String {
+        self.pending.extend_from_slice(chunk);
+
+        let mut text = String::with_capacity(self.pending.len());
+        let mut start = 0;
+
+        loop {
+            match std::str::from_utf8(&self.pending[start..]) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    start = self.pending.len();
+                    break;
+                }
+                Err(e) => {
+                    let valid_end = start + e.valid_up_to();
+                    text.push_str(&String::from_utf8_lossy(&self.pending[start..valid_end]));
+
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            start = valid_end + len;
+                        }
+                        None => {
+                            // Incomplete sequence at the end, wait for more bytes
+                            start = valid_end;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pending.drain(..start);
+        text
+    }
+
+    /// Flush any buffered bytes at end of stream
+    pub fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+
+    /// Number of bytes held back waiting for the rest of a sequence
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_emoji() {
+        let bytes = "ok 🚀 done".as_bytes();
+        // Split in the middle of the 4-byte rocket emoji
+        let (first, second) = bytes.split_at(5);
+
+        let mut reassembler = Utf8Reassembler::new();
+        assert_eq!(reassembler.push(first), "ok ");
+        assert_eq!(reassembler.pending_len(), 2);
+        assert_eq!(reassembler.push(second), "🚀 done");
+        assert_eq!(reassembler.pending_len(), 0);
+    }
+
+    #[test]
+    fn test_byte_by_byte() {
+        let input = "héllo wörld ✅";
+        let mut reassembler = Utf8Reassembler::new();
+        let mut output = String::new();
+        for byte in input.as_bytes() {
+            output.push_str(&reassembler.push(std::slice::from_ref(byte)));
+        }
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn test_invalid_bytes_replaced() {
+        let mut reassembler = Utf8Reassembler::new();
+        assert_eq!(reassembler.push(b"a\xffb"), "a\u{FFFD}b");
+
+        // A truncated sequence at end of stream is flushed lossily
+        assert_eq!(reassembler.push(b"c\xe2\x9c"), "c");
+        assert_eq!(reassembler.finish(), "\u{FFFD}");
+        assert_eq!(reassembler.pending_len(), 0);
+    }
+}
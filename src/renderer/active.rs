@@ -0,0 +1,247 @@
+// Startup renderer selection: GPU when available, software otherwise
+
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use winit::dpi::PhysicalSize;
+
+use crate::renderer::{CellGeometry, FontSizeLimits, RenderError, Renderer, TextGrid};
+
+/// Which renderer to start with, e.g. from `--renderer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RendererChoice {
+    /// GPU when it initializes, software otherwise
+    #[default]
+    Auto,
+    /// GPU or fail
+    Gpu,
+    /// Software only; the GPU is never initialized
+    Software,
+}
+
+impl fmt::Display for RendererChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RendererChoice::Auto => "auto",
+            RendererChoice::Gpu => "gpu",
+            RendererChoice::Software => "software",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for RendererChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(RendererChoice::Auto),
+            "gpu" => Ok(RendererChoice::Gpu),
+            "software" => Ok(RendererChoice::Software),
+            other => Err(format!("Unknown renderer '{}' (expected auto, gpu or software)", other)),
+        }
+    }
+}
+
+/// The renderer chosen at startup. `G` is the GPU backend and `S` the
+/// software backend together with whatever presents its frames.
+pub enum ActiveRenderer<G, S> {
+    Gpu(G),
+    Software(S),
+}
+
+impl<G, S> ActiveRenderer<G, S> {
+    /// Use the GPU renderer if it initialized; on `RenderError::GpuInit` fall
+    /// back to `software`. Any other error is returned as is, since the
+    /// software renderer would hit the same window or font problem.
+    pub fn select(
+        gpu: Result<G, RenderError>,
+        software: impl FnOnce() -> Result<S, RenderError>,
+    ) -> Result<Self, RenderError> {
+        match gpu {
+            Ok(gpu) => Ok(Self::Gpu(gpu)),
+            Err(RenderError::GpuInit(reason)) => {
+                tracing::warn!("GPU renderer unavailable ({}), falling back to software rendering", reason);
+                software().map(Self::Software)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Build the renderer `choice` asks for. `gpu` is only called (and its
+    /// future awaited) when the choice allows the GPU; a forced GPU that fails
+    /// to initialize is an error rather than a fallback.
+    pub async fn select_with<F>(
+        choice: RendererChoice,
+        gpu: impl FnOnce() -> F,
+        software: impl FnOnce() -> Result<S, RenderError>,
+    ) -> Result<Self, RenderError>
+    where
+        F: Future<Output = Result<G, RenderError>>,
+    {
+        match choice {
+            RendererChoice::Auto => Self::select(gpu().await, software),
+            RendererChoice::Gpu => match gpu().await {
+                Ok(gpu) => Ok(Self::Gpu(gpu)),
+                Err(RenderError::GpuInit(reason)) => Err(RenderError::GpuInit(format!(
+                    "{} (the GPU renderer was required; use --renderer auto or software to fall back)",
+                    reason
+                ))),
+                Err(e) => Err(e),
+            },
+            RendererChoice::Software => software().map(Self::Software),
+        }
+    }
+    
+    pub fn is_gpu(&self) -> bool {
+        matches!(self, Self::Gpu(_))
+    }
+}
+
+impl<G: Renderer, S: Renderer> Renderer for ActiveRenderer<G, S> {
+    fn render_frame(&mut self, grid: &TextGrid) -> Result<(), RenderError> {
+        match self {
+            Self::Gpu(gpu) => gpu.render_frame(grid),
+            Self::Software(software) => software.render_frame(grid),
+        }
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) -> Result<(), RenderError> {
+        match self {
+            Self::Gpu(gpu) => gpu.resize(size),
+            Self::Software(software) => software.resize(size),
+        }
+    }
+
+    fn cell_size(&self) -> (u32, u32) {
+        match self {
+            Self::Gpu(gpu) => gpu.cell_size(),
+            Self::Software(software) => software.cell_size(),
+        }
+    }
+
+    fn geometry(&self) -> CellGeometry {
+        match self {
+            Self::Gpu(gpu) => gpu.geometry(),
+            Self::Software(software) => software.geometry(),
+        }
+    }
+    
+    fn font_size(&self) -> f32 {
+        match self {
+            Self::Gpu(gpu) => gpu.font_size(),
+            Self::Software(software) => software.font_size(),
+        }
+    }
+    
+    fn set_font_size(&mut self, font_size: f32) -> Result<f32, RenderError> {
+        match self {
+            Self::Gpu(gpu) => gpu.set_font_size(font_size),
+            Self::Software(software) => software.set_font_size(font_size),
+        }
+    }
+    
+    fn set_font_size_limits(&mut self, limits: FontSizeLimits) -> Result<f32, RenderError> {
+        match self {
+            Self::Gpu(gpu) => gpu.set_font_size_limits(limits),
+            Self::Software(software) => software.set_font_size_limits(limits),
+        }
+    }
+    
+    fn set_fallback_fonts(&mut self, paths: &[PathBuf]) -> Result<(), RenderError> {
+        match self {
+            Self::Gpu(gpu) => gpu.set_fallback_fonts(paths),
+            Self::Software(software) => software.set_fallback_fonts(paths),
+        }
+    }
+    
+    fn set_bell_flash(&mut self, intensity: f32) {
+        match self {
+            Self::Gpu(gpu) => gpu.set_bell_flash(intensity),
+            Self::Software(software) => software.set_bell_flash(intensity),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::gpu::GpuRenderer;
+    use crate::renderer::software::SoftwareRenderer;
+
+    fn assert_renderer<R: Renderer>() {}
+
+    #[test]
+    fn test_backends_implement_renderer() {
+        assert_renderer::<GpuRenderer>();
+        assert_renderer::<SoftwareRenderer>();
+        assert_renderer::<ActiveRenderer<GpuRenderer, SoftwareRenderer>>();
+    }
+
+    #[test]
+    fn test_falls_back_to_software_on_gpu_init_failure() {
+        let gpu: Result<&str, RenderError> = Err(RenderError::GpuInit("no compatible adapter".to_string()));
+        let active = ActiveRenderer::select(gpu, || Ok("software")).unwrap();
+        assert!(matches!(active, ActiveRenderer::Software("software")));
+    }
+
+    #[test]
+    fn test_prefers_gpu() {
+        let active = ActiveRenderer::<_, &str>::select(Ok("gpu"), || panic!("software renderer built needlessly")).unwrap();
+        assert!(active.is_gpu());
+    }
+
+    #[tokio::test]
+    async fn test_select_with_each_choice() {
+        let gpu_ok = || async { Ok::<_, RenderError>("gpu") };
+        let gpu_missing = || async { Err::<&str, _>(RenderError::GpuInit("no compatible adapter".to_string())) };
+        let software = || Ok::<_, RenderError>("software");
+        
+        let auto = ActiveRenderer::select_with(RendererChoice::Auto, gpu_ok, software).await.unwrap();
+        assert!(auto.is_gpu());
+        let auto = ActiveRenderer::select_with(RendererChoice::Auto, gpu_missing, software).await.unwrap();
+        assert!(matches!(auto, ActiveRenderer::Software("software")));
+        
+        let forced = ActiveRenderer::<_, &str>::select_with(RendererChoice::Gpu, gpu_ok, || panic!("software renderer built")).await;
+        assert!(forced.unwrap().is_gpu());
+        let forced = ActiveRenderer::select_with(RendererChoice::Gpu, gpu_missing, software).await;
+        match forced {
+            Err(RenderError::GpuInit(reason)) => assert!(reason.contains("no compatible adapter") && reason.contains("required")),
+            _ => panic!("forced GPU fell back or succeeded"),
+        }
+        
+        let software_only = ActiveRenderer::<&str, _>::select_with(
+            RendererChoice::Software,
+            || async { panic!("GPU initialized for --renderer software") },
+            software,
+        )
+        .await
+        .unwrap();
+        assert!(!software_only.is_gpu());
+    }
+    
+    #[test]
+    fn test_renderer_choice_parsing() {
+        for choice in [RendererChoice::Auto, RendererChoice::Gpu, RendererChoice::Software] {
+            assert_eq!(choice.to_string().parse::<RendererChoice>(), Ok(choice));
+        }
+        assert_eq!("GPU".parse::<RendererChoice>(), Ok(RendererChoice::Gpu));
+        assert!("vulkan".parse::<RendererChoice>().is_err());
+    }
+    
+    #[test]
+    fn test_other_errors_do_not_fall_back() {
+        let gpu: Result<&str, RenderError> = Err(RenderError::Window("surface lost".to_string()));
+        let result = ActiveRenderer::select(gpu, || Ok("software"));
+        assert!(matches!(result, Err(RenderError::Window(_))));
+
+        // A failing fallback reports its own error
+        let gpu: Result<&str, RenderError> = Err(RenderError::GpuInit("headless".to_string()));
+        let result = ActiveRenderer::<_, &str>::select(gpu, || Err(RenderError::Font("no font".to_string())));
+        assert!(matches!(result, Err(RenderError::Font(_))));
+    }
+}
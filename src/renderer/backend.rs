@@ -0,0 +1,37 @@
+// Operations shared by the GPU and software renderers
+
+use std::path::PathBuf;
+
+use winit::dpi::PhysicalSize;
+
+use crate::renderer::{CellGeometry, FontSizeLimits, RenderError, TextGrid};
+
+/// A rendering backend the event loop can drive without knowing which one it is
+pub trait Renderer {
+    /// Draw `grid`. Backends that present to a surface do so here; the
+    /// software renderer leaves the frame in its pixel buffer.
+    fn render_frame(&mut self, grid: &TextGrid) -> Result<(), RenderError>;
+    
+    fn resize(&mut self, size: PhysicalSize<u32>) -> Result<(), RenderError>;
+    
+    /// Cell `(width, height)` in pixels
+    fn cell_size(&self) -> (u32, u32);
+    
+    /// Where the grid sits in the window and how many cells fit
+    fn geometry(&self) -> CellGeometry;
+    
+    fn font_size(&self) -> f32;
+    
+    /// Change the font size within the backend's limits, returning the size
+    /// applied. `geometry()` reflects the new cell size afterwards.
+    fn set_font_size(&mut self, font_size: f32) -> Result<f32, RenderError>;
+    
+    /// Restrict the font size to `limits`, clamping the current size
+    fn set_font_size_limits(&mut self, limits: FontSizeLimits) -> Result<f32, RenderError>;
+    
+    /// Fonts tried in order for glyphs the primary font lacks
+    fn set_fallback_fonts(&mut self, paths: &[PathBuf]) -> Result<(), RenderError>;
+    
+    /// Visual bell strength for the following frames, from 0.0 (off) to 1.0
+    fn set_bell_flash(&mut self, intensity: f32);
+}
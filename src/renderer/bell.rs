@@ -0,0 +1,181 @@
+// Bell policy: what a BEL (`TerminalEvent::Bell`) does. A visual bell tints
+// the frame for a moment; an audible one beeps when built with `audible-bell`.
+
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::renderer::Rgba;
+
+/// How long the visual bell takes to fade out
+pub const VISUAL_BELL_DURATION: Duration = Duration::from_millis(150);
+
+/// How far a full-strength flash moves colors towards the flash color
+const MAX_BELL_TINT: f32 = 0.35;
+
+/// How the bell is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BellMode {
+    /// Flash the window
+    #[default]
+    Visual,
+    /// Beep; without the `audible-bell` feature this does nothing
+    Audible,
+    Both,
+    None,
+}
+
+impl BellMode {
+    fn flashes(self) -> bool {
+        matches!(self, Self::Visual | Self::Both)
+    }
+    
+    fn beeps(self) -> bool {
+        matches!(self, Self::Audible | Self::Both)
+    }
+}
+
+/// The `[bell]` table of `config.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BellConfig {
+    pub mode: BellMode,
+}
+
+/// A flash that starts at full strength when rung and fades out linearly
+/// over its duration. Ringing again restarts it.
+#[derive(Debug, Clone, Copy)]
+pub struct VisualBell {
+    duration: Duration,
+    rung_at: Option<Instant>,
+}
+
+impl Default for VisualBell {
+    fn default() -> Self {
+        Self::new(VISUAL_BELL_DURATION)
+    }
+}
+
+impl VisualBell {
+    pub fn new(duration: Duration) -> Self {
+        Self { duration, rung_at: None }
+    }
+    
+    pub fn ring(&mut self, now: Instant) {
+        self.rung_at = Some(now);
+    }
+    
+    /// Flash strength at `now`, from 1.0 just after ringing down to 0.0
+    /// once the duration has passed
+    pub fn intensity(&self, now: Instant) -> f32 {
+        let Some(rung_at) = self.rung_at else {
+            return 0.0;
+        };
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+        let elapsed = now.saturating_duration_since(rung_at);
+        1.0 - (elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+    
+    pub fn is_active(&self, now: Instant) -> bool {
+        self.intensity(now) > 0.0
+    }
+}
+
+/// The bell as configured: rings the visual bell, beeps, both or neither
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bell {
+    mode: BellMode,
+    flash: VisualBell,
+}
+
+impl Bell {
+    pub fn new(config: BellConfig) -> Self {
+        Self { mode: config.mode, flash: VisualBell::default() }
+    }
+    
+    pub fn ring(&mut self, now: Instant) {
+        if self.mode.flashes() {
+            self.flash.ring(now);
+        }
+        if self.mode.beeps() {
+            beep();
+        }
+    }
+    
+    /// Strength of the visual bell at `now`, for `Renderer::set_bell_flash`
+    pub fn flash_intensity(&self, now: Instant) -> f32 {
+        self.flash.intensity(now)
+    }
+}
+
+/// `color` as drawn during a visual bell of `intensity`, tinted towards `flash`
+pub fn bell_tint(color: Rgba, flash: Rgba, intensity: f32) -> Rgba {
+    color.mixed(flash, intensity * MAX_BELL_TINT)
+}
+
+/// Ring the bell of the terminal termind was started from, if any
+#[cfg(feature = "audible-bell")]
+fn beep() {
+    use std::io::Write;
+    
+    let mut stderr = std::io::stderr();
+    if let Err(e) = stderr.write_all(b"\x07").and_then(|()| stderr.flush()) {
+        tracing::debug!("Failed to ring the audible bell: {}", e);
+    }
+}
+
+#[cfg(not(feature = "audible-bell"))]
+fn beep() {
+    tracing::debug!("Audible bell requested, but termind was built without the audible-bell feature");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_visual_bell_flash_fades_and_clears() {
+        let start = Instant::now();
+        let mut flash = VisualBell::new(Duration::from_millis(100));
+        assert!(!flash.is_active(start));
+        
+        flash.ring(start);
+        assert_eq!(flash.intensity(start), 1.0);
+        let halfway = flash.intensity(start + Duration::from_millis(50));
+        assert!((halfway - 0.5).abs() < 1e-3);
+        assert!(!flash.is_active(start + Duration::from_millis(100)));
+        assert_eq!(flash.intensity(start + Duration::from_secs(5)), 0.0);
+        
+        // Ringing again restarts the flash
+        let later = start + Duration::from_secs(1);
+        flash.ring(later);
+        assert!(flash.is_active(later + Duration::from_millis(10)));
+    }
+    
+    #[test]
+    fn test_bell_mode_decides_flash() {
+        let now = Instant::now();
+        for (mode, flashes) in [
+            (BellMode::Visual, true),
+            (BellMode::Both, true),
+            (BellMode::Audible, false),
+            (BellMode::None, false),
+        ] {
+            let mut bell = Bell::new(BellConfig { mode });
+            bell.ring(now);
+            assert_eq!(bell.flash_intensity(now) > 0.0, flashes, "{:?}", mode);
+        }
+    }
+    
+    #[test]
+    fn test_bell_tint() {
+        let (black, white) = (Rgba::rgb(0, 0, 0), Rgba::rgb(255, 255, 255));
+        assert_eq!(bell_tint(black, white, 0.0), black);
+        let tinted = bell_tint(black, white, 1.0);
+        assert!(tinted.r > 0 && tinted.r < 255);
+        assert_eq!((tinted.r, tinted.g, tinted.b, tinted.a), (tinted.g, tinted.b, tinted.r, 0xFF));
+    }
+}
@@ -0,0 +1,168 @@
+// Copying the mouse selection to the system clipboard, and pasting from it
+
+use crate::error::{Result, TermindError};
+use crate::renderer::grid::TextGrid;
+
+/// Somewhere copied text goes; the system clipboard, or a mock in tests
+pub trait Clipboard {
+    fn set_text(&mut self, text: String) -> Result<()>;
+    
+    fn get_text(&mut self) -> Result<String>;
+}
+
+impl<C: Clipboard + ?Sized> Clipboard for Box<C> {
+    fn set_text(&mut self, text: String) -> Result<()> {
+        (**self).set_text(text)
+    }
+    
+    fn get_text(&mut self) -> Result<String> {
+        (**self).get_text()
+    }
+}
+
+/// The desktop clipboard, through `arboard`
+#[cfg(feature = "clipboard")]
+pub struct SystemClipboard(arboard::Clipboard);
+
+#[cfg(feature = "clipboard")]
+impl Clipboard for SystemClipboard {
+    fn set_text(&mut self, text: String) -> Result<()> {
+        self.0.set_text(text).map_err(|e| TermindError::Clipboard(e.to_string()))
+    }
+    
+    fn get_text(&mut self) -> Result<String> {
+        self.0.get_text().map_err(|e| TermindError::Clipboard(e.to_string()))
+    }
+}
+
+/// The system clipboard, or an error when it can't be reached or termind
+/// was built without the `clipboard` feature
+pub fn system_clipboard() -> Result<Box<dyn Clipboard>> {
+    #[cfg(feature = "clipboard")]
+    {
+        let clipboard = arboard::Clipboard::new().map_err(|e| TermindError::Clipboard(e.to_string()))?;
+        Ok(Box::new(SystemClipboard(clipboard)))
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        Err(TermindError::Clipboard("built without the clipboard feature".to_string()))
+    }
+}
+
+/// Copies a grid's selection to a clipboard: on request, and on mouse-up
+/// when copy-on-select is enabled
+pub struct SelectionClipboard<C> {
+    clipboard: C,
+    copy_on_select: bool,
+}
+
+impl<C: Clipboard> SelectionClipboard<C> {
+    pub fn new(clipboard: C) -> Self {
+        Self {
+            clipboard,
+            copy_on_select: true,
+        }
+    }
+    
+    pub fn with_copy_on_select(mut self, enabled: bool) -> Self {
+        self.copy_on_select = enabled;
+        self
+    }
+    
+    pub fn copy_on_select(&self) -> bool {
+        self.copy_on_select
+    }
+    
+    /// The mouse button was released after a drag. Returns whether text was copied.
+    pub fn on_mouse_up(&mut self, grid: &TextGrid) -> Result<bool> {
+        if !self.copy_on_select {
+            return Ok(false);
+        }
+        self.copy(grid)
+    }
+    
+    /// Copy the selected text, if there is any. Returns whether text was copied.
+    pub fn copy(&mut self, grid: &TextGrid) -> Result<bool> {
+        match grid.selected_text() {
+            Some(text) if !text.is_empty() => {
+                self.clipboard.set_text(text)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+    
+    /// Text to paste: the clipboard contents
+    pub fn paste(&mut self) -> Result<String> {
+        self.clipboard.get_text()
+    }
+    
+    pub fn clipboard(&self) -> &C {
+        &self.clipboard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[derive(Default)]
+    struct MockClipboard {
+        contents: Vec<String>,
+    }
+    
+    impl Clipboard for MockClipboard {
+        fn set_text(&mut self, text: String) -> Result<()> {
+            self.contents.push(text);
+            Ok(())
+        }
+        
+        fn get_text(&mut self) -> Result<String> {
+            self.contents.last().cloned().ok_or_else(|| TermindError::Clipboard("empty".to_string()))
+        }
+    }
+    
+    fn sample_grid() -> TextGrid {
+        let mut grid = TextGrid::new(4, 10);
+        grid.write_str("$ ls -la\r\nCargo.toml\r\nsrc");
+        grid
+    }
+    
+    #[test]
+    fn test_mouse_up_copies_selection() {
+        let mut grid = sample_grid();
+        let mut bridge = SelectionClipboard::new(MockClipboard::default());
+        
+        // Nothing selected yet
+        assert!(!bridge.on_mouse_up(&grid).unwrap());
+        
+        // Drag from "ls" on the first row to the end of "Cargo"
+        grid.start_selection(0, 2);
+        grid.extend_selection(1, 4);
+        assert!(bridge.on_mouse_up(&grid).unwrap());
+        
+        // Dragging backwards selects the same text; a row wrapped at the
+        // margin joins the next one
+        grid.write_str("\r\n0123456789abc");
+        grid.start_selection(3, 2);
+        grid.extend_selection(2, 5);
+        assert!(bridge.on_mouse_up(&grid).unwrap());
+        
+        assert_eq!(bridge.clipboard().contents, vec!["ls -la\nCargo", "56789abc"]);
+    }
+    
+    #[test]
+    fn test_copy_on_select_disabled() {
+        let mut grid = sample_grid();
+        grid.start_selection(2, 0);
+        grid.extend_selection(2, 9);
+        
+        let mut bridge = SelectionClipboard::new(MockClipboard::default()).with_copy_on_select(false);
+        assert!(!bridge.on_mouse_up(&grid).unwrap());
+        assert!(bridge.clipboard().contents.is_empty());
+        
+        // An explicit copy still works
+        assert!(bridge.copy(&grid).unwrap());
+        assert_eq!(bridge.clipboard().contents, vec!["src"]);
+    }
+}
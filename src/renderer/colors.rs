@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum TerminalColor {
     // Standard 16 colors
     Black,
@@ -92,6 +94,53 @@ impl TerminalColor {
         }
     }
     
+    /// Resolve through `palette` and pack as 0xAARRGGBB
+    pub fn to_packed_argb(self, palette: &Palette) -> u32 {
+        palette.resolve(self).to_u32()
+    }
+    
+    /// Parse a `#rgb` or `#rrggbb` hex color, e.g. from a theme file
+    pub fn from_hex(hex: &str) -> Option<TerminalColor> {
+        let digits = hex.strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        
+        let channel = |i: usize, len: usize| u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).ok();
+        let (r, g, b) = match digits.len() {
+            // Short form repeats each digit: #f80 == #ff8800
+            3 => (channel(0, 1)? * 0x11, channel(1, 1)? * 0x11, channel(2, 1)? * 0x11),
+            6 => (channel(0, 2)?, channel(1, 2)?, channel(2, 2)?),
+            _ => return None,
+        };
+        Some(TerminalColor::Rgb { r, g, b })
+    }
+    
+    /// Index into the 16-color ANSI table for the named colors
+    pub fn ansi_index(self) -> Option<u8> {
+        let index = match self {
+            TerminalColor::Black => 0,
+            TerminalColor::Red => 1,
+            TerminalColor::Green => 2,
+            TerminalColor::Yellow => 3,
+            TerminalColor::Blue => 4,
+            TerminalColor::Magenta => 5,
+            TerminalColor::Cyan => 6,
+            TerminalColor::White => 7,
+            TerminalColor::BrightBlack => 8,
+            TerminalColor::BrightRed => 9,
+            TerminalColor::BrightGreen => 10,
+            TerminalColor::BrightYellow => 11,
+            TerminalColor::BrightBlue => 12,
+            TerminalColor::BrightMagenta => 13,
+            TerminalColor::BrightCyan => 14,
+            TerminalColor::BrightWhite => 15,
+            TerminalColor::Indexed(idx) if idx < 16 => idx,
+            _ => return None,
+        };
+        Some(index)
+    }
+    
     fn indexed_to_rgb(index: u8) -> [f32; 4] {
         match index {
             // Standard 16 colors (0-15)
@@ -136,6 +185,122 @@ impl TerminalColor {
     }
 }
 
+/// A resolved 8-bit RGBA color that both renderers can consume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 0xFF }
+    }
+    
+    pub fn from_f32(rgba: [f32; 4]) -> Self {
+        let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self {
+            r: channel(rgba[0]),
+            g: channel(rgba[1]),
+            b: channel(rgba[2]),
+            a: channel(rgba[3]),
+        }
+    }
+    
+    /// Packed as 0xAARRGGBB, the software renderer's pixel format
+    pub fn to_u32(self) -> u32 {
+        (self.a as u32) << 24 | (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+    }
+    
+    /// Unpack a 0xAARRGGBB pixel
+    pub fn from_u32(packed: u32) -> Self {
+        Self {
+            r: (packed >> 16) as u8,
+            g: (packed >> 8) as u8,
+            b: packed as u8,
+            a: (packed >> 24) as u8,
+        }
+    }
+    
+    /// Normalized RGBA, the GPU renderer's vertex color format
+    pub fn to_f32(self) -> [f32; 4] {
+        [
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+            self.a as f32 / 255.0,
+        ]
+    }
+    
+    /// Scale the color channels by `factor`, keeping alpha
+    pub fn scaled(self, factor: f32) -> Self {
+        let channel = |v: u8| (v as f32 * factor).clamp(0.0, 255.0).round() as u8;
+        Self {
+            r: channel(self.r),
+            g: channel(self.g),
+            b: channel(self.b),
+            a: self.a,
+        }
+    }
+    
+    /// Blend towards `other` by `amount`, from 0.0 (this color) to 1.0
+    /// (`other`), keeping alpha
+    pub fn mixed(self, other: Rgba, amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        let channel = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount).round() as u8;
+        Self {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: self.a,
+        }
+    }
+}
+
+/// Color theme used to resolve `TerminalColor`s to concrete colors
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// The 16 standard ANSI colors (black..white, then bright variants)
+    pub ansi: [Rgba; 16],
+    pub default_fg: Rgba,
+    pub default_bg: Rgba,
+    pub cursor: Rgba,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        let mut ansi = [Rgba::rgb(0, 0, 0); 16];
+        for (index, slot) in ansi.iter_mut().enumerate() {
+            *slot = Rgba::from_f32(TerminalColor::Indexed(index as u8).to_rgb());
+        }
+        
+        Self {
+            ansi,
+            default_fg: Rgba::from_f32(TerminalColor::DefaultFg.to_rgb()),
+            default_bg: Rgba::from_f32(TerminalColor::DefaultBg.to_rgb()),
+            cursor: Rgba::from_f32(TerminalColor::DefaultFg.to_rgb()),
+        }
+    }
+}
+
+impl Palette {
+    /// Resolve a color through this palette. Default colors map to the
+    /// palette defaults; 256-color and RGB values outside the 16 ANSI slots
+    /// are fixed.
+    pub fn resolve(&self, color: TerminalColor) -> Rgba {
+        match color {
+            TerminalColor::DefaultFg => self.default_fg,
+            TerminalColor::DefaultBg => self.default_bg,
+            TerminalColor::Indexed(idx) if idx < 16 => self.ansi[idx as usize],
+            TerminalColor::Indexed(_) => Rgba::from_f32(color.to_rgb()),
+            TerminalColor::Rgb { r, g, b } => Rgba::rgb(r, g, b),
+            named => self.ansi[named.ansi_index().unwrap_or(7) as usize],
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,4 +320,37 @@ mod tests {
         let custom = TerminalColor::Rgb { r: 255, g: 128, b: 0 }.to_rgb();
         assert_eq!(custom, [1.0, 0.5019608, 0.0, 1.0]);
     }
+    
+    #[test]
+    fn test_palette_resolve() {
+        let palette = Palette::default();
+        assert_eq!(palette.resolve(TerminalColor::Red), Rgba::rgb(204, 0, 0));
+        assert_eq!(palette.resolve(TerminalColor::Indexed(1)), palette.resolve(TerminalColor::Red));
+        assert_eq!(palette.resolve(TerminalColor::DefaultFg), Rgba::rgb(230, 230, 230));
+        assert_eq!(palette.resolve(TerminalColor::Rgb { r: 1, g: 2, b: 3 }), Rgba::rgb(1, 2, 3));
+        assert_eq!(Rgba::rgb(0x12, 0x34, 0x56).to_u32(), 0xFF123456);
+    }
+    
+    #[test]
+    fn test_packed_argb_round_trip() {
+        let palette = Palette::default();
+        assert_eq!(TerminalColor::Red.to_packed_argb(&palette), 0xFFCC0000);
+        
+        let color = TerminalColor::Rgb { r: 0x12, g: 0x34, b: 0x56 };
+        let packed = color.to_packed_argb(&palette);
+        assert_eq!(packed, 0xFF123456);
+        assert_eq!(Rgba::from_u32(packed), Rgba::rgb(0x12, 0x34, 0x56));
+    }
+    
+    #[test]
+    fn test_from_hex() {
+        assert_eq!(TerminalColor::from_hex("#ff8800"), Some(TerminalColor::Rgb { r: 0xff, g: 0x88, b: 0x00 }));
+        assert_eq!(TerminalColor::from_hex("#F80"), Some(TerminalColor::Rgb { r: 0xff, g: 0x88, b: 0x00 }));
+        assert_eq!(TerminalColor::from_hex("#000"), Some(TerminalColor::Rgb { r: 0, g: 0, b: 0 }));
+        
+        assert_eq!(TerminalColor::from_hex("ff8800"), None);
+        assert_eq!(TerminalColor::from_hex("#ff88"), None);
+        assert_eq!(TerminalColor::from_hex("#gg0000"), None);
+        assert_eq!(TerminalColor::from_hex("#+f+f+f"), None);
+    }
 }
@@ -0,0 +1,112 @@
+// CSI parameters read the way escape sequences define them: positional
+// values with per-sequence defaults, each with optional `:` sub-parameters
+
+/// The parameters of one CSI sequence. A parameter that is left out or
+/// given as 0 takes the sequence's default, as in xterm, so `CSI A`,
+/// `CSI 0 A` and `CSI 1 A` all move the cursor up one row.
+#[derive(Clone, Copy)]
+pub struct CsiParams<'a> {
+    params: &'a vte::Params,
+}
+
+impl<'a> CsiParams<'a> {
+    pub fn new(params: &'a vte::Params) -> Self {
+        Self { params }
+    }
+    
+    /// Number of `;`-separated parameters
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+    
+    /// Value of parameter `index`, `None` past the last one. An empty
+    /// parameter between separators (`CSI ;5 H`) reads as 0.
+    pub fn get(&self, index: usize) -> Option<u16> {
+        self.param(index).map(|param| param[0])
+    }
+    
+    /// Parameter `index`, or `default` when it is missing or 0
+    pub fn get_or(&self, index: usize, default: u16) -> u16 {
+        self.get(index).filter(|&value| value != 0).unwrap_or(default)
+    }
+    
+    /// The `:`-separated values after parameter `index`'s own (`4:3` gives
+    /// `[3]`); empty when it has none or doesn't exist
+    pub fn subparams(&self, index: usize) -> &'a [u16] {
+        self.param(index).map_or(&[], |param| &param[1..])
+    }
+    
+    /// Each parameter, its value followed by any sub-parameters
+    pub fn iter(&self) -> vte::ParamsIter<'a> {
+        self.params.iter()
+    }
+    
+    fn param(&self, index: usize) -> Option<&'a [u16]> {
+        self.params.iter().nth(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Hands the parameters of each CSI sequence to a check
+    struct Probe<F>(F, usize);
+    
+    impl<F: FnMut(CsiParams)> vte::Perform for Probe<F> {
+        fn csi_dispatch(&mut self, params: &vte::Params, _intermediates: &[u8], _ignore: bool, _c: char) {
+            (self.0)(CsiParams::new(params));
+            self.1 += 1;
+        }
+    }
+    
+    fn with_params(sequence: &[u8], check: impl FnMut(CsiParams)) {
+        let mut parser = vte::Parser::new();
+        let mut probe = Probe(check, 0);
+        for &byte in sequence {
+            parser.advance(&mut probe, byte);
+        }
+        assert_eq!(probe.1, 1, "expected one CSI sequence in {:?}", sequence);
+    }
+    
+    #[test]
+    fn test_missing_and_zero_params_take_default() {
+        with_params(b"\x1b[A", |params| assert_eq!(params.get_or(0, 1), 1));
+        with_params(b"\x1b[0A", |params| {
+            assert_eq!(params.get(0), Some(0));
+            assert_eq!(params.get_or(0, 1), 1);
+        });
+        // An empty first parameter still counts for the second's position
+        with_params(b"\x1b[;7H", |params| {
+            assert_eq!(params.len(), 2);
+            assert_eq!(params.get_or(0, 1), 1);
+            assert_eq!(params.get_or(1, 1), 7);
+        });
+    }
+    
+    #[test]
+    fn test_out_of_range_index() {
+        with_params(b"\x1b[3;4H", |params| {
+            assert_eq!((params.get(0), params.get(1)), (Some(3), Some(4)));
+            assert_eq!(params.get(2), None);
+            assert_eq!(params.get_or(9, 5), 5);
+            assert!(params.subparams(9).is_empty());
+        });
+    }
+    
+    #[test]
+    fn test_subparams() {
+        with_params(b"\x1b[4:3;38:2::10:20:30m", |params| {
+            assert_eq!(params.get(0), Some(4));
+            assert_eq!(params.subparams(0), [3]);
+            assert_eq!(params.get(1), Some(38));
+            assert_eq!(params.subparams(1), [2, 0, 10, 20, 30]);
+            assert_eq!(params.iter().count(), 2);
+        });
+        with_params(b"\x1b[1m", |params| assert!(params.subparams(0).is_empty()));
+    }
+}
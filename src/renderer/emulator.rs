@@ -0,0 +1,94 @@
+// Bytes-in, bytes-out terminal emulation, for conformance tests and embedding
+// without a PTY or window
+
+use crate::renderer::grid::TextGrid;
+use crate::renderer::parser::{TerminalEvent, TerminalParser};
+
+/// A `TerminalParser` that hands query replies straight back to the caller.
+/// Other events (bell, title, clipboard, ...) are queued for `drain_events`.
+pub struct TerminalEmulator {
+    parser: TerminalParser,
+    events: Vec<TerminalEvent>,
+}
+
+impl TerminalEmulator {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self::from_parser(TerminalParser::new(rows, cols))
+    }
+    
+    /// Wrap an already configured parser
+    pub fn from_parser(parser: TerminalParser) -> Self {
+        Self {
+            parser,
+            events: Vec::new(),
+        }
+    }
+    
+    /// Feed `input` as if read from the PTY and return the bytes the
+    /// terminal would write back (DSR, DA and other query replies), in order
+    pub fn process(&mut self, input: &[u8]) -> Vec<u8> {
+        self.parser.parse(input);
+        
+        let mut reply = Vec::new();
+        for event in self.parser.drain_events() {
+            match event {
+                TerminalEvent::Response(bytes) => reply.extend_from_slice(&bytes),
+                other => self.events.push(other),
+            }
+        }
+        reply
+    }
+    
+    /// Events other than replies raised since the last call
+    pub fn drain_events(&mut self) -> Vec<TerminalEvent> {
+        std::mem::take(&mut self.events)
+    }
+    
+    pub fn grid(&self) -> &TextGrid {
+        self.parser.grid()
+    }
+    
+    pub fn parser(&self) -> &TerminalParser {
+        &self.parser
+    }
+    
+    pub fn parser_mut(&mut self) -> &mut TerminalParser {
+        &mut self.parser
+    }
+    
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        self.parser.resize(rows, cols);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_cursor_report_after_output() {
+        let mut emulator = TerminalEmulator::new(24, 80);
+        
+        assert_eq!(emulator.process(b"hello\r\nworld"), b"");
+        assert_eq!(emulator.process(b"\x1b[6n"), b"\x1b[2;6R");
+        
+        // Replies come back in order, interleaved with grid updates
+        let reply = emulator.process(b"\x1b[10;20Hx\x1b[6n\x1b[5n");
+        assert_eq!(reply, b"\x1b[10;21R\x1b[0n");
+        assert_eq!(emulator.grid().cell_at(9, 19).unwrap().ch, 'x');
+        assert_eq!(emulator.grid().cursor_position(), (9, 20));
+    }
+    
+    #[test]
+    fn test_device_attributes_and_other_events() {
+        let mut emulator = TerminalEmulator::new(24, 80);
+        
+        assert_eq!(emulator.process(b"\x1b[c"), b"\x1b[?62;22c");
+        assert_eq!(emulator.process(b"\x1b]2;vttest\x07\x07"), b"");
+        assert_eq!(
+            emulator.drain_events(),
+            vec![TerminalEvent::TitleChanged("vttest".to_string()), TerminalEvent::Bell]
+        );
+        assert!(emulator.drain_events().is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+// Styled export of the visible screen, for HTML output and formatted AI context
+
+use std::fmt::Write;
+
+use crate::renderer::{Cell, CellAttributes, Palette, Rgba, TerminalColor, TextGrid};
+
+/// Consecutive cells of a row sharing the same colors and attributes
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyledRun {
+    pub text: String,
+    pub fg: TerminalColor,
+    pub bg: TerminalColor,
+    pub attrs: CellAttributes,
+}
+
+impl StyledRun {
+    fn from_cell(cell: &Cell) -> Self {
+        Self {
+            text: String::new(),
+            fg: cell.fg_color,
+            bg: cell.bg_color,
+            attrs: cell.attrs.clone(),
+        }
+    }
+    
+    fn matches(&self, cell: &Cell) -> bool {
+        self.fg == cell.fg_color && self.bg == cell.bg_color && self.attrs == cell.attrs
+    }
+    
+    fn is_plain(&self) -> bool {
+        self.fg == TerminalColor::DefaultFg
+            && self.bg == TerminalColor::DefaultBg
+            && self.attrs == CellAttributes::default()
+    }
+}
+
+impl TextGrid {
+    /// Visible rows as runs of identically styled text. Never-written cells at
+    /// the end of a row are dropped; blank cells inside a row become spaces.
+    pub fn styled_rows(&self) -> Vec<Vec<StyledRun>> {
+        (0..self.rows)
+            .filter_map(|row| self.row(row))
+            .map(|cells| {
+                let end = cells.iter().rposition(|cell| cell.ch != '\0').map_or(0, |i| i + 1);
+                let mut runs: Vec<StyledRun> = Vec::new();
+                
+                for cell in &cells[..end] {
+                    if !runs.last().is_some_and(|run| run.matches(cell)) {
+                        runs.push(StyledRun::from_cell(cell));
+                    }
+                    let ch = if cell.ch == '\0' { ' ' } else { cell.ch };
+                    runs.last_mut().unwrap().text.push(ch);
+                }
+                
+                runs
+            })
+            .collect()
+    }
+    
+    /// Visible screen as a `<pre>` block with one inline-styled `<span>` per run.
+    /// Runs in the default style are emitted as plain text.
+    pub fn to_html(&self, palette: &Palette) -> String {
+        let mut html = format!(
+            "<pre style=\"color:{};background-color:{}\">",
+            css_color(palette.default_fg),
+            css_color(palette.default_bg),
+        );
+        
+        for (index, runs) in self.styled_rows().iter().enumerate() {
+            if index > 0 {
+                html.push('\n');
+            }
+            for run in runs {
+                if run.is_plain() {
+                    html.push_str(&escape_html(&run.text));
+                } else {
+                    let _ = write!(html, "<span style=\"{}\">{}</span>", run_style(run, palette), escape_html(&run.text));
+                }
+            }
+        }
+        
+        html.push_str("</pre>");
+        html
+    }
+}
+
+fn run_style(run: &StyledRun, palette: &Palette) -> String {
+    let cell = Cell {
+        ch: ' ',
+        fg_color: run.fg,
+        bg_color: run.bg,
+        attrs: run.attrs.clone(),
+        extra: None,
+    };
+    let (fg, bg) = cell.resolved_colors(palette);
+    
+    let mut style = format!("color:{};background-color:{}", css_color(fg), css_color(bg));
+    if run.attrs.bold {
+        style.push_str(";font-weight:bold");
+    }
+    if run.attrs.italic {
+        style.push_str(";font-style:italic");
+    }
+    
+    let mut decorations = Vec::new();
+    if run.attrs.underline || run.attrs.double_underline {
+        decorations.push("underline");
+    }
+    if run.attrs.strikethrough {
+        decorations.push("line-through");
+    }
+    if !decorations.is_empty() {
+        let _ = write!(style, ";text-decoration:{}", decorations.join(" "));
+    }
+    if run.attrs.double_underline {
+        style.push_str(";text-decoration-style:double");
+    }
+    if run.attrs.underline || run.attrs.double_underline {
+        if let Some(color) = run.attrs.underline_color {
+            let _ = write!(style, ";text-decoration-color:{}", css_color(palette.resolve(color)));
+        }
+    }
+    
+    style
+}
+
+fn css_color(color: Rgba) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn grid_with_colored_segment() -> TextGrid {
+        let mut grid = TextGrid::new(2, 20);
+        grid.set_fg_color(TerminalColor::DefaultFg);
+        grid.set_bg_color(TerminalColor::DefaultBg);
+        grid.write_str("ok ");
+        grid.set_fg_color(TerminalColor::Red);
+        grid.set_attrs(CellAttributes { bold: true, ..Default::default() });
+        grid.write_str("<err>");
+        grid.set_fg_color(TerminalColor::DefaultFg);
+        grid.set_attrs(CellAttributes::default());
+        grid.write_str(" done");
+        grid
+    }
+    
+    #[test]
+    fn test_styled_rows_coalesce_runs() {
+        let rows = grid_with_colored_segment().styled_rows();
+        assert_eq!(rows.len(), 2);
+        
+        let texts: Vec<&str> = rows[0].iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, ["ok ", "<err>", " done"]);
+        assert_eq!(rows[0][1].fg, TerminalColor::Red);
+        assert!(rows[0][1].attrs.bold);
+        assert_eq!(rows[0][2].fg, TerminalColor::DefaultFg);
+        
+        // Never-written rows have no runs
+        assert!(rows[1].is_empty());
+    }
+    
+    #[test]
+    fn test_to_html() {
+        let palette = Palette::default();
+        let html = grid_with_colored_segment().to_html(&palette);
+        
+        let expected = format!(
+            "<pre style=\"color:{fg};background-color:{bg}\">ok <span style=\"color:#cc0000;background-color:{bg};font-weight:bold\">&lt;err&gt;</span> done\n</pre>",
+            fg = css_color(palette.default_fg),
+            bg = css_color(palette.default_bg),
+        );
+        assert_eq!(html, expected);
+    }
+}
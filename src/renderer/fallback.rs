@@ -0,0 +1,255 @@
+// Glyph lookup across the primary font and configured fallbacks, with box
+// drawing characters drawn as lines when no font has them
+
+use std::path::PathBuf;
+
+use fontdue::{Font, FontSettings, Metrics};
+
+use crate::renderer::RenderError;
+
+/// Something glyphs are rasterized from: a `fontdue::Font`, or a stub in tests
+pub trait GlyphSource {
+    fn has_glyph(&self, ch: char) -> bool;
+    
+    /// Coverage bitmap of `ch` at `px` pixels, row-major, one byte per pixel
+    fn rasterize(&self, ch: char, px: f32) -> (Metrics, Vec<u8>);
+}
+
+impl GlyphSource for Font {
+    fn has_glyph(&self, ch: char) -> bool {
+        self.lookup_glyph_index(ch) != 0
+    }
+    
+    fn rasterize(&self, ch: char, px: f32) -> (Metrics, Vec<u8>) {
+        Font::rasterize(self, ch, px)
+    }
+}
+
+/// The primary font followed by fallbacks, tried in order for glyphs the
+/// primary font lacks (powerline symbols, box drawing, CJK, ...)
+pub struct FontChain<F = Font> {
+    primary: F,
+    fallbacks: Vec<F>,
+}
+
+impl<F: GlyphSource> FontChain<F> {
+    pub fn new(primary: F) -> Self {
+        Self {
+            primary,
+            fallbacks: Vec::new(),
+        }
+    }
+    
+    pub fn with_fallbacks(mut self, fallbacks: Vec<F>) -> Self {
+        self.fallbacks = fallbacks;
+        self
+    }
+    
+    pub fn set_fallbacks(&mut self, fallbacks: Vec<F>) {
+        self.fallbacks = fallbacks;
+    }
+    
+    /// The font cell metrics are measured from
+    pub fn primary(&self) -> &F {
+        &self.primary
+    }
+    
+    /// The first font in the chain with a glyph for `ch`
+    pub fn font_for(&self, ch: char) -> Option<&F> {
+        std::iter::once(&self.primary)
+            .chain(&self.fallbacks)
+            .find(|font| font.has_glyph(ch))
+    }
+    
+    /// Rasterize `ch` from the first font that has it, or the primary
+    /// font's missing-glyph box when none does
+    pub fn rasterize(&self, ch: char, px: f32) -> (Metrics, Vec<u8>) {
+        self.font_for(ch).unwrap_or(&self.primary).rasterize(ch, px)
+    }
+}
+
+/// Parse the font files at `paths`, in order
+pub fn load_fonts(paths: &[PathBuf]) -> Result<Vec<Font>, RenderError> {
+    paths
+        .iter()
+        .map(|path| {
+            let data = std::fs::read(path)
+                .map_err(|e| RenderError::Font(format!("Failed to read fallback font {}: {}", path.display(), e)))?;
+            Font::from_bytes(data, FontSettings::default())
+                .map_err(|e| RenderError::Font(format!("Failed to load fallback font {}: {}", path.display(), e)))
+        })
+        .collect()
+}
+
+/// Line weight of one arm of a box drawing character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+}
+
+/// Arms `(up, right, down, left)` of the box drawing characters drawn as lines
+fn box_arms(ch: char) -> Option<[Weight; 4]> {
+    use Weight::{Heavy as H, Light as L, None as N};
+    let arms = match ch {
+        '─' => [N, L, N, L],
+        '━' => [N, H, N, H],
+        '│' => [L, N, L, N],
+        '┃' => [H, N, H, N],
+        '┌' | '╭' => [N, L, L, N],
+        '┏' => [N, H, H, N],
+        '┐' | '╮' => [N, N, L, L],
+        '┓' => [N, N, H, H],
+        '└' | '╰' => [L, L, N, N],
+        '┗' => [H, H, N, N],
+        '┘' | '╯' => [L, N, N, L],
+        '┛' => [H, N, N, H],
+        '├' => [L, L, L, N],
+        '┣' => [H, H, H, N],
+        '┤' => [L, N, L, L],
+        '┫' => [H, N, H, H],
+        '┬' => [N, L, L, L],
+        '┳' => [N, H, H, H],
+        '┴' => [L, L, N, L],
+        '┻' => [H, H, N, H],
+        '┼' => [L, L, L, L],
+        '╋' => [H, H, H, H],
+        '╴' => [N, N, N, L],
+        '╵' => [L, N, N, N],
+        '╶' => [N, L, N, N],
+        '╷' => [N, N, L, N],
+        _ => return None,
+    };
+    Some(arms)
+}
+
+/// Block elements drawn as a filled `(x0, y0, x1, y1)` fraction of the cell
+fn block_fraction(ch: char) -> Option<(f32, f32, f32, f32)> {
+    match ch {
+        '█' => Some((0.0, 0.0, 1.0, 1.0)),
+        '▀' => Some((0.0, 0.0, 1.0, 0.5)),
+        '▄' => Some((0.0, 0.5, 1.0, 1.0)),
+        '▌' => Some((0.0, 0.0, 0.5, 1.0)),
+        '▐' => Some((0.5, 0.0, 1.0, 1.0)),
+        _ => None,
+    }
+}
+
+/// A `width` x `height` coverage bitmap of box drawing or block character
+/// `ch` filling the whole cell, so lines join across neighbouring cells.
+/// `None` for characters not drawn procedurally.
+pub fn box_drawing_bitmap(ch: char, width: usize, height: usize) -> Option<Vec<u8>> {
+    let mut bitmap = vec![0u8; width * height];
+    let mut fill = |x0: usize, y0: usize, x1: usize, y1: usize| {
+        for y in y0..y1.min(height) {
+            for x in x0..x1.min(width) {
+                bitmap[y * width + x] = 255;
+            }
+        }
+    };
+    
+    if let Some((x0, y0, x1, y1)) = block_fraction(ch) {
+        let scale = |fraction: f32, size: usize| (fraction * size as f32).round() as usize;
+        fill(scale(x0, width), scale(y0, height), scale(x1, width), scale(y1, height));
+        return Some(bitmap);
+    }
+    
+    let [up, right, down, left] = box_arms(ch)?;
+    let light = (width / 8).max(1);
+    let thickness = |weight| match weight {
+        Weight::None => 0,
+        Weight::Light => light,
+        Weight::Heavy => light * 2,
+    };
+    // Each stroke is a band centred on the cell's middle; arms reach
+    // through the widest band crossing the middle so corners close
+    let band = |center: usize, size: usize| {
+        let start = center.saturating_sub(size / 2);
+        (start, start + size)
+    };
+    let widest = [up, right, down, left].into_iter().map(thickness).max().unwrap_or(0);
+    let (cx, cy) = (width / 2, height / 2);
+    let (reach_x0, reach_x1) = band(cx, widest);
+    let (reach_y0, reach_y1) = band(cy, widest);
+    
+    if left != Weight::None {
+        let (y0, y1) = band(cy, thickness(left));
+        fill(0, y0, reach_x1, y1);
+    }
+    if right != Weight::None {
+        let (y0, y1) = band(cy, thickness(right));
+        fill(reach_x0, y0, width, y1);
+    }
+    if up != Weight::None {
+        let (x0, x1) = band(cx, thickness(up));
+        fill(x0, 0, x1, reach_y1);
+    }
+    if down != Weight::None {
+        let (x0, x1) = band(cx, thickness(down));
+        fill(x0, reach_y0, x1, height);
+    }
+    Some(bitmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// A font with glyphs for `chars` only, rasterizing each as a 1x1
+    /// bitmap holding `id` so tests can tell which font drew it
+    struct StubFont {
+        chars: &'static str,
+        id: u8,
+    }
+    
+    impl GlyphSource for StubFont {
+        fn has_glyph(&self, ch: char) -> bool {
+            self.chars.contains(ch)
+        }
+        
+        fn rasterize(&self, _ch: char, _px: f32) -> (Metrics, Vec<u8>) {
+            let metrics = Metrics { width: 1, height: 1, ..Metrics::default() };
+            (metrics, vec![self.id])
+        }
+    }
+    
+    #[test]
+    fn test_missing_glyph_comes_from_fallback() {
+        let chain = FontChain::new(StubFont { chars: "abc", id: 1 })
+            .with_fallbacks(vec![StubFont { chars: "\u{e0b0}", id: 2 }, StubFont { chars: "\u{e0b0}λ", id: 3 }]);
+        
+        assert_eq!(chain.rasterize('a', 16.0).1, vec![1]);
+        // Powerline arrow: absent from the primary, taken from the first fallback with it
+        assert_eq!(chain.rasterize('\u{e0b0}', 16.0).1, vec![2]);
+        assert_eq!(chain.rasterize('λ', 16.0).1, vec![3]);
+        // No font has it: the primary font draws its missing-glyph box
+        assert!(chain.font_for('字').is_none());
+        assert_eq!(chain.rasterize('字', 16.0).1, vec![1]);
+    }
+    
+    #[test]
+    fn test_box_drawing_fills_to_cell_edges() {
+        let (width, height) = (8, 16);
+        let lit = |bitmap: &[u8], x: usize, y: usize| bitmap[y * width + x] == 255;
+        
+        let horizontal = box_drawing_bitmap('─', width, height).unwrap();
+        assert!(lit(&horizontal, 0, 8) && lit(&horizontal, 7, 8));
+        assert!(!lit(&horizontal, 4, 0) && !lit(&horizontal, 4, 15));
+        
+        let cross = box_drawing_bitmap('┼', width, height).unwrap();
+        for (x, y) in [(0, 8), (7, 8), (4, 0), (4, 15)] {
+            assert!(lit(&cross, x, y), "({}, {})", x, y);
+        }
+        
+        // A corner joins without a notch and leaves the other quadrants empty
+        let corner = box_drawing_bitmap('┌', width, height).unwrap();
+        assert!(lit(&corner, 4, 8) && lit(&corner, 7, 8) && lit(&corner, 4, 15));
+        assert!(!lit(&corner, 0, 8) && !lit(&corner, 4, 0));
+        
+        let upper = box_drawing_bitmap('▀', width, height).unwrap();
+        assert!(lit(&upper, 0, 0) && !lit(&upper, 0, 8));
+        
+        assert!(box_drawing_bitmap('a', width, height).is_none());
+    }
+}
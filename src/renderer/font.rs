@@ -0,0 +1,89 @@
+// Font sizing shared by both renderers, including the range runtime zoom may use
+
+/// Font size both renderers start with, in pixels
+pub const DEFAULT_FONT_SIZE: f32 = 16.0;
+
+/// Largest font size either renderer accepts, whatever the config says
+pub const MAX_FONT_SIZE: f32 = 128.0;
+
+/// How much one zoom keypress changes the font size
+pub const FONT_SIZE_STEP: f32 = 1.0;
+
+/// Smallest and largest font size runtime zoom may pick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontSizeLimits {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl FontSizeLimits {
+    /// Limits from `min` to `max`; swapped if given in the wrong order and
+    /// kept between 1px and `MAX_FONT_SIZE`
+    pub fn new(min: f32, max: f32) -> Self {
+        let (min, max) = if min <= max { (min, max) } else { (max, min) };
+        let bound = |size: f32| if size.is_nan() { 1.0 } else { size.clamp(1.0, MAX_FONT_SIZE) };
+        Self {
+            min: bound(min),
+            max: bound(max),
+        }
+    }
+    
+    pub fn clamp(&self, size: f32) -> f32 {
+        if size.is_nan() {
+            return DEFAULT_FONT_SIZE.clamp(self.min, self.max);
+        }
+        size.clamp(self.min, self.max)
+    }
+}
+
+impl Default for FontSizeLimits {
+    fn default() -> Self {
+        Self { min: 8.0, max: 32.0 }
+    }
+}
+
+/// A font zoom step, as bound by `KeyAction::zoom`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zoom {
+    In,
+    Out,
+    Reset,
+}
+
+impl Zoom {
+    /// Font size after zooming from `current`, before the renderer clamps it
+    pub fn apply(self, current: f32) -> f32 {
+        match self {
+            Self::In => current + FONT_SIZE_STEP,
+            Self::Out => current - FONT_SIZE_STEP,
+            Self::Reset => DEFAULT_FONT_SIZE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_font_size_limits_clamp() {
+        let limits = FontSizeLimits::default();
+        assert_eq!(limits.clamp(DEFAULT_FONT_SIZE), DEFAULT_FONT_SIZE);
+        assert_eq!(limits.clamp(2.0), 8.0);
+        assert_eq!(limits.clamp(100.0), 32.0);
+        assert_eq!(limits.clamp(f32::NAN), DEFAULT_FONT_SIZE);
+        
+        let swapped = FontSizeLimits::new(20.0, 10.0);
+        assert_eq!((swapped.min, swapped.max), (10.0, 20.0));
+        assert_eq!(swapped.clamp(f32::NAN), 16.0);
+        assert_eq!(FontSizeLimits::new(-4.0, 0.0).clamp(12.0), 1.0);
+        assert_eq!(FontSizeLimits::new(8.0, 10_000.0).clamp(500.0), MAX_FONT_SIZE);
+    }
+    
+    #[test]
+    fn test_zoom_apply() {
+        assert_eq!(Zoom::In.apply(16.0), 17.0);
+        assert_eq!(Zoom::Out.apply(16.0), 15.0);
+        assert_eq!(Zoom::Reset.apply(30.0), DEFAULT_FONT_SIZE);
+    }
+}
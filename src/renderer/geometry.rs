@@ -0,0 +1,66 @@
+// Mapping between window pixels and grid cells, shared by both renderers
+
+/// Layout of the cell grid inside the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellGeometry {
+    /// Top-left pixel of the first cell (the window padding)
+    pub origin_x: u32,
+    pub origin_y: u32,
+    pub cell_width: u32,
+    pub cell_height: u32,
+    pub rows: u32,
+    pub cols: u32,
+}
+
+impl CellGeometry {
+    /// Grid `(row, col)` under the pixel, or `None` in the padding or past the last cell
+    pub fn cell_at_pixel(&self, x: u32, y: u32) -> Option<(u16, u16)> {
+        if self.cell_width == 0 || self.cell_height == 0 {
+            return None;
+        }
+        
+        let col = x.checked_sub(self.origin_x)? / self.cell_width;
+        let row = y.checked_sub(self.origin_y)? / self.cell_height;
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        
+        Some((u16::try_from(row).ok()?, u16::try_from(col).ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn padded_geometry() -> CellGeometry {
+        CellGeometry {
+            origin_x: 8,
+            origin_y: 8,
+            cell_width: 12,
+            cell_height: 20,
+            rows: 24,
+            cols: 80,
+        }
+    }
+    
+    #[test]
+    fn test_cell_at_pixel() {
+        let geometry = padded_geometry();
+        assert_eq!(geometry.cell_at_pixel(8, 8), Some((0, 0)));
+        assert_eq!(geometry.cell_at_pixel(19, 27), Some((0, 0)));
+        assert_eq!(geometry.cell_at_pixel(20, 28), Some((1, 1)));
+        assert_eq!(geometry.cell_at_pixel(8 + 79 * 12, 8 + 23 * 20), Some((23, 79)));
+    }
+    
+    #[test]
+    fn test_cell_at_pixel_outside_grid() {
+        let geometry = padded_geometry();
+        // Padding on the top and left
+        assert_eq!(geometry.cell_at_pixel(7, 30), None);
+        assert_eq!(geometry.cell_at_pixel(30, 0), None);
+        // Past the last column and row
+        assert_eq!(geometry.cell_at_pixel(8 + 80 * 12, 30), None);
+        assert_eq!(geometry.cell_at_pixel(30, 8 + 24 * 20), None);
+    }
+}
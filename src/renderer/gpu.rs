@@ -3,8 +3,15 @@
 use thiserror::Error;
 use wgpu::util::DeviceExt;
 use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
 use fontdue::{Font, FontSettings};
 
+use crate::renderer::{LineAttr, CellGeometry, Renderer, Rgba};
+use crate::renderer::bell::bell_tint;
+use crate::renderer::fallback::{box_drawing_bitmap, load_fonts, FontChain, GlyphSource};
+use crate::renderer::font::{FontSizeLimits, DEFAULT_FONT_SIZE};
+
 #[derive(Error, Debug)]
 pub enum RenderError {
     #[error("GPU initialization failed: {0}")]
@@ -21,7 +28,7 @@ pub enum RenderError {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 struct Vertex {
     position: [f32; 3],
     tex_coords: [f32; 2],
@@ -54,6 +61,36 @@ impl Vertex {
     }
 }
 
+/// Glyph cells per atlas row and rows in the atlas. Printable ASCII fills
+/// the first six rows up front; other characters take the next free cell
+/// the first time they are drawn.
+const ATLAS_COLUMNS: u32 = 16;
+const ATLAS_ROWS: u32 = 32;
+
+/// Atlas cell size in pixels at `font_size`: 32x64 at the default size, scaled with it
+fn atlas_cell_size(font_size: f32) -> (u32, u32) {
+    let scale = font_size / DEFAULT_FONT_SIZE;
+    (((32.0 * scale).ceil() as u32).max(1), ((64.0 * scale).ceil() as u32).max(1))
+}
+
+/// Largest font size whose atlas fits in a `max_dimension` texture
+fn max_atlas_font_size(max_dimension: u32) -> f32 {
+    // Cells are twice as tall as wide and the atlas has more rows than columns
+    let cell_height = max_dimension / ATLAS_ROWS;
+    (cell_height as f32 / 64.0 * DEFAULT_FONT_SIZE).floor().max(1.0)
+}
+
+/// Distinct characters of `lines` missing from `char_map`, in order of appearance
+fn missing_glyphs(lines: &[String], char_map: &HashMap<char, (f32, f32, f32, f32)>) -> Vec<char> {
+    let mut missing = Vec::new();
+    for ch in lines.iter().flat_map(|line| line.chars()) {
+        if !char_map.contains_key(&ch) && !missing.contains(&ch) {
+            missing.push(ch);
+        }
+    }
+    missing
+}
+
 struct FontAtlas {
     texture: wgpu::Texture,
     texture_view: wgpu::TextureView,
@@ -62,6 +99,95 @@ struct FontAtlas {
     char_map: HashMap<char, (f32, f32, f32, f32)>, // (u, v, width, height) in normalized coords
     char_width: f32,
     char_height: f32,
+    next_slot: u32,
+}
+
+impl FontAtlas {
+    fn free_slots(&self) -> usize {
+        (ATLAS_COLUMNS * ATLAS_ROWS - self.next_slot) as usize
+    }
+    
+    /// Drop every glyph added after printable ASCII, freeing their cells
+    fn evict_non_ascii(&mut self) {
+        self.char_map.retain(|ch, _| ch.is_ascii());
+        // ASCII was added first, so it holds exactly the leading cells
+        self.next_slot = self.char_map.len() as u32;
+    }
+    
+    /// Rasterize `ch` through `fonts` into the next free cell and upload
+    /// it. Returns `false` once every cell is taken.
+    fn add_glyph(&mut self, queue: &wgpu::Queue, fonts: &FontChain, font_size: f32, ch: char) -> bool {
+        if self.next_slot >= ATLAS_COLUMNS * ATLAS_ROWS {
+            return false;
+        }
+        
+        let (cell_width, cell_height) = (self.char_width as u32, self.char_height as u32);
+        let ((x, y), uv) = atlas_slot(self.next_slot, cell_width, cell_height);
+        let cell = glyph_cell(fonts, ch, font_size, cell_width, cell_height);
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+            },
+            &cell,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * cell_width),
+                rows_per_image: Some(cell_height),
+            },
+            wgpu::Extent3d {
+                width: cell_width,
+                height: cell_height,
+                depth_or_array_layers: 1,
+            },
+        );
+        
+        self.char_map.insert(ch, uv);
+        self.next_slot += 1;
+        true
+    }
+}
+
+/// Top-left texel of atlas cell `slot` and its (u, v, width, height) in normalized coords
+fn atlas_slot(slot: u32, cell_width: u32, cell_height: u32) -> ((u32, u32), (f32, f32, f32, f32)) {
+    let x = slot % ATLAS_COLUMNS * cell_width;
+    let y = slot / ATLAS_COLUMNS * cell_height;
+    let (atlas_width, atlas_height) = ((cell_width * ATLAS_COLUMNS) as f32, (cell_height * ATLAS_ROWS) as f32);
+    let uv = (
+        x as f32 / atlas_width,
+        y as f32 / atlas_height,
+        cell_width as f32 / atlas_width,
+        cell_height as f32 / atlas_height,
+    );
+    ((x, y), uv)
+}
+
+/// White RGBA texels for one atlas cell holding `ch`, coverage in alpha.
+/// Box drawing no font covers fills the cell as the software renderer
+/// draws it; anything else is the chain's glyph centred in the cell.
+fn glyph_cell<F: GlyphSource>(fonts: &FontChain<F>, ch: char, font_size: f32, cell_width: u32, cell_height: u32) -> Vec<u8> {
+    let (width, height) = (cell_width as usize, cell_height as usize);
+    let mut alpha = vec![0u8; width * height];
+    
+    match box_drawing_bitmap(ch, width, height).filter(|_| fonts.font_for(ch).is_none()) {
+        Some(bitmap) => alpha = bitmap,
+        None => {
+            let (metrics, bitmap) = fonts.rasterize(ch, font_size);
+            let left = width.saturating_sub(metrics.width) / 2;
+            let top = height.saturating_sub(metrics.height) / 2;
+            for y in 0..metrics.height.min(height - top) {
+                for x in 0..metrics.width.min(width - left) {
+                    if let Some(&coverage) = bitmap.get(y * metrics.width + x) {
+                        alpha[(top + y) * width + left + x] = coverage;
+                    }
+                }
+            }
+        }
+    }
+    
+    alpha.into_iter().flat_map(|a| [255, 255, 255, a]).collect()
 }
 
 pub struct GpuRenderer {
@@ -72,12 +198,16 @@ pub struct GpuRenderer {
     size: winit::dpi::PhysicalSize<u32>,
     
     render_pipeline: wgpu::RenderPipeline,
+    fonts: FontChain,
+    font_size: f32,
+    font_size_limits: FontSizeLimits,
     font_atlas: FontAtlas,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
-    
-    vertices: Vec<Vertex>,
-    indices: Vec<u16>,
+    // Quads of the last frame, reused for rows that didn't change
+    geometry: FrameGeometry,
+    // Visual bell strength, applied to the background; 0.0 when not flashing
+    bell_flash: f32,
 }
 
 impl GpuRenderer {
@@ -142,7 +272,9 @@ impl GpuRenderer {
         surface.configure(&device, &config);
         
         // Create font atlas
-        let font_atlas = Self::create_font_atlas(&device, &queue)?;
+        let fonts = FontChain::new(Self::load_font()?);
+        let font_size = DEFAULT_FONT_SIZE;
+        let font_atlas = Self::create_font_atlas(&device, &queue, &fonts, font_size)?;
         
         // Create shader
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -219,20 +351,17 @@ impl GpuRenderer {
             multiview: None,
         });
         
-        // Create initial buffers
-        let vertices = Vec::new();
-        let indices = Vec::new();
-        
+        // Create initial buffers, grown once the first frame's geometry is known
         let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Vertex Buffer"),
-            size: (vertices.len() * std::mem::size_of::<Vertex>()).max(64) as u64,
+            size: 64,
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
         
         let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Index Buffer"),
-            size: (indices.len() * std::mem::size_of::<u16>()).max(64) as u64,
+            size: 64,
             usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -244,18 +373,19 @@ impl GpuRenderer {
             config,
             size,
             render_pipeline,
+            fonts,
+            font_size,
+            font_size_limits: FontSizeLimits::default(),
             font_atlas,
             vertex_buffer,
             index_buffer,
-            vertices,
-            indices,
+            geometry: FrameGeometry::default(),
+            bell_flash: 0.0,
         })
     }
     
-    fn create_font_atlas(device: &wgpu::Device, queue: &wgpu::Queue) -> Result<FontAtlas, RenderError> {
-        // Load system monospace font for terminal rendering
-        tracing::info!("🔤 Starting font atlas creation...");
-        
+    /// Load the system monospace font used for terminal rendering
+    fn load_font() -> Result<Font, RenderError> {
         let font_data = Self::load_system_font()?
             .or_else(|| Self::load_fallback_font())
             .ok_or_else(|| RenderError::Font("No suitable font found".to_string()))?;
@@ -266,79 +396,28 @@ impl GpuRenderer {
             .map_err(|e| RenderError::Font(format!("Failed to load font: {}", e)))?;
             
         tracing::info!("✅ Font parsed successfully");
+        Ok(font)
+    }
+    
+    fn create_font_atlas(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        fonts: &FontChain,
+        font_size: f32,
+    ) -> Result<FontAtlas, RenderError> {
+        tracing::info!("🔤 Starting font atlas creation at {}px...", font_size);
         
-        const ATLAS_SIZE: u32 = 512;
-        const FONT_SIZE: f32 = 16.0;
-        const CHARS_PER_ROW: u32 = 16; // 16x8 grid for 96 printable ASCII chars
-        
-        // Create texture data - RGBA format
-        let mut texture_data = vec![0u8; (ATLAS_SIZE * ATLAS_SIZE * 4) as usize];
-        let mut char_map = HashMap::new();
-        
-        // Calculate character cell size - make cells more square
-        let cell_width = ATLAS_SIZE / CHARS_PER_ROW;  // 32 pixels
-        let cell_height = ATLAS_SIZE / 8; // 64 pixels (8 rows instead of 6)
-        
-        // Generate font atlas with actual glyphs
-        tracing::info!("🖼️  Creating font atlas: {}x{} pixels, cell size: {}x{}", ATLAS_SIZE, ATLAS_SIZE, cell_width, cell_height);
-        
-        let mut chars_processed = 0;
-        for c in 32u8..127u8 { // ASCII printable characters
-            let char_idx = (c - 32) as u32;
-            let row = char_idx / CHARS_PER_ROW;
-            let col = char_idx % CHARS_PER_ROW;
-            
-            let start_x = col * cell_width;
-            let start_y = row * cell_height;
-            
-            // Rasterize the character using fontdue
-            let (metrics, bitmap) = font.rasterize(c as char, FONT_SIZE);
-            
-            if chars_processed < 5 {
-                tracing::debug!("  Char '{}' ({}): metrics {}x{}, bitmap {} bytes", c as char, c, metrics.width, metrics.height, bitmap.len());
-            }
-            chars_processed += 1;
-            
-            // Copy glyph bitmap to atlas
-            for y in 0..metrics.height {
-                for x in 0..metrics.width {
-                    let src_idx = y * metrics.width + x;
-                    if src_idx < bitmap.len() {
-                        let atlas_x = start_x + x as u32 + (cell_width - metrics.width as u32) / 2;
-                        let atlas_y = start_y + y as u32 + (cell_height - metrics.height as u32) / 2;
-                        
-                        if atlas_x < ATLAS_SIZE && atlas_y < ATLAS_SIZE {
-                            let dst_idx = ((atlas_y * ATLAS_SIZE + atlas_x) * 4) as usize;
-                            
-                            if dst_idx + 3 < texture_data.len() {
-                                let alpha = bitmap[src_idx];
-                                texture_data[dst_idx] = 255;     // R
-                                texture_data[dst_idx + 1] = 255; // G
-                                texture_data[dst_idx + 2] = 255; // B
-                                texture_data[dst_idx + 3] = alpha; // A
-                            }
-                        }
-                    }
-                }
-            }
-            
-            // Store character UV coordinates
-            let u = start_x as f32 / ATLAS_SIZE as f32;
-            let v = start_y as f32 / ATLAS_SIZE as f32;
-            let w = cell_width as f32 / ATLAS_SIZE as f32;
-            let h = cell_height as f32 / ATLAS_SIZE as f32;
-            
-            char_map.insert(c as char, (u, v, w, h));
-        }
+        let (cell_width, cell_height) = atlas_cell_size(font_size);
+        let atlas_width = cell_width * ATLAS_COLUMNS;
+        let atlas_height = cell_height * ATLAS_ROWS;
         
-        tracing::info!("✅ Font atlas created with {} characters", chars_processed);
-        tracing::debug!("📊 Character map contains {} entries", char_map.len());
+        tracing::info!("🖼️  Creating font atlas: {}x{} pixels, cell size: {}x{}", atlas_width, atlas_height, cell_width, cell_height);
         
         // Create texture
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
-                width: ATLAS_SIZE,
-                height: ATLAS_SIZE,
+                width: atlas_width,
+                height: atlas_height,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
@@ -350,26 +429,6 @@ impl GpuRenderer {
             view_formats: &[],
         });
         
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &texture_data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * ATLAS_SIZE),
-                rows_per_image: Some(ATLAS_SIZE),
-            },
-            wgpu::Extent3d {
-                width: ATLAS_SIZE,
-                height: ATLAS_SIZE,
-                depth_or_array_layers: 1,
-            },
-        );
-        
         let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -418,15 +477,24 @@ impl GpuRenderer {
             label: Some("diffuse_bind_group"),
         });
         
-        Ok(FontAtlas {
+        let mut atlas = FontAtlas {
             texture,
             texture_view,
             sampler,
             bind_group,
-            char_map,
+            char_map: HashMap::new(),
             char_width: cell_width as f32,
             char_height: cell_height as f32,
-        })
+            next_slot: 0,
+        };
+        
+        // Printable ASCII up front; everything else is added when first drawn
+        for c in 32u8..127u8 {
+            atlas.add_glyph(queue, fonts, font_size, c as char);
+        }
+        
+        tracing::info!("✅ Font atlas created with {} characters", atlas.char_map.len());
+        Ok(atlas)
     }
     
     fn load_system_font() -> Result<Option<Vec<u8>>, RenderError> {
@@ -461,87 +529,76 @@ impl GpuRenderer {
     }
     
     pub fn render_text(&mut self, _text: &str, lines: &[String]) -> Result<(), RenderError> {
-        self.vertices.clear();
-        self.indices.clear();
-        
-        tracing::debug!("🔤 render_text called with {} lines", lines.len());
-        
-        let screen_width = self.size.width as f32;
-        let screen_height = self.size.height as f32;
-        
-        let char_width_screen = self.font_atlas.char_width / screen_width * 2.0;
-        let char_height_screen = self.font_atlas.char_height / screen_height * 2.0;
-        
-        let mut vertex_count = 0u16;
+        self.update_geometry(lines, &[]);
+        Ok(())
+    }
+    
+    /// Cell size in normalized device coordinates at the current window size
+    fn glyph_layout(&self) -> GlyphLayout {
+        GlyphLayout {
+            char_width: self.font_atlas.char_width / self.size.width as f32 * 2.0,
+            char_height: self.font_atlas.char_height / self.size.height as f32 * 2.0,
+        }
+    }
+    
+    /// Re-tessellate the rows of `lines` that changed since the last frame
+    /// and upload only what differs from the buffers already on the GPU
+    fn update_geometry(&mut self, lines: &[String], line_attrs: &[LineAttr]) {
+        let layout = self.glyph_layout();
+        let change = self.geometry.update(lines, line_attrs, layout, &self.font_atlas.char_map);
+        tracing::debug!("📊 {} vertices, {} indices ({:?})", self.geometry.vertices.len(), self.geometry.indices.len(), change);
         
-        for (line_idx, line) in lines.iter().enumerate() {
-            // Start from top of screen and move down
-            let y = 1.0 - (line_idx as f32 + 1.0) * char_height_screen;
-            
-            for (char_idx, ch) in line.chars().enumerate() {
-                if let Some(&(u, v, w, h)) = self.font_atlas.char_map.get(&ch) {
-                    let x = -1.0 + char_idx as f32 * char_width_screen;
-                    
-                    if line_idx == 0 && char_idx < 5 {
-                        tracing::debug!("  Rendering char '{}' at ({:.3}, {:.3}) with UV ({:.3}, {:.3}, {:.3}, {:.3})", ch, x, y, u, v, w, h);
-                    }
-                    
-                    // Create quad for character
-                    let vertices = [
-                        Vertex {
-                            position: [x, y, 0.0],
-                            tex_coords: [u, v],
-                            color: [1.0, 1.0, 1.0, 1.0],
-                        },
-                        Vertex {
-                            position: [x + char_width_screen, y, 0.0],
-                            tex_coords: [u + w, v],
-                            color: [1.0, 1.0, 1.0, 1.0],
-                        },
-                        Vertex {
-                            position: [x + char_width_screen, y - char_height_screen, 0.0],
-                            tex_coords: [u + w, v + h],
-                            color: [1.0, 1.0, 1.0, 1.0],
-                        },
-                        Vertex {
-                            position: [x, y - char_height_screen, 0.0],
-                            tex_coords: [u, v + h],
-                            color: [1.0, 1.0, 1.0, 1.0],
-                        },
-                    ];
-                    
-                    let indices = [
-                        vertex_count, vertex_count + 1, vertex_count + 2,
-                        vertex_count, vertex_count + 2, vertex_count + 3,
-                    ];
-                    
-                    self.vertices.extend_from_slice(&vertices);
-                    self.indices.extend_from_slice(&indices);
-                    vertex_count += 4;
+        match change {
+            GeometryChange::Unchanged => {}
+            GeometryChange::Ranges(ranges) => {
+                let vertex_size = std::mem::size_of::<Vertex>();
+                for range in ranges {
+                    let offset = (range.start * vertex_size) as wgpu::BufferAddress;
+                    self.queue.write_buffer(&self.vertex_buffer, offset, bytemuck::cast_slice(&self.geometry.vertices[range]));
+                }
+            }
+            GeometryChange::All if self.geometry.vertices.is_empty() => {}
+            GeometryChange::All => {
+                let vertices = &self.geometry.vertices;
+                // Recreate the vertex buffer if it is too small
+                if std::mem::size_of_val(vertices.as_slice()) > self.vertex_buffer.size() as usize {
+                    self.vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Vertex Buffer"),
+                        contents: bytemuck::cast_slice(vertices),
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    });
                 } else {
-                    if line_idx == 0 && char_idx < 5 {
-                        tracing::debug!("  ❌ No mapping found for char '{}' (code: {})", ch, ch as u32);
-                    }
+                    self.queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
+                }
+                
+                // Recreate the index buffer if it is too small
+                let indices = &self.geometry.indices;
+                if std::mem::size_of_val(indices.as_slice()) > self.index_buffer.size() as usize {
+                    self.index_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Index Buffer"),
+                        contents: bytemuck::cast_slice(indices),
+                        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+                    });
+                } else {
+                    self.queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(indices));
                 }
             }
         }
-        
-        tracing::debug!("📊 Generated {} vertices, {} indices", self.vertices.len(), self.indices.len());
-        
-        Ok(())
     }
     
     pub fn render_frame(&mut self, grid: &crate::TextGrid) -> Result<(), RenderError> {
         // Convert grid to lines
         let mut lines = Vec::new();
+        let mut line_attrs = Vec::new();
         let mut non_empty_lines = 0;
         let mut total_chars = 0;
         
+        // Scrolled up, the top rows come from scrollback
         for row in 0..grid.rows {
-            if let Some(row_data) = grid.row(row) {
+            if let Some(row_data) = grid.viewport_row(row) {
                 let mut line = String::new();
                 for col in 0..row_data.len().min(grid.cols as usize) {
-                    if let Some(cell) = grid.cell_at(row, col as u16) {
+                    if let Some(cell) = row_data.get(col) {
                         if cell.ch != '\0' && cell.ch != ' ' {
                             line.push(cell.ch);
                             total_chars += 1;
@@ -556,6 +613,7 @@ impl GpuRenderer {
                     non_empty_lines += 1;
                 }
                 lines.push(line);
+                line_attrs.push(grid.viewport_line_attr(row));
             }
         }
         
@@ -581,39 +639,10 @@ impl GpuRenderer {
             }
         }
         
-        // Prepare text for rendering
-        let full_text = lines.join("\n");
+        // Prepare text for rendering; an unchanged frame is only presented again
         tracing::debug!("🎯 Preparing to render {} lines, {} total chars", lines.len(), total_chars);
-        self.render_text(&full_text, &lines)?;
-        
-        tracing::debug!("🔧 Buffer update: {} vertices, {} indices", self.vertices.len(), self.indices.len());
-        
-        // Update buffers if needed
-        if !self.vertices.is_empty() {
-            // Recreate vertex buffer if needed
-            let vertex_size = self.vertices.len() * std::mem::size_of::<Vertex>();
-            if vertex_size > self.vertex_buffer.size() as usize {
-                self.vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Vertex Buffer"),
-                    contents: bytemuck::cast_slice(&self.vertices),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                });
-            } else {
-                self.queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&self.vertices));
-            }
-            
-            // Recreate index buffer if needed
-            let index_size = self.indices.len() * std::mem::size_of::<u16>();
-            if index_size > self.index_buffer.size() as usize {
-                self.index_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Index Buffer"),
-                    contents: bytemuck::cast_slice(&self.indices),
-                    usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-                });
-            } else {
-                self.queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&self.indices));
-            }
-        }
+        self.add_missing_glyphs(&lines);
+        self.update_geometry(&lines, &line_attrs);
         
         // Render
         let output = self.surface.get_current_texture()
@@ -624,6 +653,8 @@ impl GpuRenderer {
             label: Some("Render Encoder"),
         });
         
+        let [r, g, b, a] = bell_tint(Rgba::rgb(0, 0, 0), Rgba::rgb(0xFF, 0xFF, 0xFF), self.bell_flash).to_f32();
+        
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
@@ -632,10 +663,10 @@ impl GpuRenderer {
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 1.0,
+                            r: r as f64,
+                            g: g as f64,
+                            b: b as f64,
+                            a: a as f64,
                         }),
                         store: wgpu::StoreOp::Store,
                     },
@@ -645,13 +676,14 @@ impl GpuRenderer {
                 timestamp_writes: None,
             });
             
-            if !self.vertices.is_empty() {
-                tracing::debug!("🎮 Drawing {} indexed vertices ({} indices)", self.vertices.len(), self.indices.len());
+            let (vertices, indices) = (self.geometry.vertices.len(), self.geometry.indices.len());
+            if vertices > 0 {
+                tracing::debug!("🎮 Drawing {} indexed vertices ({} indices)", vertices, indices);
                 render_pass.set_pipeline(&self.render_pipeline);
                 render_pass.set_bind_group(0, &self.font_atlas.bind_group, &[]);
                 render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-                render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                render_pass.draw_indexed(0..self.indices.len() as u32, 0, 0..1);
+                render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..indices as u32, 0, 0..1);
             } else {
                 tracing::debug!("⚠️ No vertices to draw - rendering black screen");
             }
@@ -673,6 +705,85 @@ impl GpuRenderer {
         Ok(())
     }
     
+    /// Cell layout inside the window; the GPU renderer draws from the top-left
+    /// corner without padding
+    pub fn geometry(&self) -> CellGeometry {
+        let cell_width = self.char_width();
+        let cell_height = self.char_height();
+        CellGeometry {
+            origin_x: 0,
+            origin_y: 0,
+            cell_width,
+            cell_height,
+            rows: self.size.height.checked_div(cell_height).unwrap_or(0),
+            cols: self.size.width.checked_div(cell_width).unwrap_or(0),
+        }
+    }
+    
+    /// Grid `(row, col)` under a window pixel, `None` outside the grid
+    pub fn cell_at_pixel(&self, x: u32, y: u32) -> Option<(u16, u16)> {
+        self.geometry().cell_at_pixel(x, y)
+    }
+    
+    /// Rasterize characters of `lines` not yet in the atlas, such as CJK,
+    /// box drawing and fallback-font glyphs
+    fn add_missing_glyphs(&mut self, lines: &[String]) {
+        let mut missing = missing_glyphs(lines, &self.font_atlas.char_map);
+        if missing.len() > self.font_atlas.free_slots() {
+            // Start again from ASCII; cached rows may use the evicted glyphs
+            tracing::debug!("Font atlas full, evicting non-ASCII glyphs");
+            self.font_atlas.evict_non_ascii();
+            self.geometry.invalidate();
+            missing = missing_glyphs(lines, &self.font_atlas.char_map);
+        }
+        
+        let mut dropped = 0;
+        for ch in missing {
+            if !self.font_atlas.add_glyph(&self.queue, &self.fonts, self.font_size, ch) {
+                dropped += 1;
+            }
+        }
+        if dropped > 0 {
+            tracing::warn!("Font atlas full, {} characters on screen are not drawn", dropped);
+        }
+    }
+    
+    /// Change the font size, clamped to the renderer's limits and to what the
+    /// GPU's largest texture holds, and rebuild the glyph atlas at the new
+    /// size. Returns the size actually applied.
+    pub fn set_font_size(&mut self, font_size: f32) -> Result<f32, RenderError> {
+        // The atlas texture must also fit within the device's limits
+        let largest = max_atlas_font_size(self.device.limits().max_texture_dimension_2d);
+        let font_size = self.font_size_limits.clamp(font_size).min(largest);
+        if font_size == self.font_size {
+            return Ok(font_size);
+        }
+        
+        self.font_atlas = Self::create_font_atlas(&self.device, &self.queue, &self.fonts, font_size)?;
+        self.font_size = font_size;
+        self.geometry.invalidate();
+        Ok(font_size)
+    }
+    
+    pub fn font_size(&self) -> f32 {
+        self.font_size
+    }
+    
+    /// Limit later `set_font_size` calls to `limits`; the current size is clamped right away
+    pub fn set_font_size_limits(&mut self, limits: FontSizeLimits) -> Result<f32, RenderError> {
+        self.font_size_limits = limits;
+        self.set_font_size(self.font_size)
+    }
+    
+    /// Load the fonts at `paths` as fallbacks for glyphs the primary font
+    /// lacks, and rebuild the glyph atlas from the new chain
+    pub fn set_fallback_fonts(&mut self, paths: &[PathBuf]) -> Result<(), RenderError> {
+        self.fonts.set_fallbacks(load_fonts(paths)?);
+        self.font_atlas = Self::create_font_atlas(&self.device, &self.queue, &self.fonts, self.font_size)?;
+        self.geometry.invalidate();
+        Ok(())
+    }
+    
     pub fn char_width(&self) -> u32 {
         self.font_atlas.char_width as u32
     }
@@ -681,3 +792,354 @@ impl GpuRenderer {
         self.font_atlas.char_height as u32
     }
 }
+
+impl Renderer for GpuRenderer {
+    fn render_frame(&mut self, grid: &crate::TextGrid) -> Result<(), RenderError> {
+        GpuRenderer::render_frame(self, grid)
+    }
+    
+    fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) -> Result<(), RenderError> {
+        GpuRenderer::resize(self, size)
+    }
+    
+    fn cell_size(&self) -> (u32, u32) {
+        (self.char_width(), self.char_height())
+    }
+    
+    fn geometry(&self) -> CellGeometry {
+        GpuRenderer::geometry(self)
+    }
+    
+    fn font_size(&self) -> f32 {
+        self.font_size
+    }
+    
+    fn set_font_size(&mut self, font_size: f32) -> Result<f32, RenderError> {
+        GpuRenderer::set_font_size(self, font_size)
+    }
+    
+    fn set_font_size_limits(&mut self, limits: FontSizeLimits) -> Result<f32, RenderError> {
+        GpuRenderer::set_font_size_limits(self, limits)
+    }
+    
+    fn set_fallback_fonts(&mut self, paths: &[PathBuf]) -> Result<(), RenderError> {
+        GpuRenderer::set_fallback_fonts(self, paths)
+    }
+    
+    fn set_bell_flash(&mut self, intensity: f32) {
+        self.bell_flash = intensity.clamp(0.0, 1.0);
+    }
+}
+
+/// Cell size in normalized device coordinates, which glyph quads are laid out from
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GlyphLayout {
+    char_width: f32,
+    char_height: f32,
+}
+
+/// What `FrameGeometry::update` changed, and so what must be uploaded
+#[derive(Debug, Clone, PartialEq)]
+enum GeometryChange {
+    Unchanged,
+    /// Only these vertex ranges were rewritten; the vertex count, and so
+    /// the indices, are as before
+    Ranges(Vec<Range<usize>>),
+    /// The vertex count changed: everything, indices included
+    All,
+}
+
+/// One row's text as last tessellated, and its quads
+struct RowGeometry {
+    line: String,
+    attr: LineAttr,
+    vertices: Vec<Vertex>,
+}
+
+/// Glyph quads for the last frame, kept per row so that only rows whose
+/// text or line attribute changed are tessellated again
+#[derive(Default)]
+struct FrameGeometry {
+    // Layout the rows were built with; any change rebuilds them all
+    layout: Option<GlyphLayout>,
+    rows: Vec<RowGeometry>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl FrameGeometry {
+    /// Rebuild every row on the next update, e.g. after the atlas changed
+    fn invalidate(&mut self) {
+        self.layout = None;
+    }
+    
+    fn update(
+        &mut self,
+        lines: &[String],
+        line_attrs: &[LineAttr],
+        layout: GlyphLayout,
+        char_map: &HashMap<char, (f32, f32, f32, f32)>,
+    ) -> GeometryChange {
+        if self.layout != Some(layout) {
+            self.layout = Some(layout);
+            self.rows.clear();
+        }
+        // Rows keep their vertex offsets only if every row keeps its quad count
+        let mut same_shape = self.rows.len() == lines.len();
+        self.rows.truncate(lines.len());
+        
+        let mut changed = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let attr = line_attrs.get(index).copied().unwrap_or_default();
+            if let Some(row) = self.rows.get(index) {
+                if row.line == *line && row.attr == attr {
+                    continue;
+                }
+            }
+            
+            let row = RowGeometry {
+                line: line.clone(),
+                attr,
+                vertices: row_vertices(index, line, attr, layout, char_map),
+            };
+            match self.rows.get_mut(index) {
+                Some(old) => {
+                    same_shape &= old.vertices.len() == row.vertices.len();
+                    *old = row;
+                }
+                None => self.rows.push(row),
+            }
+            changed.push(index);
+        }
+        
+        if same_shape {
+            if changed.is_empty() {
+                return GeometryChange::Unchanged;
+            }
+            // Patch the changed rows in place
+            let mut ranges = Vec::with_capacity(changed.len());
+            let mut start = 0;
+            let mut next_changed = changed.iter().peekable();
+            for (index, row) in self.rows.iter().enumerate() {
+                let range = start..start + row.vertices.len();
+                if next_changed.next_if_eq(&&index).is_some() && !range.is_empty() {
+                    self.vertices[range.clone()].copy_from_slice(&row.vertices);
+                    ranges.push(range.clone());
+                }
+                start = range.end;
+            }
+            return GeometryChange::Ranges(ranges);
+        }
+        
+        self.vertices = self.rows.iter().flat_map(|row| row.vertices.iter().copied()).collect();
+        self.indices = (0..self.vertices.len() / 4)
+            .flat_map(|quad| {
+                let first = (quad * 4) as u32;
+                [first, first + 1, first + 2, first, first + 2, first + 3]
+            })
+            .collect();
+        GeometryChange::All
+    }
+}
+
+/// Quads for the glyphs of row `index`, scaled per its line attribute.
+/// Characters missing from the atlas are skipped.
+fn row_vertices(
+    index: usize,
+    line: &str,
+    line_attr: LineAttr,
+    layout: GlyphLayout,
+    char_map: &HashMap<char, (f32, f32, f32, f32)>,
+) -> Vec<Vertex> {
+    // Start from top of screen and move down
+    let y = 1.0 - (index as f32 + 1.0) * layout.char_height;
+    let quad_width = if line_attr.is_double_width() {
+        layout.char_width * 2.0
+    } else {
+        layout.char_width
+    };
+    let color = [1.0, 1.0, 1.0, 1.0];
+    
+    let mut vertices = Vec::with_capacity(line.len() * 4);
+    for (char_idx, ch) in line.chars().enumerate() {
+        let Some(&(u, v, w, h)) = char_map.get(&ch) else {
+            continue;
+        };
+        let x = -1.0 + char_idx as f32 * quad_width;
+        
+        // Double-height rows show the top or bottom half of a stretched glyph
+        let (v, h) = match line_attr {
+            LineAttr::DoubleHeightTop => (v, h / 2.0),
+            LineAttr::DoubleHeightBottom => (v + h / 2.0, h / 2.0),
+            _ => (v, h),
+        };
+        
+        vertices.extend_from_slice(&[
+            Vertex { position: [x, y, 0.0], tex_coords: [u, v], color },
+            Vertex { position: [x + quad_width, y, 0.0], tex_coords: [u + w, v], color },
+            Vertex { position: [x + quad_width, y - layout.char_height, 0.0], tex_coords: [u + w, v + h], color },
+            Vertex { position: [x, y - layout.char_height, 0.0], tex_coords: [u, v + h], color },
+        ]);
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const LAYOUT: GlyphLayout = GlyphLayout { char_width: 0.1, char_height: 0.2 };
+    
+    /// An atlas mapping for printable ASCII, one slot per character
+    fn ascii_map() -> HashMap<char, (f32, f32, f32, f32)> {
+        (32u8..127).map(|c| (c as char, (f32::from(c - 32) / 95.0, 0.0, 0.01, 1.0))).collect()
+    }
+    
+    fn lines(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+    
+    /// A font with glyphs for `chars` only, each a 2x2 bitmap of full coverage
+    struct StubFont {
+        chars: &'static str,
+    }
+    
+    impl GlyphSource for StubFont {
+        fn has_glyph(&self, ch: char) -> bool {
+            self.chars.contains(ch)
+        }
+        
+        fn rasterize(&self, _ch: char, _px: f32) -> (fontdue::Metrics, Vec<u8>) {
+            let metrics = fontdue::Metrics { width: 2, height: 2, ..fontdue::Metrics::default() };
+            (metrics, vec![255; 4])
+        }
+    }
+    
+    #[test]
+    fn test_font_size_limited_by_texture_size() {
+        // wgpu's default limit: the atlas holds glyphs up to 64px
+        let largest = max_atlas_font_size(8192);
+        assert_eq!(largest, 64.0);
+        let (cell_width, cell_height) = atlas_cell_size(largest);
+        assert!(cell_width * ATLAS_COLUMNS <= 8192 && cell_height * ATLAS_ROWS <= 8192);
+        assert!(atlas_cell_size(largest + 1.0).1 * ATLAS_ROWS > 8192);
+        
+        assert_eq!(max_atlas_font_size(2048), DEFAULT_FONT_SIZE);
+    }
+    
+    #[test]
+    fn test_missing_glyphs_are_distinct_and_ordered() {
+        let frame = lines(&["│ 字 │", "a─字"]);
+        assert_eq!(missing_glyphs(&frame, &ascii_map()), vec!['│', '字', '─']);
+        assert!(missing_glyphs(&lines(&["$ ls"]), &ascii_map()).is_empty());
+    }
+    
+    #[test]
+    fn test_atlas_slots_fill_rows_in_order() {
+        let ((x, y), (u, v, w, h)) = atlas_slot(0, 8, 16);
+        assert_eq!((x, y), (0, 0));
+        assert_eq!((u, v, w, h), (0.0, 0.0, 1.0 / 16.0, 1.0 / 32.0));
+        
+        // Slot 17 is the second cell of the second row
+        let ((x, y), (u, v, _, _)) = atlas_slot(17, 8, 16);
+        assert_eq!((x, y), (8, 16));
+        assert_eq!((u, v), (1.0 / 16.0, 1.0 / 32.0));
+        
+        // The last slot ends on the texture's edge
+        let ((x, y), _) = atlas_slot(ATLAS_COLUMNS * ATLAS_ROWS - 1, 8, 16);
+        assert_eq!((x + 8, y + 16), (8 * ATLAS_COLUMNS, 16 * ATLAS_ROWS));
+    }
+    
+    #[test]
+    fn test_glyph_cell_draws_fallbacks_and_box_drawing() {
+        let fonts = FontChain::new(StubFont { chars: "a" })
+            .with_fallbacks(vec![StubFont { chars: "字" }]);
+        let alpha = |cell: &[u8]| cell.chunks(4).map(|texel| texel[3]).collect::<Vec<_>>();
+        let lit = |cell: &[u8]| alpha(cell).iter().filter(|&&a| a > 0).count();
+        
+        // A CJK glyph from the fallback font, centred in the cell
+        let cjk = glyph_cell(&fonts, '字', 16.0, 8, 16);
+        assert_eq!(cjk.len(), 8 * 16 * 4);
+        assert_eq!(lit(&cjk), 4);
+        assert_eq!(alpha(&cjk)[7 * 8 + 3], 255);
+        assert!(cjk.chunks(4).all(|texel| texel[..3] == [255, 255, 255]));
+        
+        // Box drawing no font has is drawn across the whole cell
+        let line = glyph_cell(&fonts, '─', 16.0, 8, 16);
+        assert_eq!(alpha(&line), box_drawing_bitmap('─', 8, 16).unwrap());
+    }
+    
+    #[test]
+    fn test_identical_frames_reuse_geometry() {
+        let map = ascii_map();
+        let frame = lines(&["$ ls", "Cargo.toml  src", "$ "]);
+        
+        let mut geometry = FrameGeometry::default();
+        assert_eq!(geometry.update(&frame, &[], LAYOUT, &map), GeometryChange::All);
+        let first = geometry.vertices.clone();
+        assert_eq!(first.len(), (4 + 15 + 2) * 4);
+        assert_eq!(geometry.indices.len(), first.len() / 4 * 6);
+        
+        // Nothing to tessellate or upload again
+        assert_eq!(geometry.update(&frame, &[], LAYOUT, &map), GeometryChange::Unchanged);
+        assert_eq!(geometry.vertices, first);
+        
+        // A fresh cache builds the very same buffers
+        let mut fresh = FrameGeometry::default();
+        fresh.update(&frame, &[], LAYOUT, &map);
+        assert_eq!(fresh.vertices, first);
+        assert_eq!(fresh.indices, geometry.indices);
+    }
+    
+    #[test]
+    fn test_large_frames_index_every_quad() {
+        // More quads than 16-bit indices can address
+        let row = "x".repeat(200);
+        let frame = lines(&vec![row.as_str(); 100]);
+        let mut geometry = FrameGeometry::default();
+        geometry.update(&frame, &[], LAYOUT, &ascii_map());
+        
+        assert_eq!(geometry.vertices.len(), 200 * 100 * 4);
+        assert_eq!(geometry.indices.iter().max().copied(), Some(geometry.vertices.len() as u32 - 1));
+    }
+    
+    #[test]
+    fn test_changed_cell_patches_only_its_row() {
+        let map = ascii_map();
+        let mut geometry = FrameGeometry::default();
+        geometry.update(&lines(&["$ ls", "Cargo.toml  src", "$ "]), &[], LAYOUT, &map);
+        let before = geometry.vertices.clone();
+        
+        // One character differs on the middle row: only that row's quads are rewritten
+        let edited = lines(&["$ ls", "Cargo.lock  src", "$ "]);
+        let change = geometry.update(&edited, &[], LAYOUT, &map);
+        let middle_row = Range { start: 16, end: 16 + 15 * 4 };
+        assert_eq!(change, GeometryChange::Ranges(vec![middle_row]));
+        let differing: Vec<usize> = (0..before.len()).filter(|&i| before[i] != geometry.vertices[i]).collect();
+        assert!(!differing.is_empty() && differing.len() <= 4 * 4, "{:?}", differing);
+        assert!(differing.iter().all(|&i| (16..76).contains(&i)));
+        
+        let mut fresh = FrameGeometry::default();
+        fresh.update(&edited, &[], LAYOUT, &map);
+        assert_eq!(fresh.vertices, geometry.vertices);
+        
+        // A row growing shifts the rows after it, so everything is uploaded
+        assert_eq!(geometry.update(&lines(&["$ ls -la", "Cargo.lock  src", "$ "]), &[], LAYOUT, &map), GeometryChange::All);
+        // As does a new layout, e.g. after a resize
+        let wider = GlyphLayout { char_width: 0.05, ..LAYOUT };
+        assert_eq!(geometry.update(&lines(&["$ ls -la", "Cargo.lock  src", "$ "]), &[], wider, &map), GeometryChange::All);
+    }
+    
+    #[test]
+    fn test_line_attr_change_rebuilds_row() {
+        let map = ascii_map();
+        let frame = lines(&["title", "body"]);
+        let mut geometry = FrameGeometry::default();
+        geometry.update(&frame, &[], LAYOUT, &map);
+        
+        let change = geometry.update(&frame, &[LineAttr::DoubleWidth], LAYOUT, &map);
+        let first_row = Range { start: 0, end: 20 };
+        assert_eq!(change, GeometryChange::Ranges(vec![first_row]));
+        assert_eq!(geometry.vertices[1].position[0], -1.0 + LAYOUT.char_width * 2.0);
+    }
+}
@@ -1,12 +1,35 @@
 use std::collections::VecDeque;
-use crate::renderer::colors::TerminalColor;
+use std::fmt::{self, Write};
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+use crate::renderer::colors::{Palette, Rgba, TerminalColor};
+use crate::renderer::selection::Selection;
 
-#[derive(Debug, Clone, Default)]
+/// Brightness factor applied to the foreground of dim (SGR 2) cells
+const DIM_FACTOR: f32 = 0.66;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Cell {
     pub ch: char,
     pub fg_color: TerminalColor,
     pub bg_color: TerminalColor,
     pub attrs: CellAttributes,
+    /// Code points following `ch` in the same grapheme cluster: combining
+    /// marks, variation selectors, the rest of a ZWJ sequence or flag
+    pub extra: Option<Box<str>>,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: '\0',
+            fg_color: TerminalColor::DefaultFg,
+            bg_color: TerminalColor::DefaultBg,
+            attrs: CellAttributes::default(),
+            extra: None,
+        }
+    }
 }
 
 impl Cell {
@@ -16,6 +39,7 @@ impl Cell {
             fg_color: TerminalColor::White,
             bg_color: TerminalColor::Black,
             attrs: CellAttributes::default(),
+            extra: None,
         }
     }
     
@@ -23,22 +47,124 @@ impl Cell {
         Self::default()
     }
     
+    /// An erased cell filled with background `bg`, as left by clearing while
+    /// a background color is set
+    pub fn blank_with(bg: TerminalColor) -> Self {
+        Self {
+            bg_color: bg,
+            ..Self::default()
+        }
+    }
+    
     pub fn is_empty(&self) -> bool {
         self.ch == '\0' || self.ch == ' '
     }
+    
+    /// The whole grapheme cluster held by this cell, with unwritten cells as a space
+    pub fn grapheme(&self) -> String {
+        let mut text = String::new();
+        self.push_grapheme(&mut text);
+        text
+    }
+    
+    pub(crate) fn push_grapheme(&self, out: &mut String) {
+        out.push(if self.ch == '\0' { ' ' } else { self.ch });
+        if let Some(extra) = &self.extra {
+            out.push_str(extra);
+        }
+    }
+    
+    /// Columns this cell's grapheme takes on screen
+    pub fn width(&self) -> usize {
+        match &self.extra {
+            Some(extra) => grapheme_width(self.ch, extra),
+            None => char_width(self.ch),
+        }
+    }
+    
+    /// Color to draw this cell's underline with: the SGR 58 color, else the
+    /// resolved foreground
+    pub fn resolved_underline_color(&self, palette: &Palette) -> Rgba {
+        match self.attrs.underline_color {
+            Some(color) => palette.resolve(color),
+            None => self.resolved_colors(palette).0,
+        }
+    }
+    
+    /// Resolve the (foreground, background) colors to draw this cell with,
+    /// applying the palette, reverse video and dim attenuation
+    pub fn resolved_colors(&self, palette: &Palette) -> (Rgba, Rgba) {
+        let mut fg = palette.resolve(self.fg_color);
+        let mut bg = palette.resolve(self.bg_color);
+        
+        if self.attrs.reverse {
+            std::mem::swap(&mut fg, &mut bg);
+        }
+        if self.attrs.dim {
+            fg = fg.scaled(DIM_FACTOR);
+        }
+        
+        (fg, bg)
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct CellAttributes {
     pub bold: bool,
+    pub dim: bool,
     pub italic: bool,
     pub underline: bool,
+    pub double_underline: bool,
     pub strikethrough: bool,
     pub blink: bool,
     pub reverse: bool,
+    /// Underline color from SGR 58; `None` underlines in the foreground color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underline_color: Option<TerminalColor>,
 }
 
-#[derive(Debug, Clone)]
+/// Per-row size attribute set by DECDWL/DECDHL (`ESC # 3..6`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineAttr {
+    /// Single width, single height (also set by DECSWL, `ESC # 5`)
+    #[default]
+    Normal,
+    DoubleWidth,
+    DoubleHeightTop,
+    DoubleHeightBottom,
+}
+
+impl LineAttr {
+    /// Double-height lines are also rendered double width
+    pub fn is_double_width(self) -> bool {
+        self != LineAttr::Normal
+    }
+    
+    pub fn is_double_height(self) -> bool {
+        matches!(self, LineAttr::DoubleHeightTop | LineAttr::DoubleHeightBottom)
+    }
+}
+
+/// Cursor shape chosen by DECSCUSR (`CSI Ps SP q`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum CursorShape {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+}
+
+/// Everything a renderer needs to draw the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorState {
+    pub row: u16,
+    pub col: u16,
+    pub visible: bool,
+    pub shape: CursorShape,
+    pub blinking: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Region {
     pub row: u16,
     pub col: u16,
@@ -46,24 +172,230 @@ pub struct Region {
     pub height: u16,
 }
 
+impl Region {
+    // Exclusive end row/column, widened so edges past u16::MAX don't wrap
+    fn row_end(&self) -> u32 {
+        self.row as u32 + self.height as u32
+    }
+    
+    fn col_end(&self) -> u32 {
+        self.col as u32 + self.width as u32
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+    
+    pub fn contains(&self, row: u16, col: u16) -> bool {
+        row >= self.row && (row as u32) < self.row_end() && col >= self.col && (col as u32) < self.col_end()
+    }
+    
+    /// Cells covered by both regions, `None` if they don't overlap
+    pub fn intersect(&self, other: &Region) -> Option<Region> {
+        let row = self.row.max(other.row);
+        let col = self.col.max(other.col);
+        let row_end = self.row_end().min(other.row_end());
+        let col_end = self.col_end().min(other.col_end());
+        if row as u32 >= row_end || col as u32 >= col_end {
+            return None;
+        }
+        
+        Some(Region {
+            row,
+            col,
+            width: (col_end - col as u32) as u16,
+            height: (row_end - row as u32) as u16,
+        })
+    }
+    
+    /// Smallest region covering both; an empty region contributes nothing
+    pub fn union(&self, other: &Region) -> Region {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        
+        let row = self.row.min(other.row);
+        let col = self.col.min(other.col);
+        let row_end = self.row_end().max(other.row_end());
+        let col_end = self.col_end().max(other.col_end());
+        Region {
+            row,
+            col,
+            width: (col_end - col as u32).min(u16::MAX as u32) as u16,
+            height: (row_end - row as u32).min(u16::MAX as u32) as u16,
+        }
+    }
+}
+
+/// Upper bound for rows/cols to avoid huge allocations from bogus sizes
+pub const MAX_GRID_DIMENSION: u16 = 9999;
+
+/// Maximum number of lines kept in scrollback
+const MAX_SCROLLBACK_LINES: usize = 10000;
+
+/// Once dirty regions cover more than this percentage of the screen they are
+/// collapsed into a single full-screen region
+const FULL_REDRAW_THRESHOLD_PERCENT: u64 = 50;
+
+/// Visible rows as plain text, one line per row with trailing blanks trimmed
+impl fmt::Display for TextGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, row) in self.cells.iter().enumerate() {
+            if index > 0 {
+                f.write_char('\n')?;
+            }
+            f.write_str(cells_text(row).trim_end_matches(' '))?;
+        }
+        Ok(())
+    }
+}
+
+/// Columns `ch` takes on screen; zero for combining marks
+fn char_width(ch: char) -> usize {
+    ch.width().unwrap_or(1)
+}
+
+/// Columns a grapheme cluster of `base` followed by `extra` takes. Emoji
+/// presentation (VS16) and flags (regional indicator pairs) are wide even
+/// though their first code point alone is narrow.
+fn grapheme_width(base: char, extra: &str) -> usize {
+    let regional_indicator = |c: char| ('\u{1F1E6}'..='\u{1F1FF}').contains(&c);
+    let emoji = extra.contains('\u{FE0F}')
+        || (regional_indicator(base) && extra.chars().next().is_some_and(regional_indicator));
+    if emoji {
+        2
+    } else {
+        char_width(base)
+    }
+}
+
+/// Text of a run of cells starting at column 0, with unwritten cells as spaces
+fn cells_text(row: &[Cell]) -> String {
+    let mut text = String::new();
+    for (col, cell) in row.iter().enumerate() {
+        if !is_wide_spacer(row, col) {
+            cell.push_grapheme(&mut text);
+        }
+    }
+    text
+}
+
+/// Whether `row[col]` is the blank right half of a wide character
+pub(crate) fn is_wide_spacer(row: &[Cell], col: usize) -> bool {
+    col > 0 && row[col].ch == '\0' && row[col - 1].width() == 2
+}
+
+/// Whether `cell` holds a combining mark with no base character to join
+fn is_combining(cell: &Cell) -> bool {
+    cell.ch != '\0' && char_width(cell.ch) == 0
+}
+
+/// Tab stops every 8 columns for columns `from..to`
+fn default_tab_stops(from: u16, to: u16) -> impl Iterator<Item = bool> {
+    (from..to).map(|col| col > 0 && col % 8 == 0)
+}
+
+/// Where the last grapheme cluster was written, and the cursor state right
+/// after, so zero-width code points that follow can join it
+#[derive(Debug, Clone, Copy)]
+struct LastGrapheme {
+    row: u16,
+    col: u16,
+    cursor: (u16, u16, bool),
+}
+
+/// Cursor state stored by DECSC (`ESC 7`, `CSI ? 1048 h`)
+#[derive(Debug, Clone)]
+struct SavedCursor {
+    row: u16,
+    col: u16,
+    wrap_pending: bool,
+    attrs: CellAttributes,
+    fg: TerminalColor,
+    bg: TerminalColor,
+}
+
+impl SavedCursor {
+    /// Keep the position within `last_row`/`last_col`; a pending wrap only
+    /// survives if the column did
+    fn clamp(&mut self, last_row: u16, last_col: u16) {
+        self.row = self.row.min(last_row);
+        if self.col > last_col {
+            self.col = last_col;
+            self.wrap_pending = false;
+        }
+    }
+}
+
+/// The inactive screen's contents while the other one is shown
+#[derive(Debug, Clone)]
+struct InactiveScreen {
+    cells: Vec<Vec<Cell>>,
+    line_attrs: Vec<LineAttr>,
+    wrapped: Vec<bool>,
+    saved_cursor: Option<SavedCursor>,
+}
+
 pub struct TextGrid {
     pub rows: u16,
     pub cols: u16,
     cells: Vec<Vec<Cell>>,
+    line_attrs: Vec<LineAttr>,
+    // Rows that were soft-wrapped onto the following row
+    wrapped: Vec<bool>,
     scrollback: VecDeque<Vec<Cell>>,
+    // Rows scrolled off the top of the full screen since creation
+    lines_scrolled: u64,
+    // How many scrollback lines the viewport is scrolled up; 0 shows the live screen
+    scroll_offset: usize,
+    // Snap the viewport back to the bottom when output scrolls; otherwise keep
+    // the history being read in place
+    scroll_on_output: bool,
     cursor_row: u16,
     cursor_col: u16,
+    // Set after writing the last column; the next printable character wraps
+    wrap_pending: bool,
+    // DECAWM: printing past the last column continues on the next row;
+    // when off, the last column is overwritten instead
+    autowrap: bool,
+    // IRM: printed characters shift the rest of the row right instead of
+    // overwriting it
+    insert_mode: bool,
+    // Lets zero-width code points join the cluster just written
+    last_grapheme: Option<LastGrapheme>,
+    tab_stops: Vec<bool>,
     cursor_visible: bool,
+    // DECSET 12 (att610) cursor blinking
+    cursor_blink_mode: bool,
+    // Backspace blanks the character it moves over
+    destructive_backspace: bool,
+    // Blink requested by the last DECSCUSR; `None` defers to the mode
+    cursor_style_blink: Option<bool>,
+    cursor_shape: CursorShape,
     dirty_regions: Vec<Region>,
+    dirty_area: u64,
     current_attrs: CellAttributes,
     current_fg: TerminalColor,
     current_bg: TerminalColor,
     scroll_region_top: u16,
     scroll_region_bottom: u16,
+    // Saved cursor of the screen being shown; each screen has its own
+    saved_cursor: Option<SavedCursor>,
+    // The main screen, kept aside while the alternate screen is active
+    main_screen: Option<InactiveScreen>,
+    // Lines scrolled off the alternate screen also enter scrollback
+    alternate_scrollback: bool,
+    // Mouse selection on the visible screen
+    selection: Option<Selection>,
 }
 
 impl TextGrid {
+    /// Create a grid, clamping each dimension to `1..=MAX_GRID_DIMENSION`
     pub fn new(rows: u16, cols: u16) -> Self {
+        let (rows, cols) = Self::clamp_dimensions(rows, cols);
         let mut cells = Vec::with_capacity(rows as usize);
         for _ in 0..rows {
             cells.push(vec![Cell::empty(); cols as usize]);
@@ -73,78 +405,323 @@ impl TextGrid {
             rows,
             cols,
             cells,
+            line_attrs: vec![LineAttr::Normal; rows as usize],
+            wrapped: vec![false; rows as usize],
             scrollback: VecDeque::new(),
+            lines_scrolled: 0,
+            scroll_offset: 0,
+            scroll_on_output: true,
             cursor_row: 0,
             cursor_col: 0,
+            wrap_pending: false,
+            autowrap: true,
+            insert_mode: false,
+            last_grapheme: None,
+            tab_stops: default_tab_stops(0, cols).collect(),
             cursor_visible: true,
+            cursor_blink_mode: false,
+            destructive_backspace: false,
+            cursor_style_blink: None,
+            cursor_shape: CursorShape::default(),
             dirty_regions: Vec::new(),
+            dirty_area: 0,
             current_attrs: CellAttributes::default(),
             current_fg: TerminalColor::White,
             current_bg: TerminalColor::Black,
             scroll_region_top: 0,
-            scroll_region_bottom: rows - 1,
+            scroll_region_bottom: rows.saturating_sub(1),
+            saved_cursor: None,
+            main_screen: None,
+            alternate_scrollback: false,
+            selection: None,
         }
     }
     
+    /// Clamp requested dimensions to the supported range
+    pub fn clamp_dimensions(rows: u16, cols: u16) -> (u16, u16) {
+        (rows.clamp(1, MAX_GRID_DIMENSION), cols.clamp(1, MAX_GRID_DIMENSION))
+    }
+    
+    /// Resize the grid, reflowing soft-wrapped lines to the new width.
+    /// The cursor stays on the same character of its logical line; a cursor
+    /// in never-written space past the end of the line snaps to its end.
     pub fn resize(&mut self, new_rows: u16, new_cols: u16) {
+        let (new_rows, new_cols) = Self::clamp_dimensions(new_rows, new_cols);
         if new_rows == self.rows && new_cols == self.cols {
             return;
         }
+        self.last_grapheme = None;
+        // Reflow moves text out from under the selected cells
+        self.selection = None;
+        let full_region = self.scroll_region() == (0, self.last_row());
         
-        // Resize existing rows
-        for row in &mut self.cells {
-            if new_cols > self.cols {
-                // Add cells to the right
-                row.extend(vec![Cell::empty(); (new_cols - self.cols) as usize]);
-            } else if new_cols < self.cols {
-                // Remove cells from the right
-                row.truncate(new_cols as usize);
-            }
+        self.reflow(new_rows, new_cols);
+        
+        // Keep stops set on surviving columns; new columns get the defaults
+        self.tab_stops.truncate(new_cols as usize);
+        let existing = self.tab_stops.len() as u16;
+        self.tab_stops.extend(default_tab_stops(existing, new_cols));
+        
+        self.rows = new_rows;
+        self.cols = new_cols;
+        // A custom scroll region is kept while at least two of its rows remain;
+        // one spanning the screen keeps spanning it
+        self.scroll_region_bottom = self.scroll_region_bottom.min(self.last_row());
+        if full_region || self.scroll_region_top >= self.scroll_region_bottom {
+            self.scroll_region_top = 0;
+            self.scroll_region_bottom = self.last_row();
+        }
+        
+        // Saved cursors on either screen must land on the new grid
+        let (last_row, last_col) = (self.last_row(), self.last_col());
+        let inactive = self.main_screen.as_mut().and_then(|screen| screen.saved_cursor.as_mut());
+        for saved in self.saved_cursor.iter_mut().chain(inactive) {
+            saved.clamp(last_row, last_col);
         }
         
-        // Add or remove rows
-        if new_rows > self.rows {
-            // Add rows at the bottom
-            for _ in self.rows..new_rows {
-                self.cells.push(vec![Cell::empty(); new_cols as usize]);
+        self.mark_all_dirty();
+    }
+    
+    fn reflow(&mut self, new_rows: u16, new_cols: u16) {
+        let old_cells = std::mem::take(&mut self.cells);
+        let old_wrapped = std::mem::take(&mut self.wrapped);
+        let old_attrs = std::mem::take(&mut self.line_attrs);
+        
+        // Join soft-wrapped rows into logical lines, locating the cursor in them
+        let mut lines: Vec<(Vec<Cell>, LineAttr)> = Vec::new();
+        let mut current: Vec<Cell> = Vec::new();
+        let mut current_attr = None;
+        let mut cursor_line = 0;
+        let mut cursor_offset = 0;
+        
+        for (index, row) in old_cells.into_iter().enumerate() {
+            if index == self.cursor_row as usize {
+                cursor_line = lines.len();
+                cursor_offset = current.len() + self.cursor_col as usize + self.wrap_pending as usize;
             }
-        } else if new_rows < self.rows {
-            // Remove rows from the bottom, move to scrollback if needed
-            while self.cells.len() > new_rows as usize {
-                if let Some(row) = self.cells.pop() {
-                    self.scrollback.push_back(row);
+            let attr = *current_attr.get_or_insert(old_attrs[index]);
+            current.extend(row);
+            
+            let is_last = index + 1 == old_wrapped.len();
+            if !old_wrapped[index] || is_last {
+                // Drop never-written cells at the end of the line
+                while current.last().is_some_and(|cell| cell.ch == '\0') {
+                    current.pop();
                 }
+                lines.push((std::mem::take(&mut current), attr));
+                current_attr = None;
             }
         }
         
-        self.rows = new_rows;
-        self.cols = new_cols;
-        self.scroll_region_bottom = new_rows - 1;
+        cursor_offset = cursor_offset.min(lines[cursor_line].0.len());
         
-        // Clamp cursor position
-        self.cursor_row = self.cursor_row.min(new_rows - 1);
-        self.cursor_col = self.cursor_col.min(new_cols - 1);
+        // Re-wrap every logical line at the new width
+        let cols = new_cols as usize;
+        let mut cells = Vec::new();
+        let mut wrapped = Vec::new();
+        let mut line_attrs = Vec::new();
+        let mut cursor = (0, 0, false);
         
-        self.mark_all_dirty();
+        for (index, (line, attr)) in lines.into_iter().enumerate() {
+            let first_row = cells.len();
+            let row_count = line.len().div_ceil(cols).max(1);
+            
+            for chunk_index in 0..row_count {
+                let start = chunk_index * cols;
+                let end = (start + cols).min(line.len());
+                let mut row = line[start.min(end)..end].to_vec();
+                row.resize(cols, Cell::empty());
+                cells.push(row);
+                wrapped.push(chunk_index + 1 < row_count);
+                line_attrs.push(attr);
+            }
+            
+            if index == cursor_line {
+                cursor = if cursor_offset > 0 && cursor_offset == line.len() && cursor_offset % cols == 0 {
+                    // Cursor sits right after a full last row: keep the wrap pending
+                    (first_row + row_count - 1, cols.saturating_sub(1), true)
+                } else {
+                    (first_row + cursor_offset / cols, cursor_offset % cols, false)
+                };
+            }
+        }
+        
+        // Drop blank rows below the cursor that no longer fit
+        while cells.len() > new_rows as usize
+            && cells.len() - 1 > cursor.0
+            && cells.last().is_some_and(|row| row.iter().all(|cell| cell.ch == '\0'))
+        {
+            cells.pop();
+            wrapped.pop();
+            line_attrs.pop();
+        }
+        
+        // Push remaining overflow from the top into scrollback
+        let overflow = cells.len().saturating_sub(new_rows as usize);
+        for row in cells.drain(..overflow) {
+            if self.feeds_scrollback() {
+                self.push_scrollback(row);
+            }
+        }
+        wrapped.drain(..overflow);
+        line_attrs.drain(..overflow);
+        
+        cells.resize(new_rows as usize, vec![Cell::empty(); cols]);
+        wrapped.resize(new_rows as usize, false);
+        line_attrs.resize(new_rows as usize, LineAttr::Normal);
+        
+        self.cells = cells;
+        self.wrapped = wrapped;
+        self.line_attrs = line_attrs;
+        self.cursor_row = (cursor.0 - overflow) as u16;
+        self.cursor_col = cursor.1 as u16;
+        self.wrap_pending = cursor.2;
     }
     
+    /// Write `ch` at the cursor and advance. Wide characters take two cells,
+    /// the second left blank; one that doesn't fit on the row wraps first.
     pub fn write_char(&mut self, ch: char) {
-        if self.cursor_col >= self.cols {
-            self.newline();
+        if self.extend_grapheme(ch) {
+            return;
+        }
+        
+        let wide = char_width(ch) == 2 && self.cols >= 2;
+        if self.wrap_pending || (wide && self.cursor_col + 1 >= self.cols) {
+            if self.autowrap {
+                let cursor_row = self.cursor_index().0;
+                self.wrapped[cursor_row] = true;
+                self.newline();
+            } else if wide {
+                // No wrapping: a wide character takes the last two columns
+                self.cursor_col = self.cols - 2;
+            }
+            self.wrap_pending = false;
+        }
+        
+        let width = if wide { 2 } else { 1 };
+        if self.insert_mode {
+            self.insert_blanks(width);
         }
         
-        self.cells[self.cursor_row as usize][self.cursor_col as usize] = Cell {
+        let cell = Cell {
             ch,
             fg_color: self.current_fg,
             bg_color: self.current_bg,
             attrs: self.current_attrs.clone(),
+            extra: None,
+        };
+        let (cursor_row, col) = self.cursor_index();
+        let row = &mut self.cells[cursor_row];
+        // Overwriting the right half of a wide character blanks its left half,
+        // which would otherwise still be drawn across both cells
+        if is_wide_spacer(row, col) {
+            row[col - 1].ch = ' ';
+            self.mark_dirty(self.cursor_row, self.cursor_col - 1, 1, 1);
+        }
+        let row = &mut self.cells[cursor_row];
+        if wide {
+            row[col + 1] = Cell { ch: '\0', ..cell.clone() };
+        }
+        row[col] = cell;
+        
+        self.mark_dirty(self.cursor_row, self.cursor_col, width, 1);
+        
+        let (row, col) = (self.cursor_row, self.cursor_col);
+        self.advance_cursor(width);
+        self.last_grapheme = Some(LastGrapheme { row, col, cursor: self.cursor_state() });
+    }
+    
+    /// Shift the cursor row right by `n` cells from the cursor, blanking the
+    /// gap. Cells pushed past the last column are lost.
+    fn insert_blanks(&mut self, n: u16) {
+        let blank = self.blank();
+        let (cursor_row, col) = self.cursor_index();
+        let row = &mut self.cells[cursor_row];
+        // Splitting a wide character leaves neither half
+        let start = if is_wide_spacer(row, col) {
+            row[col - 1..=col].fill(blank.clone());
+            col - 1
+        } else {
+            col
+        };
+        
+        let n = (n as usize).min(row.len() - col);
+        row[col..].rotate_right(n);
+        row[col..col + n].fill(blank);
+        let last = row.len() - 1;
+        if row[last].width() == 2 {
+            row[last] = Cell { ch: ' ', extra: None, ..row[last].clone() };
+        }
+        
+        let start = start as u16;
+        self.mark_dirty(self.cursor_row, start, self.cols - start, 1);
+    }
+    
+    fn advance_cursor(&mut self, width: u16) {
+        if self.cursor_col + width >= self.cols {
+            self.cursor_col += width - 1;
+            self.wrap_pending = self.autowrap;
+        } else {
+            self.cursor_col += width;
+        }
+    }
+    
+    fn cursor_state(&self) -> (u16, u16, bool) {
+        (self.cursor_row, self.cursor_col, self.wrap_pending)
+    }
+    
+    /// Append `ch` to the grapheme cluster written last if it continues it
+    /// and the cursor hasn't moved since. A cluster that becomes emoji
+    /// presentation grows to two cells when there is room.
+    fn extend_grapheme(&mut self, ch: char) -> bool {
+        let Some(last) = self.last_grapheme else {
+            return false;
+        };
+        if last.cursor != self.cursor_state() {
+            return false;
+        }
+        
+        let (row, col) = (last.row as usize, last.col as usize);
+        let Some(cell) = self.cells.get_mut(row).and_then(|cells| cells.get_mut(col)) else {
+            return false;
         };
+        if cell.ch == '\0' {
+            return false;
+        }
+        let mut cluster = cell.grapheme();
+        cluster.push(ch);
+        if cluster.graphemes(true).count() != 1 {
+            return false;
+        }
         
-        self.mark_dirty(self.cursor_row, self.cursor_col, 1, 1);
-        self.cursor_col += 1;
+        let was_wide = cell.width() == 2;
+        cell.extra = Some(cluster[cell.ch.len_utf8()..].into());
+        let spacer = Cell { ch: '\0', extra: None, ..cell.clone() };
+        let widened = !was_wide && cell.width() == 2;
         
-        if self.cursor_col >= self.cols {
-            self.cursor_col = self.cols - 1;
+        if widened && !self.wrap_pending && col + 1 < self.cols as usize {
+            self.cells[row][col + 1] = spacer;
+            self.mark_dirty(last.row, last.col, 2, 1);
+            self.advance_cursor(1);
+        } else {
+            // At the right margin there is no room to grow, so it stays in one cell
+            self.mark_dirty(last.row, last.col, 1, 1);
+        }
+        self.last_grapheme = Some(LastGrapheme { cursor: self.cursor_state(), ..last });
+        true
+    }
+    
+    /// Write a string as plain text: `\n`, `\r`, `\t` and backspace move the
+    /// cursor, everything else is written as-is (escape sequences are not parsed)
+    pub fn write_str(&mut self, s: &str) {
+        for ch in s.chars() {
+            match ch {
+                '\n' => self.newline(),
+                '\r' => self.carriage_return(),
+                '\t' => self.tab(),
+                '\x08' => self.backspace(),
+                _ => self.write_char(ch),
+            }
         }
     }
     
@@ -155,12 +732,14 @@ impl TextGrid {
                 fg_color: TerminalColor::White,
                 bg_color: TerminalColor::Black,
                 attrs: CellAttributes::default(),
+                extra: None,
             };
             self.mark_dirty(row, col, 1, 1);
         }
     }
     
     pub fn newline(&mut self) {
+        self.wrap_pending = false;
         self.cursor_col = 0;
         if self.cursor_row >= self.scroll_region_bottom {
             self.scroll_up(1);
@@ -170,37 +749,109 @@ impl TextGrid {
     }
     
     pub fn carriage_return(&mut self) {
+        self.wrap_pending = false;
         self.cursor_col = 0;
     }
     
     pub fn tab(&mut self) {
-        // Move to next tab stop (every 8 characters)
-        let next_tab = ((self.cursor_col / 8) + 1) * 8;
-        self.cursor_col = next_tab.min(self.cols - 1);
+        // Move to the next tab stop, or the last column if there is none
+        self.wrap_pending = false;
+        let start = self.cursor_col as usize + 1;
+        let next_tab = self.tab_stops.iter().skip(start).position(|&stop| stop).map(|i| start + i);
+        self.cursor_col = next_tab.map_or(self.last_col(), |col| col as u16);
+    }
+    
+    /// Set a tab stop at the cursor column (HTS)
+    pub fn set_tab_stop(&mut self) {
+        self.tab_stops[self.cursor_col as usize] = true;
+    }
+    
+    /// Clear the tab stop at the cursor column (`CSI 0 g`)
+    pub fn clear_tab_stop(&mut self) {
+        self.tab_stops[self.cursor_col as usize] = false;
+    }
+    
+    /// Clear every tab stop (`CSI 3 g`)
+    pub fn clear_all_tab_stops(&mut self) {
+        self.tab_stops.fill(false);
+    }
+    
+    /// Move down one row, scrolling at the bottom of the scroll region (IND)
+    pub fn index(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor_row == self.scroll_region_bottom {
+            self.scroll_up(1);
+        } else if self.cursor_row + 1 < self.rows {
+            self.cursor_row += 1;
+        }
     }
     
+    /// Move up one row, scrolling down at the top of the scroll region (RI)
+    pub fn reverse_index(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor_row == self.scroll_region_top {
+            self.scroll_down(1);
+        } else if self.cursor_row > 0 {
+            self.cursor_row -= 1;
+        }
+    }
+    
+    /// Move back to the start of the previous character: over both halves of
+    /// a wide character, and past any combining marks to their base. In
+    /// destructive mode the cells moved over are blanked.
     pub fn backspace(&mut self) {
-        if self.cursor_col > 0 {
-            self.cursor_col -= 1;
+        self.wrap_pending = false;
+        if self.cursor_col == 0 {
+            return;
+        }
+        
+        let row = &self.cells[self.cursor_index().0];
+        let mut col = self.cursor_col as usize - 1;
+        while col > 0 && is_combining(&row[col]) {
+            col -= 1;
+        }
+        if is_wide_spacer(row, col) {
+            col -= 1;
+        }
+        
+        let from = self.cursor_col;
+        self.cursor_col = col as u16;
+        if self.destructive_backspace {
+            let blank = self.blank();
+            let cursor_row = self.cursor_index().0;
+            for cell in &mut self.cells[cursor_row][col..from as usize] {
+                *cell = blank.clone();
+            }
+            self.mark_dirty(self.cursor_row, self.cursor_col, from - self.cursor_col, 1);
         }
     }
     
+    pub fn set_destructive_backspace(&mut self, enabled: bool) {
+        self.destructive_backspace = enabled;
+    }
+    
+    /// Scroll the scroll region up. Lines leaving the top only enter scrollback
+    /// when the region is the full screen; a sub-region (e.g. below a status
+    /// line) just rotates its content.
     pub fn scroll_up(&mut self, lines: u16) {
+        let full_screen = self.scroll_region_top == 0 && self.scroll_region_bottom == self.last_row();
+        
         for _ in 0..lines {
             if self.scroll_region_top < self.cells.len() as u16 {
                 let top_line = self.cells.remove(self.scroll_region_top as usize);
-                self.scrollback.push_back(top_line);
+                if full_screen && self.feeds_scrollback() {
+                    self.push_scrollback(top_line);
+                }
+                self.line_attrs.remove(self.scroll_region_top as usize);
+                self.wrapped.remove(self.scroll_region_top as usize);
                 
                 // Insert empty line at scroll region bottom
                 self.cells.insert(
                     self.scroll_region_bottom as usize,
-                    vec![Cell::empty(); self.cols as usize]
+                    vec![self.blank(); self.cols as usize]
                 );
-            }
-            
-            // Limit scrollback size
-            if self.scrollback.len() > 10000 {
-                self.scrollback.pop_front();
+                self.line_attrs.insert(self.scroll_region_bottom as usize, LineAttr::Normal);
+                self.wrapped.insert(self.scroll_region_bottom as usize, false);
             }
         }
         
@@ -214,8 +865,12 @@ impl TextGrid {
                 self.cells.remove(self.scroll_region_bottom as usize);
                 self.cells.insert(
                     self.scroll_region_top as usize,
-                    vec![Cell::empty(); self.cols as usize]
+                    vec![self.blank(); self.cols as usize]
                 );
+                self.line_attrs.remove(self.scroll_region_bottom as usize);
+                self.line_attrs.insert(self.scroll_region_top as usize, LineAttr::Normal);
+                self.wrapped.remove(self.scroll_region_bottom as usize);
+                self.wrapped.insert(self.scroll_region_top as usize, false);
             }
         }
         
@@ -223,26 +878,145 @@ impl TextGrid {
                        self.scroll_region_bottom - self.scroll_region_top + 1);
     }
     
+    /// Whether lines leaving the top of the screen are kept. The alternate
+    /// screen has no scrollback unless `set_alternate_scrollback` enabled it.
+    fn feeds_scrollback(&self) -> bool {
+        self.main_screen.is_none() || self.alternate_scrollback
+    }
+    
+    /// Keep lines scrolled off the alternate screen in scrollback, as some
+    /// terminals do; off by default so full-screen apps leave history untouched
+    pub fn set_alternate_scrollback(&mut self, enabled: bool) {
+        self.alternate_scrollback = enabled;
+    }
+    
+    fn push_scrollback(&mut self, row: Vec<Cell>) {
+        self.lines_scrolled += 1;
+        self.scrollback.push_back(row);
+        if self.scrollback.len() > MAX_SCROLLBACK_LINES {
+            self.scrollback.pop_front();
+        }
+        
+        if self.scroll_offset > 0 {
+            if self.scroll_on_output {
+                self.scroll_to_bottom();
+            } else {
+                // Follow the lines being read as they move up into history
+                self.scroll_offset = (self.scroll_offset + 1).min(self.scrollback.len());
+            }
+        }
+    }
+    
+    /// Whether output that scrolls the screen also returns a scrolled-up
+    /// viewport to the bottom (the default), or leaves it where it is
+    pub fn set_scroll_on_output(&mut self, enabled: bool) {
+        self.scroll_on_output = enabled;
+    }
+    
+    pub fn scroll_on_output(&self) -> bool {
+        self.scroll_on_output
+    }
+    
+    /// Scrollback lines above the live screen the viewport is showing
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+    
+    /// Move the viewport `lines` further into history, stopping at the oldest line
+    pub fn scroll_viewport_up(&mut self, lines: usize) {
+        self.set_scroll_offset(self.scroll_offset.saturating_add(lines));
+    }
+    
+    /// Move the viewport `lines` back towards the live screen
+    pub fn scroll_viewport_down(&mut self, lines: usize) {
+        self.set_scroll_offset(self.scroll_offset.saturating_sub(lines));
+    }
+    
+    pub fn scroll_to_bottom(&mut self) {
+        self.set_scroll_offset(0);
+    }
+    
+    fn set_scroll_offset(&mut self, offset: usize) {
+        let offset = offset.min(self.scrollback.len());
+        if offset != self.scroll_offset {
+            self.scroll_offset = offset;
+            self.mark_all_dirty();
+        }
+    }
+    
+    /// Row `row` of the viewport: scrollback while scrolled up, else the screen
+    pub fn viewport_row(&self, row: u16) -> Option<&[Cell]> {
+        let index = (self.scrollback.len() - self.scroll_offset) + row as usize;
+        match index.checked_sub(self.scrollback.len()) {
+            None => self.scrollback.get(index).map(Vec::as_slice),
+            Some(screen_row) => self.cells.get(screen_row).map(Vec::as_slice),
+        }
+    }
+    
+    /// Size attribute of viewport row `row`; scrollback lines are normal size
+    pub fn viewport_line_attr(&self, row: u16) -> LineAttr {
+        match (row as usize).checked_sub(self.scroll_offset) {
+            Some(screen_row) => self.line_attr(screen_row as u16),
+            None => LineAttr::Normal,
+        }
+    }
+    
+    /// Viewport row the cursor is on, or `None` while scrolled so far up
+    /// that the cursor's row is out of view
+    pub fn viewport_cursor_row(&self) -> Option<u16> {
+        let row = self.cursor_row as usize + self.scroll_offset;
+        (row < self.rows as usize).then_some(row as u16)
+    }
+    
     // Cursor movement methods
     pub fn cursor_up(&mut self, lines: u16) {
+        self.wrap_pending = false;
         self.cursor_row = self.cursor_row.saturating_sub(lines).max(self.scroll_region_top);
     }
     
     pub fn cursor_down(&mut self, lines: u16) {
+        self.wrap_pending = false;
         self.cursor_row = (self.cursor_row + lines).min(self.scroll_region_bottom);
     }
     
     pub fn cursor_left(&mut self, cols: u16) {
+        self.wrap_pending = false;
         self.cursor_col = self.cursor_col.saturating_sub(cols);
     }
     
     pub fn cursor_right(&mut self, cols: u16) {
-        self.cursor_col = (self.cursor_col + cols).min(self.cols - 1);
+        self.wrap_pending = false;
+        self.cursor_col = (self.cursor_col + cols).min(self.last_col());
     }
     
     pub fn set_cursor(&mut self, row: u16, col: u16) {
-        self.cursor_row = row.min(self.rows - 1);
-        self.cursor_col = col.min(self.cols - 1);
+        self.wrap_pending = false;
+        self.cursor_row = row.min(self.last_row());
+        self.cursor_col = col.min(self.last_col());
+    }
+    
+    /// Bottom row index. Dimensions are clamped to at least 1, so this
+    /// never underflows, but it saturates rather than trusting that.
+    fn last_row(&self) -> u16 {
+        self.rows.saturating_sub(1)
+    }
+    
+    /// Rightmost column index, saturating like `last_row`
+    fn last_col(&self) -> u16 {
+        self.cols.saturating_sub(1)
+    }
+    
+    /// The cursor as indices into `cells`, checked against the grid in debug builds
+    fn cursor_index(&self) -> (usize, usize) {
+        debug_assert!(
+            self.cursor_row < self.rows && self.cursor_col < self.cols,
+            "cursor ({}, {}) outside the {}x{} grid",
+            self.cursor_row,
+            self.cursor_col,
+            self.rows,
+            self.cols
+        );
+        (self.cursor_row as usize, self.cursor_col as usize)
     }
     
     pub fn cursor_position(&self) -> (u16, u16) {
@@ -257,77 +1031,303 @@ impl TextGrid {
         self.cursor_visible
     }
     
-    // Screen clearing methods
-    pub fn clear_screen(&mut self) {
-        for row in &mut self.cells {
-            for cell in row {
-                *cell = Cell::empty();
-            }
+    /// Position, visibility, shape and blinking of the cursor in one value
+    pub fn cursor(&self) -> CursorState {
+        CursorState {
+            row: self.cursor_row,
+            col: self.cursor_col,
+            visible: self.cursor_visible,
+            shape: self.cursor_shape,
+            blinking: self.cursor_blink_enabled(),
         }
-        self.mark_all_dirty();
     }
     
-    pub fn clear_line(&mut self) {
-        let row = &mut self.cells[self.cursor_row as usize];
-        for cell in row {
-            *cell = Cell::empty();
-        }
-        self.mark_dirty(self.cursor_row, 0, self.cols, 1);
+    /// Whether the last column was written and the next character wraps
+    pub fn wrap_pending(&self) -> bool {
+        self.wrap_pending
     }
     
-    pub fn clear_line_from_cursor(&mut self) {
-        let row = &mut self.cells[self.cursor_row as usize];
-        for i in self.cursor_col as usize..row.len() {
-            row[i] = Cell::empty();
+    /// DECAWM (`?7`); with it off, characters past the last column overwrite it
+    pub fn set_autowrap(&mut self, enabled: bool) {
+        self.autowrap = enabled;
+        if !enabled {
+            self.wrap_pending = false;
         }
-        self.mark_dirty(self.cursor_row, self.cursor_col, self.cols - self.cursor_col, 1);
     }
     
-    pub fn clear_line_to_cursor(&mut self) {
-        let row = &mut self.cells[self.cursor_row as usize];
-        for i in 0..=self.cursor_col as usize {
-            if i < row.len() {
-                row[i] = Cell::empty();
-            }
-        }
-        self.mark_dirty(self.cursor_row, 0, self.cursor_col + 1, 1);
+    pub fn autowrap(&self) -> bool {
+        self.autowrap
     }
     
-    // Attribute and color methods
-    pub fn set_attrs(&mut self, attrs: CellAttributes) {
-        self.current_attrs = attrs;
+    /// IRM (`4`); with it on, printing inserts rather than overwrites
+    pub fn set_insert_mode(&mut self, enabled: bool) {
+        self.insert_mode = enabled;
     }
     
-    pub fn set_fg_color(&mut self, color: TerminalColor) {
-        self.current_fg = color;
+    pub fn insert_mode(&self) -> bool {
+        self.insert_mode
     }
     
-    pub fn set_bg_color(&mut self, color: TerminalColor) {
-        self.current_bg = color;
+    pub fn set_cursor_blink_mode(&mut self, enabled: bool) {
+        self.cursor_blink_mode = enabled;
     }
     
-    pub fn reset_attrs(&mut self) {
-        self.current_attrs = CellAttributes::default();
-        self.current_fg = TerminalColor::White;
-        self.current_bg = TerminalColor::Black;
+    /// Record the blink part of a DECSCUSR style; `None` resets to the default style
+    pub fn set_cursor_style_blink(&mut self, blink: Option<bool>) {
+        self.cursor_style_blink = blink;
     }
     
-    // Scroll region methods
+    /// Whether the cursor should blink: an explicit DECSCUSR style wins over DECSET 12
+    pub fn cursor_blink_enabled(&self) -> bool {
+        self.cursor_style_blink.unwrap_or(self.cursor_blink_mode)
+    }
+    
+    pub fn set_cursor_shape(&mut self, shape: CursorShape) {
+        self.cursor_shape = shape;
+    }
+    
+    pub fn cursor_shape(&self) -> CursorShape {
+        self.cursor_shape
+    }
+    
+    /// A cell erased with the current background color
+    fn blank(&self) -> Cell {
+        Cell::blank_with(self.current_bg)
+    }
+    
+    // Screen clearing methods; erased cells take the current background color
+    pub fn clear_screen(&mut self) {
+        let blank = self.blank();
+        for row in &mut self.cells {
+            row.fill(blank.clone());
+        }
+        self.line_attrs.fill(LineAttr::Normal);
+        self.wrapped.fill(false);
+        self.mark_all_dirty();
+    }
+    
+    /// ED 0: clear from the cursor to the end of the screen
+    pub fn clear_screen_from_cursor(&mut self) {
+        self.clear_line_from_cursor();
+        let below = self.cursor_row as usize + 1;
+        let blank = self.blank();
+        for row in &mut self.cells[below..] {
+            row.fill(blank.clone());
+        }
+        self.line_attrs[below..].fill(LineAttr::Normal);
+        self.wrapped[below..].fill(false);
+        self.mark_dirty(below as u16, 0, self.cols, self.rows - below as u16);
+    }
+    
+    /// ED 1: clear from the start of the screen through the cursor
+    pub fn clear_screen_to_cursor(&mut self) {
+        self.clear_line_to_cursor();
+        let above = self.cursor_row as usize;
+        let blank = self.blank();
+        for row in &mut self.cells[..above] {
+            row.fill(blank.clone());
+        }
+        self.line_attrs[..above].fill(LineAttr::Normal);
+        self.wrapped[..above].fill(false);
+        self.mark_dirty(0, 0, self.cols, above as u16);
+    }
+    
+    pub fn clear_line(&mut self) {
+        let blank = self.blank();
+        let cursor_row = self.cursor_index().0;
+        self.cells[cursor_row].fill(blank);
+        self.mark_dirty(self.cursor_row, 0, self.cols, 1);
+    }
+    
+    pub fn clear_line_from_cursor(&mut self) {
+        let blank = self.blank();
+        let cursor_row = self.cursor_index().0;
+        let row = &mut self.cells[cursor_row];
+        row[self.cursor_col as usize..].fill(blank);
+        self.mark_dirty(self.cursor_row, self.cursor_col, self.cols - self.cursor_col, 1);
+    }
+    
+    pub fn clear_line_to_cursor(&mut self) {
+        let blank = self.blank();
+        let cursor_row = self.cursor_index().0;
+        let row = &mut self.cells[cursor_row];
+        let end = (self.cursor_col as usize + 1).min(row.len());
+        row[..end].fill(blank);
+        self.mark_dirty(self.cursor_row, 0, self.cursor_col + 1, 1);
+    }
+    
+    // Attribute and color methods
+    pub fn set_attrs(&mut self, attrs: CellAttributes) {
+        self.current_attrs = attrs;
+    }
+    
+    pub fn set_fg_color(&mut self, color: TerminalColor) {
+        self.current_fg = color;
+    }
+    
+    pub fn set_bg_color(&mut self, color: TerminalColor) {
+        self.current_bg = color;
+    }
+    
+    pub fn current_attrs(&self) -> &CellAttributes {
+        &self.current_attrs
+    }
+    
+    /// Current `(foreground, background)` colors
+    pub fn current_colors(&self) -> (TerminalColor, TerminalColor) {
+        (self.current_fg, self.current_bg)
+    }
+    
+    /// Save the cursor position, pending wrap and attributes (DECSC)
+    pub fn save_cursor(&mut self) {
+        self.saved_cursor = Some(SavedCursor {
+            row: self.cursor_row,
+            col: self.cursor_col,
+            wrap_pending: self.wrap_pending,
+            attrs: self.current_attrs.clone(),
+            fg: self.current_fg,
+            bg: self.current_bg,
+        });
+    }
+    
+    /// Restore what `save_cursor` stored (DECRC); without a save the cursor
+    /// goes home and the attributes reset, as in xterm
+    pub fn restore_cursor(&mut self) {
+        match self.saved_cursor.clone() {
+            Some(saved) => {
+                self.set_cursor(saved.row, saved.col);
+                self.wrap_pending = saved.wrap_pending && self.cursor_col == saved.col;
+                self.current_attrs = saved.attrs;
+                self.current_fg = saved.fg;
+                self.current_bg = saved.bg;
+            }
+            None => {
+                self.set_cursor(0, 0);
+                self.reset_attrs();
+            }
+        }
+    }
+    
+    /// Switch to a blank alternate screen (DECSET 47/1047); no-op if already there
+    pub fn enter_alternate_screen(&mut self) {
+        if self.main_screen.is_some() {
+            return;
+        }
+        self.last_grapheme = None;
+        // Full-screen apps are always shown live
+        self.scroll_to_bottom();
+        
+        let blank = InactiveScreen {
+            cells: vec![vec![Cell::empty(); self.cols as usize]; self.rows as usize],
+            line_attrs: vec![LineAttr::Normal; self.rows as usize],
+            wrapped: vec![false; self.rows as usize],
+            saved_cursor: None,
+        };
+        self.main_screen = Some(self.swap_screen(blank));
+        self.mark_all_dirty();
+    }
+    
+    /// Return to the main screen, discarding the alternate screen's contents
+    pub fn exit_alternate_screen(&mut self) {
+        let Some(mut main) = self.main_screen.take() else {
+            return;
+        };
+        self.last_grapheme = None;
+        
+        // The window may have been resized while the alternate screen was up
+        let (rows, cols) = (self.rows as usize, self.cols as usize);
+        main.cells.resize_with(rows, || vec![Cell::empty(); cols]);
+        for row in &mut main.cells {
+            row.resize(cols, Cell::empty());
+        }
+        main.line_attrs.resize(rows, LineAttr::Normal);
+        main.wrapped.resize(rows, false);
+        
+        self.swap_screen(main);
+        self.set_cursor(self.cursor_row, self.cursor_col);
+        self.mark_all_dirty();
+    }
+    
+    pub fn in_alternate_screen(&self) -> bool {
+        self.main_screen.is_some()
+    }
+    
+    /// Show `screen`, returning the one that was shown
+    fn swap_screen(&mut self, screen: InactiveScreen) -> InactiveScreen {
+        InactiveScreen {
+            cells: std::mem::replace(&mut self.cells, screen.cells),
+            line_attrs: std::mem::replace(&mut self.line_attrs, screen.line_attrs),
+            wrapped: std::mem::replace(&mut self.wrapped, screen.wrapped),
+            saved_cursor: std::mem::replace(&mut self.saved_cursor, screen.saved_cursor),
+        }
+    }
+    
+    pub fn reset_attrs(&mut self) {
+        self.current_attrs = CellAttributes::default();
+        self.current_fg = TerminalColor::White;
+        self.current_bg = TerminalColor::Black;
+    }
+    
+    /// Soft terminal reset (DECSTR): the pen, scroll region, autowrap,
+    /// insert mode, cursor visibility and saved cursor go back to their defaults while
+    /// the screen contents and cursor position stay as they are
+    pub fn soft_reset(&mut self) {
+        self.current_attrs = CellAttributes::default();
+        self.current_fg = TerminalColor::DefaultFg;
+        self.current_bg = TerminalColor::DefaultBg;
+        self.scroll_region_top = 0;
+        self.scroll_region_bottom = self.last_row();
+        self.autowrap = true;
+        self.insert_mode = false;
+        self.wrap_pending = false;
+        self.last_grapheme = None;
+        self.cursor_visible = true;
+        // A later DECRC goes home with default attributes
+        self.saved_cursor = None;
+    }
+    
+    // Scroll region methods
     pub fn set_scroll_region(&mut self, top: u16, bottom: u16) {
-        self.scroll_region_top = top.min(self.rows - 1);
-        self.scroll_region_bottom = bottom.min(self.rows - 1);
+        self.scroll_region_top = top.min(self.last_row());
+        self.scroll_region_bottom = bottom.min(self.last_row());
         
         if self.scroll_region_top > self.scroll_region_bottom {
             std::mem::swap(&mut self.scroll_region_top, &mut self.scroll_region_bottom);
         }
     }
     
+    /// Scrolling region as inclusive `(top, bottom)` rows
+    pub fn scroll_region(&self) -> (u16, u16) {
+        (self.scroll_region_top, self.scroll_region_bottom)
+    }
+    
     // Dirty region tracking
     fn mark_dirty(&mut self, row: u16, col: u16, width: u16, height: u16) {
-        self.dirty_regions.push(Region { row, col, width, height });
+        let screen_area = self.rows as u64 * self.cols as u64;
+        if self.dirty_area >= screen_area {
+            // Already a full redraw
+            return;
+        }
+        
+        // Repeated writes to the same area are already covered
+        let region = Region { row, col, width, height };
+        if let Some(last) = self.dirty_regions.last() {
+            if last.intersect(&region) == Some(region) {
+                return;
+            }
+        }
+        
+        self.dirty_area += width as u64 * height as u64;
+        if self.dirty_area * 100 > screen_area * FULL_REDRAW_THRESHOLD_PERCENT {
+            self.mark_all_dirty();
+            return;
+        }
+        
+        self.dirty_regions.push(region);
     }
     
     fn mark_all_dirty(&mut self) {
+        self.dirty_area = self.rows as u64 * self.cols as u64;
         self.dirty_regions.clear();
         self.dirty_regions.push(Region {
             row: 0,
@@ -338,6 +1338,7 @@ impl TextGrid {
     }
     
     pub fn take_dirty_regions(&mut self) -> Vec<Region> {
+        self.dirty_area = 0;
         std::mem::take(&mut self.dirty_regions)
     }
     
@@ -352,6 +1353,22 @@ impl TextGrid {
             .get(col as usize)
     }
     
+    /// Cells inside `region` as `(row, col, cell)` in row-major order; the parts
+    /// of the region outside the grid are skipped
+    pub fn region_cells(&self, region: &Region) -> impl Iterator<Item = (u16, u16, &Cell)> + '_ {
+        let screen = Region { row: 0, col: 0, width: self.cols, height: self.rows };
+        let (row_start, row_end, col_start, col_end) = screen
+            .intersect(region)
+            .map_or((0, 0, 0, 0), |r| (r.row, r.row + r.height, r.col, r.col + r.width));
+        
+        (row_start..row_end).flat_map(move |row| {
+            self.cells[row as usize][col_start as usize..col_end as usize]
+                .iter()
+                .zip(col_start..)
+                .map(move |(cell, col)| (row, col, cell))
+        })
+    }
+    
     pub fn set_cell(&mut self, row: u16, col: u16, cell: &Cell) {
         if let Some(row_cells) = self.cells.get_mut(row as usize) {
             if let Some(target_cell) = row_cells.get_mut(col as usize) {
@@ -361,6 +1378,159 @@ impl TextGrid {
         }
     }
     
+    /// Copy the cells and row attributes of `src` that differ from this grid,
+    /// marking only the changed cells dirty, and the scrollback lines `src`
+    /// gained since the last copy. Where the grids differ in size, only the
+    /// overlapping top-left area is copied.
+    pub fn copy_from(&mut self, src: &TextGrid) {
+        let rows = self.rows.min(src.rows);
+        let cols = self.cols.min(src.cols) as usize;
+        
+        // Added as `src` got them, so a scrolled-up viewport here follows
+        // `scroll_on_output`
+        self.scroll_on_output = src.scroll_on_output;
+        let added = src.lines_scrolled.saturating_sub(self.lines_scrolled).min(src.scrollback.len() as u64) as usize;
+        for line in src.scrollback.range(src.scrollback.len() - added..) {
+            self.push_scrollback(line.clone());
+        }
+        self.lines_scrolled = src.lines_scrolled;
+        if self.scrollback.len() != src.scrollback.len() {
+            // Out of step, e.g. after resizing only one of the grids
+            self.scrollback = src.scrollback.clone();
+            self.scroll_offset = self.scroll_offset.min(self.scrollback.len());
+            self.mark_all_dirty();
+        }
+        
+        for row in 0..rows {
+            let index = row as usize;
+            if self.line_attrs[index] != src.line_attrs[index] {
+                self.line_attrs[index] = src.line_attrs[index];
+                self.mark_dirty(row, 0, self.cols, 1);
+            }
+            self.wrapped[index] = src.wrapped[index];
+            
+            // Changed cells are marked in runs so a rewritten line is one region
+            let mut run_start = None;
+            for col in 0..=cols {
+                let changed = col < cols && self.cells[index][col] != src.cells[index][col];
+                if changed {
+                    self.cells[index][col] = src.cells[index][col].clone();
+                    run_start.get_or_insert(col);
+                } else if let Some(start) = run_start.take() {
+                    self.mark_dirty(row, start as u16, (col - start) as u16, 1);
+                }
+            }
+        }
+        
+        // Screen changes land on other rows of a scrolled viewport
+        if self.scroll_offset > 0 && (added > 0 || !self.dirty_regions.is_empty()) {
+            self.mark_all_dirty();
+        }
+    }
+    
+    /// Visible text as logical lines: a soft-wrapped row is joined with the
+    /// row after it, and trailing blanks are trimmed from the end of each
+    /// line. Scrollback is not included, as it doesn't record which rows wrapped.
+    pub fn logical_lines(&self) -> impl Iterator<Item = String> + '_ {
+        let mut row = 0;
+        std::iter::from_fn(move || {
+            if row >= self.cells.len() {
+                return None;
+            }
+            
+            let mut line = String::new();
+            loop {
+                let cells = &self.cells[row];
+                let continues = self.wrapped[row] && row + 1 < self.cells.len();
+                row += 1;
+                if !continues {
+                    line.push_str(cells_text(cells).trim_end_matches(' '));
+                    return Some(line);
+                }
+                
+                // Blanks typed at the end of a wrapped row are text, but cells
+                // never written (left by a wide character that wrapped) are not
+                let end = cells.iter().rposition(|cell| cell.ch != '\0').map_or(0, |col| col + 1);
+                line.push_str(&cells_text(&cells[..end]));
+            }
+        })
+    }
+    
+    /// Start a new selection at the visible cell `(row, col)`, replacing any
+    /// previous one
+    pub fn start_selection(&mut self, row: u16, col: u16) {
+        let (row, col) = self.clamp_to_screen(row, col);
+        self.selection = Some(Selection::new(row, col));
+    }
+    
+    /// Drag the free end of the selection to `(row, col)`; does nothing
+    /// without a selection
+    pub fn extend_selection(&mut self, row: u16, col: u16) {
+        let (row, col) = self.clamp_to_screen(row, col);
+        if let Some(selection) = self.selection.as_mut() {
+            selection.extend_to(row, col);
+        }
+    }
+    
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+    
+    pub fn selection(&self) -> Option<&Selection> {
+        self.selection.as_ref()
+    }
+    
+    /// Text under the selection. Soft-wrapped rows are joined; other rows
+    /// end in a newline with trailing blanks trimmed.
+    pub fn selected_text(&self) -> Option<String> {
+        let ((start_row, start_col), (end_row, end_col)) = self.selection?.ordered();
+        
+        let mut text = String::new();
+        for row in start_row..=end_row {
+            let cells = &self.cells[row as usize];
+            let mut from = if row == start_row { start_col as usize } else { 0 };
+            // Starting on the right half of a wide character selects all of it
+            if is_wide_spacer(cells, from) {
+                from -= 1;
+            }
+            let to = if row == end_row { end_col as usize + 1 } else { cells.len() };
+            let piece = cells_text(&cells[from..to]);
+            
+            if row < end_row && self.wrapped[row as usize] {
+                text.push_str(&piece);
+            } else {
+                text.push_str(piece.trim_end_matches(' '));
+                if row < end_row {
+                    text.push('\n');
+                }
+            }
+        }
+        Some(text)
+    }
+    
+    fn clamp_to_screen(&self, row: u16, col: u16) -> (u16, u16) {
+        (row.min(self.last_row()), col.min(self.last_col()))
+    }
+    
+    /// Whether `row` was soft-wrapped, i.e. its text continues on the next row
+    pub fn is_wrapped(&self, row: u16) -> bool {
+        self.wrapped.get(row as usize).copied().unwrap_or(false)
+    }
+    
+    pub fn line_attr(&self, row: u16) -> LineAttr {
+        self.line_attrs.get(row as usize).copied().unwrap_or_default()
+    }
+    
+    /// Set the size attribute of a row (DECDWL/DECDHL/DECSWL)
+    pub fn set_line_attr(&mut self, row: u16, attr: LineAttr) {
+        if let Some(current) = self.line_attrs.get_mut(row as usize) {
+            if *current != attr {
+                *current = attr;
+                self.mark_dirty(row, 0, self.cols, 1);
+            }
+        }
+    }
+    
     pub fn row(&self, index: u16) -> Option<&Vec<Cell>> {
         self.cells.get(index as usize)
     }
@@ -376,6 +1546,13 @@ impl TextGrid {
     pub fn scrollback_len(&self) -> usize {
         self.scrollback.len()
     }
+    
+    /// Total rows scrolled off the top of the screen. Unlike `scrollback_len`
+    /// it keeps counting past the scrollback limit, so `lines_scrolled() + row`
+    /// identifies a line even after later scrolling.
+    pub fn lines_scrolled(&self) -> u64 {
+        self.lines_scrolled
+    }
 }
 
 #[cfg(test)]
@@ -401,6 +1578,22 @@ mod tests {
         assert_eq!(grid.cursor_position(), (0, 2));
     }
     
+    #[test]
+    fn test_write_str() {
+        let mut grid = TextGrid::new(24, 80);
+        grid.write_str("a\tb\nc");
+        
+        assert_eq!(grid.cell_at(0, 0).unwrap().ch, 'a');
+        assert_eq!(grid.cell_at(0, 8).unwrap().ch, 'b');
+        assert_eq!(grid.cell_at(1, 0).unwrap().ch, 'c');
+        assert_eq!(grid.cursor_position(), (1, 1));
+        
+        grid.write_str("xy\x08z\rw");
+        assert_eq!(grid.cell_at(1, 0).unwrap().ch, 'w');
+        assert_eq!(grid.cell_at(1, 2).unwrap().ch, 'z');
+        assert_eq!(grid.cursor_position(), (1, 1));
+    }
+    
     #[test]
     fn test_newline() {
         let mut grid = TextGrid::new(24, 80);
@@ -422,4 +1615,724 @@ mod tests {
         assert_eq!(grid.cols, 100);
         assert_eq!(grid.cell_at(0, 0).unwrap().ch, 'X');
     }
+    
+    #[test]
+    fn test_zero_dimensions_clamped() {
+        let mut grid = TextGrid::new(0, 0);
+        assert_eq!((grid.rows, grid.cols), (1, 1));
+        grid.write_char('A');
+        grid.newline();
+        grid.tab();
+        grid.set_scroll_region(0, 5);
+        assert_eq!(grid.cursor_position(), (0, 0));
+        
+        let mut grid = TextGrid::new(24, 80);
+        grid.set_cursor(10, 40);
+        grid.resize(0, 0);
+        assert_eq!((grid.rows, grid.cols), (1, 1));
+        assert_eq!(grid.cursor_position(), (0, 0));
+        grid.write_char('B');
+        assert_eq!(grid.cell_at(0, 0).unwrap().ch, 'B');
+    }
+    
+    #[test]
+    fn test_extreme_dimensions_capped() {
+        let grid = TextGrid::new(u16::MAX, 2);
+        assert_eq!((grid.rows, grid.cols), (MAX_GRID_DIMENSION, 2));
+        
+        let mut grid = TextGrid::new(2, 2);
+        grid.resize(3, u16::MAX);
+        assert_eq!((grid.rows, grid.cols), (3, MAX_GRID_DIMENSION));
+    }
+    
+    #[test]
+    fn test_single_cell_grid_never_panics() {
+        for (rows, cols) in [(1, 1), (1, 4), (4, 1)] {
+            let mut grid = TextGrid::new(rows, cols);
+            grid.write_str("ab字c\r\n\tx\x08\x08y\n\n");
+            grid.index();
+            grid.reverse_index();
+            grid.cursor_down(5);
+            grid.cursor_right(9);
+            grid.set_scroll_region(3, 9);
+            grid.scroll_up(3);
+            grid.scroll_down(3);
+            grid.clear_screen_from_cursor();
+            grid.clear_line_to_cursor();
+            
+            // Shrinking past the cursor, with saved cursors on both screens
+            grid.save_cursor();
+            grid.resize(1, 1);
+            grid.write_str("z字\n");
+            grid.enter_alternate_screen();
+            grid.resize(6, 6);
+            grid.set_cursor(5, 5);
+            grid.save_cursor();
+            grid.resize(1, 1);
+            grid.restore_cursor();
+            grid.write_str("字\n");
+            grid.exit_alternate_screen();
+            grid.restore_cursor();
+            
+            grid.set_autowrap(false);
+            grid.set_destructive_backspace(true);
+            grid.write_str("字x\u{301}\x08\x08");
+            grid.start_selection(9, 9);
+            grid.extend_selection(0, 0);
+            assert!(grid.selected_text().is_some());
+            
+            let (row, col) = grid.cursor_position();
+            assert!(row < grid.rows && col < grid.cols, "{}x{}: cursor ({}, {})", rows, cols, row, col);
+            assert_eq!(grid.scroll_region(), (0, grid.rows - 1));
+        }
+    }
+    
+    #[test]
+    fn test_line_attrs_follow_scroll() {
+        let mut grid = TextGrid::new(3, 10);
+        grid.set_line_attr(1, LineAttr::DoubleWidth);
+        assert_eq!(grid.line_attr(1), LineAttr::DoubleWidth);
+        
+        grid.scroll_up(1);
+        assert_eq!(grid.line_attr(0), LineAttr::DoubleWidth);
+        assert_eq!(grid.line_attr(2), LineAttr::Normal);
+        
+        grid.clear_screen();
+        assert_eq!(grid.line_attr(0), LineAttr::Normal);
+    }
+    
+    #[test]
+    fn test_resolved_colors_default() {
+        let palette = Palette::default();
+        let cell = Cell::empty();
+        assert_eq!(cell.resolved_colors(&palette), (palette.default_fg, palette.default_bg));
+        
+        let cell = Cell::new('x');
+        assert_eq!(cell.resolved_colors(&palette), (palette.ansi[7], palette.ansi[0]));
+    }
+    
+    #[test]
+    fn test_resolved_colors_reverse_and_dim() {
+        let palette = Palette::default();
+        let mut cell = Cell::new('x');
+        cell.fg_color = TerminalColor::Red;
+        cell.bg_color = TerminalColor::DefaultBg;
+        
+        cell.attrs.reverse = true;
+        assert_eq!(cell.resolved_colors(&palette), (palette.default_bg, palette.ansi[1]));
+        
+        cell.attrs.reverse = false;
+        cell.attrs.dim = true;
+        let (fg, bg) = cell.resolved_colors(&palette);
+        assert_eq!(fg, Rgba::rgb(135, 0, 0));
+        assert_eq!(bg, palette.default_bg);
+        
+        // Dim applies to the displayed foreground, i.e. after the reverse swap
+        cell.attrs.reverse = true;
+        let (fg, bg) = cell.resolved_colors(&palette);
+        assert_eq!(fg, palette.default_bg.scaled(DIM_FACTOR));
+        assert_eq!(bg, palette.ansi[1]);
+    }
+    
+    #[test]
+    fn test_dirty_regions_collapse_to_full_redraw() {
+        let mut grid = TextGrid::new(10, 10);
+        grid.take_dirty_regions();
+        
+        // Half the screen in single-cell marks stays granular
+        for row in 0..5 {
+            for col in 0..10 {
+                grid.set_char(row, col, 'x');
+            }
+        }
+        assert_eq!(grid.take_dirty_regions().len(), 50);
+        
+        // Crossing the threshold collapses everything into one region
+        for row in 0..6 {
+            for col in 0..10 {
+                grid.set_char(row, col, 'y');
+            }
+        }
+        let regions = grid.take_dirty_regions();
+        assert_eq!(regions.len(), 1);
+        assert_eq!((regions[0].row, regions[0].col, regions[0].width, regions[0].height), (0, 0, 10, 10));
+        
+        // Tracking starts over after the regions are taken
+        grid.set_char(0, 0, 'z');
+        assert_eq!(grid.take_dirty_regions().len(), 1);
+        assert!(!grid.is_dirty());
+    }
+    
+    #[test]
+    fn test_cursor_state_matches_getters() {
+        let mut grid = TextGrid::new(5, 10);
+        let check = |grid: &TextGrid| {
+            let cursor = grid.cursor();
+            assert_eq!((cursor.row, cursor.col), grid.cursor_position());
+            assert_eq!(cursor.visible, grid.cursor_visible());
+            assert_eq!(cursor.shape, grid.cursor_shape());
+            assert_eq!(cursor.blinking, grid.cursor_blink_enabled());
+        };
+        check(&grid);
+        assert_eq!(grid.cursor().shape, CursorShape::Block);
+        
+        grid.write_str("hello");
+        grid.newline();
+        grid.cursor_right(3);
+        grid.set_cursor_visible(false);
+        grid.set_cursor_shape(CursorShape::Bar);
+        grid.set_cursor_blink_mode(true);
+        check(&grid);
+        assert_eq!(
+            grid.cursor(),
+            CursorState { row: 1, col: grid.cursor_position().1, visible: false, shape: CursorShape::Bar, blinking: true }
+        );
+        
+        grid.set_cursor_style_blink(Some(false));
+        grid.resize(3, 4);
+        check(&grid);
+        assert!(!grid.cursor().blinking);
+    }
+    
+    #[test]
+    fn test_autowrap() {
+        let mut grid = TextGrid::new(3, 5);
+        for ch in "abcde".chars() {
+            grid.write_char(ch);
+        }
+        // Writing the last column leaves the cursor there until the next character
+        assert_eq!(grid.cursor_position(), (0, 4));
+        assert!(!grid.is_wrapped(0));
+        
+        grid.write_char('f');
+        assert!(grid.is_wrapped(0));
+        assert_eq!(grid.cell_at(1, 0).unwrap().ch, 'f');
+        assert_eq!(grid.cursor_position(), (1, 1));
+    }
+    
+    #[test]
+    fn test_autowrap_off_overwrites_last_column() {
+        let mut grid = TextGrid::new(3, 5);
+        grid.set_autowrap(false);
+        grid.write_str("abcdefg");
+        
+        assert_eq!(grid.logical_lines().next().unwrap(), "abcdg");
+        assert_eq!(grid.cursor_position(), (0, 4));
+        assert!(!grid.wrap_pending() && !grid.is_wrapped(0));
+        
+        // A wide character still fits, in the last two columns
+        grid.write_char('字');
+        assert_eq!(grid.cell_at(0, 3).unwrap().ch, '字');
+        assert_eq!(grid.cursor_position(), (0, 4));
+    }
+    
+    #[test]
+    fn test_insert_mode_shifts_row() {
+        let mut grid = TextGrid::new(3, 6);
+        grid.write_str("abcdef");
+        grid.set_cursor(0, 1);
+        grid.set_insert_mode(true);
+        grid.write_str("XY");
+        
+        // The tail shifts right and falls off the last column
+        assert_eq!(grid.logical_lines().next().unwrap(), "aXYbcd");
+        assert_eq!(grid.cursor_position(), (0, 3));
+        
+        // A wide character makes room for both of its cells
+        grid.write_char('字');
+        assert_eq!(grid.logical_lines().next().unwrap(), "aXY字b");
+        
+        grid.set_insert_mode(false);
+        grid.set_cursor(0, 0);
+        grid.write_char('Z');
+        assert_eq!(grid.logical_lines().next().unwrap(), "ZXY字b");
+    }
+    
+    #[test]
+    fn test_resize_reflow_keeps_cursor_on_character() {
+        let mut grid = TextGrid::new(5, 10);
+        for ch in "0123456789abcdef".chars() {
+            grid.write_char(ch);
+        }
+        grid.set_cursor(1, 2);
+        assert_eq!(grid.cell_at(1, 2).unwrap().ch, 'c');
+        
+        grid.resize(5, 6);
+        let (row, col) = grid.cursor_position();
+        assert_eq!((row, col), (2, 0));
+        assert_eq!(grid.cell_at(row, col).unwrap().ch, 'c');
+        assert_eq!(grid.cell_at(1, 0).unwrap().ch, '6');
+        
+        // Widening joins the rows back together
+        grid.resize(5, 20);
+        let (row, col) = grid.cursor_position();
+        assert_eq!((row, col), (0, 12));
+        assert_eq!(grid.cell_at(row, col).unwrap().ch, 'c');
+        assert!(!grid.is_wrapped(0));
+    }
+    
+    #[test]
+    fn test_resize_cursor_in_trailing_blank_snaps_to_content() {
+        let mut grid = TextGrid::new(3, 10);
+        grid.write_char('a');
+        grid.write_char('b');
+        grid.set_cursor(0, 8);
+        
+        grid.resize(3, 5);
+        assert_eq!(grid.cursor_position(), (0, 2));
+    }
+    
+    #[test]
+    fn test_resize_fewer_rows_keeps_cursor_line() {
+        let mut grid = TextGrid::new(5, 10);
+        for row in 0..4 {
+            grid.set_cursor(row, 0);
+            grid.write_char(char::from(b'A' + row as u8));
+        }
+        
+        grid.resize(2, 10);
+        assert_eq!(grid.cursor_position(), (1, 1));
+        assert_eq!(grid.cell_at(1, 0).unwrap().ch, 'D');
+        assert_eq!(grid.scrollback_len(), 2);
+    }
+    
+    #[test]
+    fn test_scrollback_only_from_full_screen_scroll() {
+        let mut grid = TextGrid::new(5, 10);
+        grid.scroll_up(2);
+        assert_eq!(grid.scrollback_len(), 2);
+        
+        // Scrolling below a status line rotates the region without history
+        grid.set_char(1, 0, 'x');
+        grid.set_scroll_region(1, 4);
+        grid.scroll_up(3);
+        assert_eq!(grid.scrollback_len(), 2);
+        assert_eq!(grid.cell_at(1, 0).unwrap().ch, '\0');
+        
+        grid.set_scroll_region(0, 4);
+        grid.scroll_up(1);
+        assert_eq!(grid.scrollback_len(), 3);
+    }
+    
+    #[test]
+    fn test_output_while_scrolled_up() {
+        let first_char = |grid: &TextGrid, row| grid.viewport_row(row).unwrap()[0].ch;
+        let scrolled_grid = |scroll_on_output| {
+            let mut grid = TextGrid::new(3, 10);
+            grid.set_scroll_on_output(scroll_on_output);
+            grid.write_str("1\r\n2\r\n3\r\n4\r\n5");
+            assert_eq!(grid.scrollback_len(), 2);
+            grid.scroll_viewport_up(10);
+            assert_eq!(grid.scroll_offset(), 2);
+            grid
+        };
+        
+        // Scroll lock: the same history stays in view as more lines arrive
+        let mut grid = scrolled_grid(false);
+        assert_eq!(first_char(&grid, 0), '1');
+        grid.write_str("\r\n6\r\n7");
+        assert_eq!(grid.scroll_offset(), 4);
+        assert_eq!((first_char(&grid, 0), first_char(&grid, 2)), ('1', '3'));
+        grid.scroll_viewport_down(1);
+        assert_eq!(first_char(&grid, 0), '2');
+        
+        // Scroll on output: back to the live screen
+        let mut grid = scrolled_grid(true);
+        grid.write_str("\r\n6");
+        assert_eq!(grid.scroll_offset(), 0);
+        assert_eq!((first_char(&grid, 0), first_char(&grid, 2)), ('4', '6'));
+        
+        // Output that doesn't scroll leaves either viewport alone
+        let mut grid = scrolled_grid(true);
+        grid.write_str("!");
+        assert_eq!(grid.scroll_offset(), 2);
+    }
+    
+    #[test]
+    fn test_copy_from_carries_scrollback_to_scrolled_viewport() {
+        let first_char = |grid: &TextGrid, row| grid.viewport_row(row).unwrap()[0].ch;
+        let mut parser_grid = TextGrid::new(3, 10);
+        parser_grid.set_scroll_on_output(false);
+        let mut render_grid = TextGrid::new(3, 10);
+        
+        parser_grid.write_str("1\r\n2\r\n3\r\n4");
+        render_grid.copy_from(&parser_grid);
+        assert_eq!(render_grid.scrollback_len(), 1);
+        render_grid.scroll_viewport_up(1);
+        assert_eq!((first_char(&render_grid, 0), first_char(&render_grid, 1)), ('1', '2'));
+        
+        // Only new lines are added, and the history being read stays put
+        parser_grid.write_str("\r\n5");
+        render_grid.copy_from(&parser_grid);
+        render_grid.copy_from(&parser_grid);
+        assert_eq!(render_grid.scrollback_len(), 2);
+        assert_eq!(render_grid.scroll_offset(), 2);
+        assert_eq!(first_char(&render_grid, 0), '1');
+        render_grid.scroll_to_bottom();
+        assert_eq!(first_char(&render_grid, 0), '3');
+        
+        // Scrolled up, the cursor's row moves down and then out of view
+        assert_eq!(parser_grid.viewport_cursor_row(), Some(2));
+        parser_grid.scroll_viewport_up(1);
+        assert_eq!(parser_grid.viewport_cursor_row(), None);
+        assert_eq!(parser_grid.viewport_line_attr(0), LineAttr::Normal);
+    }
+    
+    #[test]
+    fn test_alternate_screen_keeps_scrollback() {
+        let history = |grid: &TextGrid| -> Vec<char> { grid.scrollback().iter().map(|row| row[0].ch).collect() };
+        let run = |alternate_scrollback| {
+            let mut grid = TextGrid::new(3, 10);
+            grid.set_alternate_scrollback(alternate_scrollback);
+            grid.write_str("1\r\n2\r\n3\r\n4");
+            assert_eq!(history(&grid), ['1']);
+            
+            grid.enter_alternate_screen();
+            grid.set_cursor(0, 0);
+            grid.write_str("a\r\nb\r\nc\r\nd");
+            grid.scroll_up(1);
+            // Shrinking pushes the top row out of the screen as well
+            grid.resize(2, 10);
+            grid.exit_alternate_screen();
+            grid
+        };
+        
+        let grid = run(false);
+        assert_eq!(history(&grid), ['1']);
+        assert_eq!(grid.lines_scrolled(), 1);
+        
+        let grid = run(true);
+        assert_eq!(history(&grid), ['1', 'a', 'b', 'c']);
+    }
+    
+    #[test]
+    fn test_carriage_return_redraws_progress_bar() {
+        let mut grid = TextGrid::new(2, 20);
+        grid.current_fg = TerminalColor::Red;
+        grid.write_str("[##  ] 50%");
+        grid.current_fg = TerminalColor::Green;
+        grid.write_str("\r[### ] 75%\r[####] 100%");
+        assert_eq!(grid.to_string().lines().next().unwrap().trim_end(), "[####] 100%");
+        assert_eq!(grid.cursor_position(), (0, 11));
+        // Every redrawn cell takes the new colors
+        assert!((0..11).all(|col| grid.cell_at(0, col).unwrap().fg_color == TerminalColor::Green));
+        
+        // A shorter redraw keeps whatever it doesn't overwrite, as in other
+        // terminals; programs clear the rest with EL
+        grid.write_str("\rdone");
+        assert_eq!(grid.to_string().lines().next().unwrap().trim_end(), "done#] 100%");
+        
+        // Overwriting half of a wide character leaves no half glyph behind
+        grid.write_str("\r\n中文");
+        grid.set_cursor(1, 3);
+        grid.write_char('x');
+        assert_eq!(grid.to_string().lines().nth(1).unwrap().trim_end(), "中 x");
+    }
+    
+    #[test]
+    fn test_region_cells() {
+        let mut grid = TextGrid::new(3, 4);
+        grid.write_str("abcd");
+        grid.set_char(1, 1, 'x');
+        
+        let region = Region { row: 0, col: 1, width: 2, height: 2 };
+        let cells: Vec<(u16, u16, char)> = grid.region_cells(&region).map(|(r, c, cell)| (r, c, cell.ch)).collect();
+        assert_eq!(cells, [(0, 1, 'b'), (0, 2, 'c'), (1, 1, 'x'), (1, 2, '\0')]);
+    }
+    
+    #[test]
+    fn test_region_cells_clamped_to_grid() {
+        let grid = TextGrid::new(3, 4);
+        
+        // Partially off the bottom-right corner
+        let region = Region { row: 2, col: 2, width: 10, height: 10 };
+        let positions: Vec<(u16, u16)> = grid.region_cells(&region).map(|(r, c, _)| (r, c)).collect();
+        assert_eq!(positions, [(2, 2), (2, 3)]);
+        
+        // Entirely outside, including sizes that would overflow u16
+        let region = Region { row: 5, col: u16::MAX, width: u16::MAX, height: 1 };
+        assert_eq!(grid.region_cells(&region).count(), 0);
+        let region = Region { row: 0, col: 7, width: 2, height: 3 };
+        assert_eq!(grid.region_cells(&region).count(), 0);
+    }
+    
+    #[test]
+    fn test_region_overlapping() {
+        let a = Region { row: 0, col: 0, width: 4, height: 3 };
+        let b = Region { row: 2, col: 2, width: 4, height: 4 };
+        
+        assert!(a.contains(2, 3));
+        assert!(!a.contains(3, 0));
+        assert!(!a.contains(0, 4));
+        assert_eq!(a.intersect(&b), Some(Region { row: 2, col: 2, width: 2, height: 1 }));
+        assert_eq!(b.intersect(&a), a.intersect(&b));
+        assert_eq!(a.union(&b), Region { row: 0, col: 0, width: 6, height: 6 });
+        
+        // Containment
+        let inner = Region { row: 1, col: 1, width: 1, height: 1 };
+        assert_eq!(a.intersect(&inner), Some(inner));
+        assert_eq!(a.union(&inner), a);
+    }
+    
+    #[test]
+    fn test_region_adjacent_and_disjoint() {
+        let left = Region { row: 0, col: 0, width: 2, height: 2 };
+        let right = Region { row: 0, col: 2, width: 2, height: 2 };
+        // Sharing an edge is not an overlap
+        assert_eq!(left.intersect(&right), None);
+        assert_eq!(left.union(&right), Region { row: 0, col: 0, width: 4, height: 2 });
+        
+        let far = Region { row: 10, col: 10, width: 1, height: 1 };
+        assert_eq!(left.intersect(&far), None);
+        assert_eq!(left.union(&far), Region { row: 0, col: 0, width: 11, height: 11 });
+        
+        // Empty regions contain nothing and don't grow a union
+        let empty = Region { row: 5, col: 5, width: 0, height: 3 };
+        assert!(!empty.contains(5, 5));
+        assert_eq!(empty.intersect(&left), None);
+        assert_eq!(left.union(&empty), left);
+        
+        // Edges beyond u16::MAX don't wrap
+        let edge = Region { row: u16::MAX - 1, col: u16::MAX - 1, width: 10, height: 10 };
+        assert!(edge.contains(u16::MAX, u16::MAX));
+        assert_eq!(edge.intersect(&left), None);
+    }
+    
+    #[test]
+    fn test_repeated_writes_mark_once() {
+        let mut grid = TextGrid::new(10, 10);
+        grid.take_dirty_regions();
+        
+        grid.set_char(3, 3, 'a');
+        grid.set_char(3, 3, 'b');
+        assert_eq!(grid.take_dirty_regions(), [Region { row: 3, col: 3, width: 1, height: 1 }]);
+    }
+    
+    #[test]
+    fn test_custom_tab_stop() {
+        let mut grid = TextGrid::new(2, 20);
+        grid.set_cursor(0, 3);
+        grid.set_tab_stop();
+        
+        grid.set_cursor(0, 0);
+        grid.tab();
+        assert_eq!(grid.cursor_position(), (0, 3));
+        grid.tab();
+        assert_eq!(grid.cursor_position(), (0, 8));
+        grid.tab();
+        grid.tab();
+        assert_eq!(grid.cursor_position(), (0, 19));
+    }
+    
+    #[test]
+    fn test_resize_keeps_custom_tab_stops() {
+        let mut grid = TextGrid::new(2, 20);
+        grid.set_cursor(0, 3);
+        grid.set_tab_stop();
+        grid.set_cursor(0, 8);
+        grid.clear_tab_stop();
+        
+        grid.resize(2, 40);
+        let stops: Vec<u16> = (0..40).filter(|&col| grid.tab_stops[col as usize]).collect();
+        // The custom stop and the cleared default survive; new columns get defaults
+        assert_eq!(stops, [3, 16, 24, 32]);
+        
+        // Columns cut off and grown back start over with the defaults
+        grid.clear_all_tab_stops();
+        grid.resize(2, 10);
+        grid.resize(2, 20);
+        let stops: Vec<u16> = (0..20).filter(|&col| grid.tab_stops[col as usize]).collect();
+        assert_eq!(stops, [16]);
+    }
+    
+    #[test]
+    fn test_index_and_reverse_index() {
+        let mut grid = TextGrid::new(3, 10);
+        grid.write_str("top");
+        grid.set_cursor(2, 4);
+        
+        // IND keeps the column and scrolls at the bottom
+        grid.index();
+        assert_eq!(grid.cursor_position(), (2, 4));
+        assert_eq!(grid.cell_at(1, 0).unwrap().ch, '\0');
+        assert_eq!(grid.scrollback_len(), 1);
+        
+        // RI scrolls down at the top
+        grid.set_cursor(0, 4);
+        grid.reverse_index();
+        assert_eq!(grid.cursor_position(), (0, 4));
+        assert_eq!(grid.cell_at(0, 0).unwrap().ch, '\0');
+    }
+    
+    #[test]
+    fn test_display_plain_text() {
+        let mut grid = TextGrid::new(3, 10);
+        grid.write_str("ls\r\na  b");
+        assert_eq!(grid.to_string(), "ls\na  b\n");
+    }
+    
+    #[test]
+    fn test_copy_from_marks_only_changed_cells() {
+        let mut src = TextGrid::new(4, 10);
+        let mut display = TextGrid::new(4, 10);
+        for (col, ch) in "hello".chars().enumerate() {
+            src.set_char(1, col as u16, ch);
+        }
+        display.copy_from(&src);
+        assert_eq!(display.take_dirty_regions(), vec![Region { row: 1, col: 0, width: 5, height: 1 }]);
+        assert_eq!(display.to_string(), src.to_string());
+        
+        // Identical content copies nothing
+        display.copy_from(&src);
+        assert!(!display.is_dirty());
+        
+        // Two separate edits give two single-cell regions
+        src.set_char(1, 1, 'a');
+        src.set_char(3, 9, '!');
+        display.copy_from(&src);
+        assert_eq!(
+            display.take_dirty_regions(),
+            vec![
+                Region { row: 1, col: 1, width: 1, height: 1 },
+                Region { row: 3, col: 9, width: 1, height: 1 },
+            ]
+        );
+        assert_eq!(display.to_string(), "\nhallo\n\n         !");
+        
+        // Only the overlap of differently sized grids is copied
+        let mut small = TextGrid::new(2, 3);
+        small.copy_from(&src);
+        assert_eq!(small.to_string(), "\nhal");
+    }
+    
+    #[test]
+    fn test_wide_characters_take_two_cells() {
+        let mut grid = TextGrid::new(2, 5);
+        grid.write_str("a中");
+        assert_eq!(grid.cursor_position(), (0, 3));
+        assert_eq!(grid.cell_at(0, 1).unwrap().ch, '中');
+        assert_eq!(grid.cell_at(0, 2).unwrap().ch, '\0');
+        
+        // Only one column is left, so the next wide character wraps
+        grid.write_str("b文");
+        assert_eq!(grid.cursor_position(), (1, 2));
+        assert!(grid.is_wrapped(0));
+        assert_eq!(grid.to_string(), "a中b\n文");
+    }
+    
+    #[test]
+    fn test_backspace_over_graphemes() {
+        let mut grid = TextGrid::new(2, 10);
+        grid.write_str("a中");
+        grid.backspace();
+        assert_eq!(grid.cursor_position(), (0, 1));
+        
+        // e + COMBINING ACUTE ACCENT is one grapheme, held in one cell
+        let mut grid = TextGrid::new(2, 10);
+        grid.write_str("xe\u{301}\u{302}");
+        assert_eq!(grid.cursor_position(), (0, 2));
+        assert_eq!(grid.cell_at(0, 1).unwrap().grapheme(), "e\u{301}\u{302}");
+        grid.backspace();
+        assert_eq!(grid.cursor_position(), (0, 1));
+        grid.backspace();
+        assert_eq!(grid.cursor_position(), (0, 0));
+        grid.backspace();
+        assert_eq!(grid.cursor_position(), (0, 0));
+        // Non-destructive by default
+        assert_eq!(grid.to_string(), "xe\u{301}\u{302}\n");
+        
+        // A mark after the cursor moved has no base to join
+        let mut grid = TextGrid::new(2, 10);
+        grid.write_str("e\r\u{301}");
+        assert_eq!(grid.cursor_position(), (0, 1));
+        assert_eq!(grid.cell_at(0, 0).unwrap().grapheme(), "\u{301}");
+    }
+    
+    #[test]
+    fn test_emoji_sequences_take_one_cell_group() {
+        // WOMAN ZWJ WOMAN ZWJ GIRL
+        let family = "\u{1F469}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let mut grid = TextGrid::new(2, 10);
+        grid.write_str(family);
+        assert_eq!(grid.cursor_position(), (0, 2));
+        assert_eq!(grid.cell_at(0, 0).unwrap().grapheme(), family);
+        assert!(is_wide_spacer(grid.row(0).unwrap(), 1));
+        grid.write_char('!');
+        assert_eq!(grid.to_string(), format!("{}!\n", family));
+        
+        // Regional indicators P + T: narrow alone, a wide flag together
+        let flag = "\u{1F1F5}\u{1F1F9}";
+        let mut grid = TextGrid::new(2, 10);
+        grid.write_str(flag);
+        grid.write_str(flag);
+        assert_eq!(grid.cursor_position(), (0, 4));
+        assert_eq!(grid.cell_at(0, 0).unwrap().grapheme(), flag);
+        assert_eq!(grid.cell_at(0, 2).unwrap().grapheme(), flag);
+        assert_eq!(grid.to_string(), format!("{}{}\n", flag, flag));
+        grid.backspace();
+        assert_eq!(grid.cursor_position(), (0, 2));
+        
+        // HEAVY BLACK HEART + VS16 switches to emoji presentation
+        let mut grid = TextGrid::new(2, 10);
+        grid.write_str("\u{2764}\u{FE0F}x");
+        assert_eq!(grid.cursor_position(), (0, 3));
+        assert_eq!(grid.cell_at(0, 2).unwrap().ch, 'x');
+    }
+    
+    #[test]
+    fn test_clear_uses_current_background() {
+        let mut grid = TextGrid::new(3, 10);
+        grid.write_str("hello");
+        grid.set_bg_color(TerminalColor::Blue);
+        grid.clear_line();
+        
+        let row = grid.row(0).unwrap();
+        assert!(row.iter().all(|cell| *cell == Cell::blank_with(TerminalColor::Blue)));
+        assert_eq!(grid.to_string(), "\n\n");
+        
+        // Partial erases leave the rest of the line alone
+        grid.set_bg_color(TerminalColor::Red);
+        grid.set_cursor(1, 0);
+        grid.write_str("abcdef");
+        grid.set_cursor(1, 3);
+        grid.clear_line_from_cursor();
+        let row = grid.row(1).unwrap();
+        assert_eq!(row[2].ch, 'c');
+        assert!(row[3..].iter().all(|cell| cell.is_empty() && cell.bg_color == TerminalColor::Red));
+        
+        // Lines scrolled in are blank in the current background too
+        grid.set_bg_color(TerminalColor::Green);
+        grid.scroll_up(1);
+        assert!(grid.row(2).unwrap().iter().all(|cell| cell.bg_color == TerminalColor::Green));
+    }
+    
+    #[test]
+    fn test_destructive_backspace() {
+        let mut grid = TextGrid::new(2, 10);
+        grid.set_destructive_backspace(true);
+        grid.write_str("ok中");
+        grid.take_dirty_regions();
+        
+        grid.backspace();
+        assert_eq!(grid.cursor_position(), (0, 2));
+        assert_eq!(grid.to_string(), "ok\n");
+        assert_eq!(grid.take_dirty_regions(), vec![Region { row: 0, col: 2, width: 2, height: 1 }]);
+    }
+    
+    #[test]
+    fn test_logical_lines_join_wrapped_rows() {
+        let mut grid = TextGrid::new(7, 5);
+        // Wraps over four rows; the spaces at row ends are kept
+        grid.write_str("echo hello world\r\n");
+        grid.write_str("ok   \r\n");
+        // Exactly filling a row without writing more doesn't wrap
+        grid.write_str("12345");
+        
+        assert_eq!(grid.logical_lines().collect::<Vec<_>>(), ["echo hello world", "ok", "12345", ""]);
+        
+        // A wide character that doesn't fit leaves an unwritten cell, not a space
+        let mut grid = TextGrid::new(3, 4);
+        grid.write_str("abc中d");
+        assert_eq!(grid.logical_lines().collect::<Vec<_>>(), ["abc中d", ""]);
+    }
 }
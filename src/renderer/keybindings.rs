@@ -0,0 +1,300 @@
+// Key bindings: which key chords the terminal handles itself instead of
+// forwarding to the shell. Defaults, with the config file layered on top.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+use winit::keyboard::{KeyCode, ModifiersState};
+
+use crate::renderer::font::Zoom;
+
+/// What a bound key does
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    Quit,
+    /// Copy the selection to the clipboard
+    Copy,
+    /// Send the clipboard contents to the shell
+    Paste,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    /// Scroll the viewport one page back through scrollback
+    ScrollUp,
+    /// Scroll the viewport one page towards the live screen
+    ScrollDown,
+    /// Write these bytes to the shell
+    SendBytes(#[serde(deserialize_with = "string_bytes")] Vec<u8>),
+    /// Forward the key to the shell, undoing a default binding
+    Passthrough,
+}
+
+impl KeyAction {
+    pub fn zoom(&self) -> Option<Zoom> {
+        match self {
+            Self::ZoomIn => Some(Zoom::In),
+            Self::ZoomOut => Some(Zoom::Out),
+            Self::ZoomReset => Some(Zoom::Reset),
+            _ => None,
+        }
+    }
+}
+
+fn string_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    String::deserialize(deserializer).map(String::into_bytes)
+}
+
+/// A key with the exact modifiers held, written like `Ctrl+Shift+C`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyChord {
+    pub key: KeyCode,
+    pub modifiers: ModifiersState,
+}
+
+impl KeyChord {
+    pub fn new(key: KeyCode, modifiers: ModifiersState) -> Self {
+        Self { key, modifiers }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+    
+    /// Modifiers (`Ctrl`, `Shift`, `Alt`, `Super`, or their aliases) then a
+    /// key name, joined by `+`; case-insensitive. `Ctrl++` names the plus key.
+    fn from_str(chord: &str) -> Result<Self, Self::Err> {
+        let (modifier_part, key_name) = match chord.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => chord.rsplit_once('+').unwrap_or(("", chord)),
+        };
+        
+        let mut modifiers = ModifiersState::empty();
+        for name in modifier_part.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => ModifiersState::CONTROL,
+                "shift" => ModifiersState::SHIFT,
+                "alt" | "option" => ModifiersState::ALT,
+                "super" | "cmd" | "command" | "meta" => ModifiersState::SUPER,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", name, chord)),
+            };
+        }
+        let key = key_code(key_name.trim()).ok_or_else(|| format!("unknown key `{}` in `{}`", key_name, chord))?;
+        Ok(Self { key, modifiers })
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = String;
+    
+    fn try_from(chord: String) -> Result<Self, Self::Error> {
+        chord.parse()
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, name) in [
+            (ModifiersState::CONTROL, "Ctrl"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(flag) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+/// Key names accepted in a chord: letters, digits, `F1`..`F12`, and the
+/// named keys below
+fn key_code(name: &str) -> Option<KeyCode> {
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+        KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+        KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+        KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+        KeyCode::KeyY, KeyCode::KeyZ,
+    ];
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+        KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    ];
+    const FUNCTION_KEYS: [KeyCode; 12] = [
+        KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+        KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    ];
+    
+    let lower = name.to_ascii_lowercase();
+    if let [ch] = lower.as_bytes() {
+        match ch {
+            b'a'..=b'z' => return Some(LETTERS[(ch - b'a') as usize]),
+            b'0'..=b'9' => return Some(DIGITS[(ch - b'0') as usize]),
+            _ => {}
+        }
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return n.checked_sub(1).and_then(|i| FUNCTION_KEYS.get(i)).copied();
+    }
+    
+    Some(match lower.as_str() {
+        "escape" | "esc" => KeyCode::Escape,
+        "enter" | "return" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "space" => KeyCode::Space,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" => KeyCode::ArrowUp,
+        "down" => KeyCode::ArrowDown,
+        "left" => KeyCode::ArrowLeft,
+        "right" => KeyCode::ArrowRight,
+        // `+` shares its key with `=` on most layouts
+        "+" | "plus" | "=" | "equal" => KeyCode::Equal,
+        "-" | "minus" => KeyCode::Minus,
+        "numpadadd" => KeyCode::NumpadAdd,
+        "numpadsubtract" => KeyCode::NumpadSubtract,
+        "numpad0" => KeyCode::Numpad0,
+        _ => return None,
+    })
+}
+
+/// One `[[keybindings]]` entry of the config file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyBinding {
+    pub key: KeyChord,
+    pub action: KeyAction,
+}
+
+/// Actions for key chords; anything unbound goes to the shell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keybindings {
+    bindings: HashMap<KeyChord, KeyAction>,
+}
+
+impl Default for Keybindings {
+    /// Escape quits; Ctrl (or Cmd) with `+`, `-`, `0` zooms; Ctrl+Shift+C/V
+    /// copy and paste; Shift+PageUp/PageDown scroll
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        bindings.insert(KeyChord::new(KeyCode::Escape, ModifiersState::empty()), KeyAction::Quit);
+        
+        let zoom_keys = [
+            (KeyCode::Equal, KeyAction::ZoomIn),
+            (KeyCode::NumpadAdd, KeyAction::ZoomIn),
+            (KeyCode::Minus, KeyAction::ZoomOut),
+            (KeyCode::NumpadSubtract, KeyAction::ZoomOut),
+            (KeyCode::Digit0, KeyAction::ZoomReset),
+            (KeyCode::Numpad0, KeyAction::ZoomReset),
+        ];
+        // Shift too, since `+` is often Shift+`=`
+        for modifier in [ModifiersState::CONTROL, ModifiersState::SUPER] {
+            for modifiers in [modifier, modifier | ModifiersState::SHIFT] {
+                for (key, action) in &zoom_keys {
+                    bindings.insert(KeyChord::new(*key, modifiers), action.clone());
+                }
+            }
+        }
+        
+        let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        bindings.insert(KeyChord::new(KeyCode::KeyC, ctrl_shift), KeyAction::Copy);
+        bindings.insert(KeyChord::new(KeyCode::KeyV, ctrl_shift), KeyAction::Paste);
+        bindings.insert(KeyChord::new(KeyCode::PageUp, ModifiersState::SHIFT), KeyAction::ScrollUp);
+        bindings.insert(KeyChord::new(KeyCode::PageDown, ModifiersState::SHIFT), KeyAction::ScrollDown);
+        Self { bindings }
+    }
+}
+
+impl Keybindings {
+    /// No bindings at all: every key goes to the shell
+    pub fn empty() -> Self {
+        Self { bindings: HashMap::new() }
+    }
+    
+    /// These bindings with `overrides` replacing any for the same chord
+    pub fn with_overrides<'a>(mut self, overrides: impl IntoIterator<Item = &'a KeyBinding>) -> Self {
+        for binding in overrides {
+            self.bind(binding.key, binding.action.clone());
+        }
+        self
+    }
+    
+    pub fn bind(&mut self, chord: KeyChord, action: KeyAction) {
+        self.bindings.insert(chord, action);
+    }
+    
+    /// The action for `key` with exactly `modifiers` held, or `None` when
+    /// the key should be forwarded to the shell
+    pub fn resolve(&self, key: KeyCode, modifiers: ModifiersState) -> Option<&KeyAction> {
+        self.bindings
+            .get(&KeyChord::new(key, modifiers))
+            .filter(|action| **action != KeyAction::Passthrough)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_chords() {
+        let chord = |s: &str| s.parse::<KeyChord>();
+        let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        assert_eq!(chord("Ctrl+Shift+C"), Ok(KeyChord::new(KeyCode::KeyC, ctrl_shift)));
+        assert_eq!(chord("shift+ctrl+c"), Ok(KeyChord::new(KeyCode::KeyC, ctrl_shift)));
+        assert_eq!(chord("Escape"), Ok(KeyChord::new(KeyCode::Escape, ModifiersState::empty())));
+        assert_eq!(chord("Cmd++"), Ok(KeyChord::new(KeyCode::Equal, ModifiersState::SUPER)));
+        assert_eq!(chord("Alt+F12"), Ok(KeyChord::new(KeyCode::F12, ModifiersState::ALT)));
+        assert_eq!(chord("Shift+PageUp"), Ok(KeyChord::new(KeyCode::PageUp, ModifiersState::SHIFT)));
+        
+        assert!(chord("Hyper+C").is_err());
+        assert!(chord("Ctrl+").is_err());
+        assert!(chord("F13").is_err());
+        assert!(chord("Ctrl+Shift+Comma").is_err());
+        assert_eq!(KeyChord::new(KeyCode::KeyC, ctrl_shift).to_string(), "Ctrl+Shift+KeyC");
+    }
+    
+    #[test]
+    fn test_bound_actions_take_precedence_over_passthrough() {
+        let defaults = Keybindings::default();
+        assert_eq!(defaults.resolve(KeyCode::Escape, ModifiersState::empty()), Some(&KeyAction::Quit));
+        assert_eq!(defaults.resolve(KeyCode::Equal, ModifiersState::CONTROL | ModifiersState::SHIFT), Some(&KeyAction::ZoomIn));
+        // Modifiers must match exactly; other keys go to the shell
+        assert_eq!(defaults.resolve(KeyCode::Escape, ModifiersState::SHIFT), None);
+        assert_eq!(defaults.resolve(KeyCode::KeyC, ModifiersState::CONTROL), None);
+        assert_eq!(defaults.resolve(KeyCode::KeyA, ModifiersState::empty()), None);
+        
+        let overrides = [
+            KeyBinding { key: "Escape".parse().unwrap(), action: KeyAction::Passthrough },
+            KeyBinding { key: "Ctrl+Q".parse().unwrap(), action: KeyAction::Quit },
+            KeyBinding { key: "Ctrl+Shift+C".parse().unwrap(), action: KeyAction::SendBytes(b"\x03".to_vec()) },
+        ];
+        let bindings = defaults.with_overrides(&overrides);
+        assert_eq!(bindings.resolve(KeyCode::Escape, ModifiersState::empty()), None);
+        assert_eq!(bindings.resolve(KeyCode::KeyQ, ModifiersState::CONTROL), Some(&KeyAction::Quit));
+        assert_eq!(
+            bindings.resolve(KeyCode::KeyC, ModifiersState::CONTROL | ModifiersState::SHIFT),
+            Some(&KeyAction::SendBytes(vec![3]))
+        );
+        // Defaults not overridden stay
+        assert_eq!(bindings.resolve(KeyCode::PageUp, ModifiersState::SHIFT), Some(&KeyAction::ScrollUp));
+        assert_eq!(Keybindings::empty().resolve(KeyCode::Escape, ModifiersState::empty()), None);
+    }
+    
+    #[test]
+    fn test_actions_map_to_zoom() {
+        assert_eq!(KeyAction::ZoomIn.zoom(), Some(Zoom::In));
+        assert_eq!(KeyAction::ZoomReset.zoom(), Some(Zoom::Reset));
+        assert_eq!(KeyAction::Copy.zoom(), None);
+    }
+}
@@ -3,9 +3,35 @@ pub mod software;
 pub mod grid;
 pub mod parser;
 pub mod colors;
+pub mod geometry;
+pub mod export;
+pub mod active;
+pub mod backend;
+pub mod font;
+pub mod keybindings;
+pub mod mouse;
+pub mod emulator;
+pub mod selection;
+pub mod clipboard;
+pub mod fallback;
+pub mod bell;
+pub mod csi;
 
 pub use gpu::RenderError;
 // Note: GpuRenderer is generic and needs to be used with lifetime parameter
-pub use grid::{TextGrid, Cell, CellAttributes, Region};
-pub use parser::TerminalParser;
-pub use colors::TerminalColor;
+pub use grid::{TextGrid, Cell, CellAttributes, CursorShape, CursorState, LineAttr, Region, MAX_GRID_DIMENSION};
+pub use parser::{TerminalParser, TerminalModes, TerminalEvent, ParserStateSnapshot};
+pub use colors::{TerminalColor, Palette, Rgba};
+pub use geometry::CellGeometry;
+pub use export::StyledRun;
+pub use active::{ActiveRenderer, RendererChoice};
+pub use backend::Renderer;
+pub use mouse::{encode_mouse_event, MouseAction, MouseButton, MouseEncoding, MouseEvent, MouseTracking};
+pub use emulator::TerminalEmulator;
+pub use selection::Selection;
+pub use clipboard::{system_clipboard, Clipboard, SelectionClipboard};
+pub use fallback::{FontChain, GlyphSource};
+pub use font::{FontSizeLimits, Zoom, DEFAULT_FONT_SIZE, FONT_SIZE_STEP, MAX_FONT_SIZE};
+pub use keybindings::{KeyAction, KeyBinding, KeyChord, Keybindings};
+pub use csi::CsiParams;
+pub use bell::{bell_tint, Bell, BellConfig, BellMode, VisualBell, VISUAL_BELL_DURATION};
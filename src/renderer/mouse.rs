@@ -0,0 +1,279 @@
+// Mouse reporting: which events applications asked for and how they are encoded
+
+use serde::Serialize;
+use winit::keyboard::ModifiersState;
+
+/// Which mouse events are reported, set with `CSI ? Pm h`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum MouseTracking {
+    #[default]
+    Off,
+    /// `?9`: button presses only, without modifiers
+    X10,
+    /// `?1000`: presses and releases
+    Normal,
+    /// `?1002`: also motion while a button is held
+    ButtonEvent,
+    /// `?1003`: also motion with no button held
+    AnyEvent,
+}
+
+/// How reports are written, set with `CSI ? Pm h`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum MouseEncoding {
+    /// `CSI M Cb Cx Cy` with each value as a single byte offset by 32,
+    /// so coordinates past 223 can't be reported
+    #[default]
+    X10,
+    /// `?1005`: as X10, but values are UTF-8 encoded, reaching 2015
+    Utf8,
+    /// `?1006`: `CSI < Cb ; Cx ; Cy M`, with `m` for releases
+    Sgr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    WheelUp,
+    WheelDown,
+}
+
+impl MouseButton {
+    fn code(self) -> u32 {
+        match self {
+            Self::Left => 0,
+            Self::Middle => 1,
+            Self::Right => 2,
+            Self::WheelUp => 64,
+            Self::WheelDown => 65,
+        }
+    }
+    
+    fn is_wheel(self) -> bool {
+        matches!(self, Self::WheelUp | Self::WheelDown)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseAction {
+    Press(MouseButton),
+    Release(MouseButton),
+    /// Pointer moved to a new cell, with the button held down if any
+    Motion(Option<MouseButton>),
+}
+
+/// A mouse event at a 0-based grid cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    pub action: MouseAction,
+    pub row: u16,
+    pub col: u16,
+    pub modifiers: ModifiersState,
+}
+
+/// Button code for motion with no button held (3 for "no button" + 32 for motion)
+const NO_BUTTON_MOTION: u32 = 35;
+
+/// Added to the button code of motion reports
+const MOTION_FLAG: u32 = 32;
+
+/// Button code of releases in the X10 and UTF-8 encodings, which can't say
+/// which button was released
+const RELEASE_CODE: u32 = 3;
+
+/// Largest value a single-byte X10 field can carry, after the 32 offset
+const X10_MAX_VALUE: u32 = 255 - 32;
+
+/// Largest value a UTF-8 (`?1005`) field can carry: two-byte UTF-8 tops out at 2047
+const UTF8_MAX_VALUE: u32 = 2047 - 32;
+
+/// Bytes reporting `event` to the application, or `None` when the tracking
+/// mode doesn't cover it or the position can't be encoded
+pub fn encode_mouse_event(tracking: MouseTracking, encoding: MouseEncoding, event: &MouseEvent) -> Option<Vec<u8>> {
+    let (code, released) = match (tracking, event.action) {
+        (MouseTracking::Off, _) => return None,
+        (MouseTracking::X10, MouseAction::Press(button)) => {
+            // No modifiers in X10 compatibility mode
+            return encode(encoding, button.code(), event.col, event.row, false);
+        }
+        (MouseTracking::X10, _) => return None,
+        // Wheels have no release
+        (_, MouseAction::Release(button)) if button.is_wheel() => return None,
+        (_, MouseAction::Press(button)) => (button.code(), false),
+        (_, MouseAction::Release(button)) => (button.code(), true),
+        (MouseTracking::ButtonEvent | MouseTracking::AnyEvent, MouseAction::Motion(Some(button))) => {
+            (button.code() + MOTION_FLAG, false)
+        }
+        (MouseTracking::AnyEvent, MouseAction::Motion(None)) => (NO_BUTTON_MOTION, false),
+        (_, MouseAction::Motion(_)) => return None,
+    };
+    
+    let code = code + modifier_bits(event.modifiers);
+    encode(encoding, code, event.col, event.row, released)
+}
+
+fn modifier_bits(modifiers: ModifiersState) -> u32 {
+    let mut bits = 0;
+    if modifiers.shift_key() {
+        bits += 4;
+    }
+    if modifiers.alt_key() {
+        bits += 8;
+    }
+    if modifiers.control_key() {
+        bits += 16;
+    }
+    bits
+}
+
+fn encode(encoding: MouseEncoding, code: u32, col: u16, row: u16, released: bool) -> Option<Vec<u8>> {
+    // Reports use 1-based coordinates
+    let x = u32::from(col) + 1;
+    let y = u32::from(row) + 1;
+    
+    match encoding {
+        MouseEncoding::Sgr => {
+            let final_byte = if released { 'm' } else { 'M' };
+            Some(format!("\x1b[<{};{};{}{}", code, x, y, final_byte).into_bytes())
+        }
+        MouseEncoding::X10 | MouseEncoding::Utf8 => {
+            // Only SGR can say which button was released; keep the modifier bits
+            let code = if released { (code & !0b11) | RELEASE_CODE } else { code };
+            let mut report = b"\x1b[M".to_vec();
+            for value in [code, x, y] {
+                push_legacy_value(&mut report, encoding, value)?;
+            }
+            Some(report)
+        }
+    }
+}
+
+fn push_legacy_value(report: &mut Vec<u8>, encoding: MouseEncoding, value: u32) -> Option<()> {
+    if encoding == MouseEncoding::Utf8 {
+        if value > UTF8_MAX_VALUE {
+            return None;
+        }
+        let c = char::from_u32(value + 32)?;
+        let mut buf = [0u8; 4];
+        report.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+    } else {
+        if value > X10_MAX_VALUE {
+            return None;
+        }
+        report.push((value + 32) as u8);
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn event(action: MouseAction, row: u16, col: u16) -> MouseEvent {
+        MouseEvent { action, row, col, modifiers: ModifiersState::empty() }
+    }
+    
+    #[test]
+    fn test_encoding_matrix() {
+        use MouseAction::*;
+        use MouseButton::*;
+        
+        // (action, X10 bytes, SGR bytes) at row 4, col 9 => x 10, y 5
+        let cases: [(MouseAction, &[u8], &[u8]); 9] = [
+            (Press(Left), b"\x1b[M *%", b"\x1b[<0;10;5M"),
+            (Press(Middle), b"\x1b[M!*%", b"\x1b[<1;10;5M"),
+            (Press(Right), b"\x1b[M\"*%", b"\x1b[<2;10;5M"),
+            (Release(Left), b"\x1b[M#*%", b"\x1b[<0;10;5m"),
+            (Release(Right), b"\x1b[M#*%", b"\x1b[<2;10;5m"),
+            (Press(WheelUp), b"\x1b[M`*%", b"\x1b[<64;10;5M"),
+            (Press(WheelDown), b"\x1b[Ma*%", b"\x1b[<65;10;5M"),
+            (Motion(Some(Left)), b"\x1b[M@*%", b"\x1b[<32;10;5M"),
+            (Motion(None), b"\x1b[MC*%", b"\x1b[<35;10;5M"),
+        ];
+        
+        for (action, x10, sgr) in cases {
+            let e = event(action, 4, 9);
+            let any = MouseTracking::AnyEvent;
+            assert_eq!(encode_mouse_event(any, MouseEncoding::X10, &e).as_deref(), Some(x10), "{:?}", action);
+            // Small values encode the same in UTF-8 as in X10
+            assert_eq!(encode_mouse_event(any, MouseEncoding::Utf8, &e).as_deref(), Some(x10), "{:?}", action);
+            assert_eq!(encode_mouse_event(any, MouseEncoding::Sgr, &e).as_deref(), Some(sgr), "{:?}", action);
+        }
+    }
+    
+    #[test]
+    fn test_tracking_modes_filter_events() {
+        use MouseAction::*;
+        use MouseButton::*;
+        use MouseTracking::*;
+        
+        let reported = |tracking, action| encode_mouse_event(tracking, MouseEncoding::Sgr, &event(action, 0, 0)).is_some();
+        
+        for tracking in [X10, Normal, ButtonEvent, AnyEvent] {
+            assert!(reported(tracking, Press(Left)));
+            assert!(reported(tracking, Press(WheelDown)));
+            assert!(!reported(tracking, Release(WheelUp)));
+        }
+        assert!(!reported(Off, Press(Left)));
+        assert!(!reported(X10, Release(Left)));
+        assert!(reported(Normal, Release(Left)));
+        assert!(!reported(Normal, Motion(Some(Left))));
+        assert!(reported(ButtonEvent, Motion(Some(Left))));
+        assert!(!reported(ButtonEvent, Motion(None)));
+        assert!(reported(AnyEvent, Motion(None)));
+    }
+    
+    #[test]
+    fn test_modifiers() {
+        let mut e = event(MouseAction::Press(MouseButton::Left), 0, 0);
+        e.modifiers = ModifiersState::SHIFT | ModifiersState::CONTROL;
+        assert_eq!(
+            encode_mouse_event(MouseTracking::Normal, MouseEncoding::Sgr, &e),
+            Some(b"\x1b[<20;1;1M".to_vec())
+        );
+        // Release keeps the modifier bits in the legacy encodings
+        e.action = MouseAction::Release(MouseButton::Left);
+        assert_eq!(
+            encode_mouse_event(MouseTracking::Normal, MouseEncoding::X10, &e),
+            Some(b"\x1b[M7!!".to_vec())
+        );
+        e.action = MouseAction::Motion(Some(MouseButton::Right));
+        e.modifiers = ModifiersState::ALT;
+        assert_eq!(
+            encode_mouse_event(MouseTracking::ButtonEvent, MouseEncoding::Sgr, &e),
+            Some(b"\x1b[<42;1;1M".to_vec())
+        );
+        // X10 compatibility mode never reports modifiers
+        e.action = MouseAction::Press(MouseButton::Left);
+        assert_eq!(
+            encode_mouse_event(MouseTracking::X10, MouseEncoding::Sgr, &e),
+            Some(b"\x1b[<0;1;1M".to_vec())
+        );
+    }
+    
+    #[test]
+    fn test_large_coordinates() {
+        let press = |col| event(MouseAction::Press(MouseButton::Left), 0, col);
+        
+        // x = 223 (255 after the offset) is the last the X10 encoding can carry
+        assert_eq!(
+            encode_mouse_event(MouseTracking::Normal, MouseEncoding::X10, &press(222)),
+            Some(b"\x1b[M \xff!".to_vec())
+        );
+        assert_eq!(encode_mouse_event(MouseTracking::Normal, MouseEncoding::X10, &press(223)), None);
+        
+        // UTF-8 encodes x = 300 as U+014C
+        assert_eq!(
+            encode_mouse_event(MouseTracking::Normal, MouseEncoding::Utf8, &press(299)),
+            Some("\x1b[M \u{14c}!".as_bytes().to_vec())
+        );
+        assert_eq!(encode_mouse_event(MouseTracking::Normal, MouseEncoding::Utf8, &press(2015)), None);
+        
+        assert_eq!(
+            encode_mouse_event(MouseTracking::Normal, MouseEncoding::Sgr, &press(2999)),
+            Some(b"\x1b[<0;3000;1M".to_vec())
+        );
+    }
+}
@@ -1,8 +1,99 @@
 // VT100/ANSI Terminal Parser - Phase A Week 2
 // This will implement VTE parsing for terminal escape sequences
 
+use std::path::{Path, PathBuf};
+use serde::Serialize;
 use vte::{Parser, Perform};
-use crate::renderer::{TextGrid, CellAttributes, TerminalColor};
+use crate::renderer::csi::CsiParams;
+use crate::renderer::grid::is_wide_spacer;
+use crate::renderer::mouse::{self, MouseEncoding, MouseEvent, MouseTracking};
+use crate::renderer::{TextGrid, CellAttributes, CursorShape, LineAttr, TerminalColor, Palette, Rgba};
+
+/// Reply to `CSI c`: a VT220 (62) with ANSI color (22)
+pub const PRIMARY_DEVICE_ATTRIBUTES: &[u8] = b"\x1b[?62;22c";
+
+/// Reply to `CSI > c`: VT220 terminal type (1), the termind version as
+/// major * 10000 + minor * 100 + patch, and no ROM cartridge (0)
+fn secondary_device_attributes() -> Vec<u8> {
+    let part = |s: &str| s.parse::<u32>().unwrap_or(0);
+    let version = part(env!("CARGO_PKG_VERSION_MAJOR")) * 10000
+        + part(env!("CARGO_PKG_VERSION_MINOR")) * 100
+        + part(env!("CARGO_PKG_VERSION_PATCH"));
+    format!("\x1b[>1;{};0c", version).into_bytes()
+}
+
+/// DEC private modes toggled with `CSI ? Pm h` / `CSI ? Pm l`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TerminalModes {
+    /// `?1004`: report focus changes as `CSI I` / `CSI O`
+    pub focus_reporting: bool,
+    /// `?9`, `?1000`, `?1002`, `?1003`: which mouse events are reported
+    pub mouse_tracking: MouseTracking,
+    /// `?1005`, `?1006`: how mouse reports are encoded
+    pub mouse_encoding: MouseEncoding,
+    /// `?1007`: on the alternate screen, the mouse wheel sends arrow keys
+    pub alternate_scroll: bool,
+    /// `?6` (DECOM): cursor addressing is relative to the scroll region
+    pub origin_mode: bool,
+}
+
+/// Parser and grid state at one point in time, for golden-file tests of
+/// escape sequence handling. Serializes to stable JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParserStateSnapshot {
+    pub rows: u16,
+    pub cols: u16,
+    pub cursor_row: u16,
+    pub cursor_col: u16,
+    pub wrap_pending: bool,
+    pub cursor_visible: bool,
+    pub cursor_blink: bool,
+    /// Inclusive `(top, bottom)` rows
+    pub scroll_region: (u16, u16),
+    pub alternate_screen: bool,
+    pub modes: TerminalModes,
+    pub attrs: CellAttributes,
+    pub fg: TerminalColor,
+    pub bg: TerminalColor,
+    pub title: String,
+    pub current_directory: Option<PathBuf>,
+    /// Screen text, one entry per row with trailing blanks trimmed
+    pub lines: Vec<String>,
+}
+
+/// Something the front end should act on, queued in the order it was parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalEvent {
+    /// BEL outside of a string sequence
+    Bell,
+    /// Window title set via OSC 0/2 or restored with `CSI 23 t`
+    TitleChanged(String),
+    /// OSC 52 request to put `text` on the clipboard. `selection` holds the
+    /// requested targets (`c` clipboard, `p` primary, ...), as sent.
+    Clipboard { selection: String, text: String },
+    /// Desktop notification from OSC 9 (body only) or OSC 777 `notify`
+    Notify { title: Option<String>, body: String },
+    /// Reply to a terminal query, to be written back to the PTY
+    Response(Vec<u8>),
+    /// Working directory reported by the shell via OSC 7 or OSC 633 `P;Cwd=`
+    CwdChanged(PathBuf),
+    /// The shell's integration script announced the command line about to
+    /// run (OSC 633 `E`), with the last reported working directory
+    CommandStarted { command: String, cwd: Option<PathBuf> },
+}
+
+/// Where the OSC 133 input markers of the current prompt were seen, as
+/// `(lines_scrolled + row, col)` so positions survive scrolling
+#[derive(Debug, Clone, Copy, Default)]
+struct PromptMarkers {
+    /// `B`: prompt end, where the typed command begins
+    input_start: Option<(u64, u16)>,
+    /// `C`: command start, after the typed line was submitted
+    output_start: Option<(u64, u16)>,
+}
+
+/// Saved titles kept by `CSI 22 t`; xterm uses the same depth
+const MAX_TITLE_STACK_DEPTH: usize = 10;
 
 // Separate performer to avoid borrowing issues with the parser
 struct ParserPerformer<'a> {
@@ -10,6 +101,16 @@ struct ParserPerformer<'a> {
     current_attrs: &'a mut CellAttributes,
     current_fg: &'a mut TerminalColor,
     current_bg: &'a mut TerminalColor,
+    current_directory: &'a mut Option<PathBuf>,
+    title: &'a mut String,
+    title_stack: &'a mut Vec<String>,
+    cursor_color: &'a mut Option<Rgba>,
+    default_cursor_color: Rgba,
+    modes: &'a mut TerminalModes,
+    events: &'a mut Vec<TerminalEvent>,
+    prompt_markers: &'a mut PromptMarkers,
+    cell_pixel_size: Option<(u32, u32)>,
+    last_printed: &'a mut Option<char>,
 }
 
 pub struct TerminalParser {
@@ -18,6 +119,19 @@ pub struct TerminalParser {
     current_attrs: CellAttributes,
     current_fg: TerminalColor,
     current_bg: TerminalColor,
+    current_directory: Option<PathBuf>,
+    title: String,
+    title_stack: Vec<String>,
+    cursor_color: Option<Rgba>,
+    // Reported by OSC 12 queries while no color has been set
+    default_cursor_color: Rgba,
+    modes: TerminalModes,
+    // Events (including replies to queries) not yet drained by the front end
+    events: Vec<TerminalEvent>,
+    prompt_markers: PromptMarkers,
+    cell_pixel_size: Option<(u32, u32)>,
+    // Graphic character REP (`CSI b`) repeats; cleared by control characters
+    last_printed: Option<char>,
 }
 
 impl TerminalParser {
@@ -28,22 +142,53 @@ impl TerminalParser {
             current_attrs: CellAttributes::default(),
             current_fg: TerminalColor::White,
             current_bg: TerminalColor::Black,
+            current_directory: None,
+            title: String::new(),
+            title_stack: Vec::new(),
+            cursor_color: None,
+            default_cursor_color: Palette::default().cursor,
+            modes: TerminalModes::default(),
+            events: Vec::new(),
+            prompt_markers: PromptMarkers::default(),
+            cell_pixel_size: None,
+            last_printed: None,
         }
     }
     
     pub fn parse(&mut self, data: &[u8]) {
+        // The performer borrows everything but the vte parser, so one serves the whole slice
+        let mut performer = ParserPerformer {
+            grid: &mut self.grid,
+            current_attrs: &mut self.current_attrs,
+            current_fg: &mut self.current_fg,
+            current_bg: &mut self.current_bg,
+            current_directory: &mut self.current_directory,
+            title: &mut self.title,
+            title_stack: &mut self.title_stack,
+            cursor_color: &mut self.cursor_color,
+            default_cursor_color: self.default_cursor_color,
+            modes: &mut self.modes,
+            events: &mut self.events,
+            prompt_markers: &mut self.prompt_markers,
+            cell_pixel_size: self.cell_pixel_size,
+            last_printed: &mut self.last_printed,
+        };
         for &byte in data {
-            // Create a temporary performer to avoid borrowing issues
-            let mut performer = ParserPerformer {
-                grid: &mut self.grid,
-                current_attrs: &mut self.current_attrs,
-                current_fg: &mut self.current_fg,
-                current_bg: &mut self.current_bg,
-            };
             self.parser.advance(&mut performer, byte);
         }
     }
     
+    /// Parse a string, as if its UTF-8 bytes were read from the PTY
+    pub fn feed_str(&mut self, s: &str) {
+        self.parse(s.as_bytes());
+    }
+    
+    /// Finalize the stream by discarding any incomplete escape sequence, so the
+    /// next input starts from the ground state. Completed sequences are unaffected.
+    pub fn flush(&mut self) {
+        self.parser = Parser::new();
+    }
+    
     pub fn grid(&self) -> &TextGrid {
         &self.grid
     }
@@ -52,27 +197,179 @@ impl TerminalParser {
         &mut self.grid
     }
     
+    /// Resize and reflow the grid. In origin mode the cursor is kept inside
+    /// the scroll region the grid ends up with.
     pub fn resize(&mut self, rows: u16, cols: u16) {
         self.grid.resize(rows, cols);
+        // Reflow moves text, so recorded marker positions no longer apply
+        self.prompt_markers = PromptMarkers::default();
+        
+        if self.modes.origin_mode {
+            let (top, bottom) = self.grid.scroll_region();
+            let (row, col) = self.grid.cursor_position();
+            if !(top..=bottom).contains(&row) {
+                self.grid.set_cursor(row.clamp(top, bottom), col);
+            }
+        }
+    }
+    
+    /// The command typed at the last prompt, read from the grid between the
+    /// OSC 133 `B` (input start) and `C` (command start) markers. Soft-wrapped
+    /// rows are joined without a break; explicit line breaks are kept.
+    /// `None` until both markers were seen, or once the input scrolled off screen.
+    pub fn command_line_between_markers(&self) -> Option<String> {
+        let (start_line, start_col) = self.prompt_markers.input_start?;
+        let (end_line, end_col) = self.prompt_markers.output_start?;
+        if (end_line, end_col) < (start_line, start_col) {
+            return None;
+        }
+        
+        let scrolled = self.grid.lines_scrolled();
+        let start_row = u16::try_from(start_line.checked_sub(scrolled)?).ok()?;
+        let end_row = u16::try_from(end_line - scrolled).ok()?.min(self.grid.rows.saturating_sub(1));
+        
+        let mut command = String::new();
+        for row in start_row..=end_row {
+            let cells = self.grid.row(row)?;
+            let from = if row == start_row { start_col as usize } else { 0 };
+            let to = if row == end_row { end_col as usize } else { cells.len() };
+            let mut text = String::new();
+            for col in (from.min(cells.len())..to.min(cells.len())).filter(|&col| !is_wide_spacer(cells, col)) {
+                cells[col].push_grapheme(&mut text);
+            }
+            
+            if row < end_row && self.grid.is_wrapped(row) {
+                command.push_str(&text);
+            } else {
+                command.push_str(text.trim_end());
+                if row < end_row {
+                    command.push('\n');
+                }
+            }
+        }
+        
+        Some(command.trim_end().to_string())
+    }
+    
+    /// Capture modes, cursor, pen attributes and screen text in one value
+    pub fn state_snapshot(&self) -> ParserStateSnapshot {
+        let (cursor_row, cursor_col) = self.grid.cursor_position();
+        ParserStateSnapshot {
+            rows: self.grid.rows,
+            cols: self.grid.cols,
+            cursor_row,
+            cursor_col,
+            wrap_pending: self.grid.wrap_pending(),
+            cursor_visible: self.grid.cursor_visible(),
+            cursor_blink: self.grid.cursor_blink_enabled(),
+            scroll_region: self.grid.scroll_region(),
+            alternate_screen: self.grid.in_alternate_screen(),
+            modes: self.modes.clone(),
+            attrs: self.current_attrs.clone(),
+            fg: self.current_fg,
+            bg: self.current_bg,
+            title: self.title.clone(),
+            current_directory: self.current_directory.clone(),
+            lines: self.grid.to_string().split('\n').map(str::to_string).collect(),
+        }
+    }
+    
+    /// Working directory last reported by the shell via OSC 7
+    pub fn current_directory(&self) -> Option<&Path> {
+        self.current_directory.as_deref()
+    }
+    
+    /// Window title set via OSC 0/2
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    
+    /// Take the events queued since the last call, oldest first. The caller
+    /// writes `TerminalEvent::Response` bytes back to the PTY.
+    pub fn drain_events(&mut self) -> Vec<TerminalEvent> {
+        std::mem::take(&mut self.events)
+    }
+    
+    /// Cell size in pixels, used to answer pixel size queries (`CSI 14 t`)
+    pub fn set_cell_pixel_size(&mut self, width: u32, height: u32) {
+        self.cell_pixel_size = Some((width, height));
+    }
+    
+    pub fn modes(&self) -> &TerminalModes {
+        &self.modes
+    }
+    
+    /// Bytes to send to the PTY when the window gains or loses focus, if the
+    /// application enabled focus reporting (`CSI ? 1004 h`)
+    pub fn focus_report_bytes(&self, focused: bool) -> Option<Vec<u8>> {
+        if !self.modes.focus_reporting {
+            return None;
+        }
+        let report: &[u8] = if focused { b"\x1b[I" } else { b"\x1b[O" };
+        Some(report.to_vec())
+    }
+    
+    /// Up or down arrow presses standing in for `lines` of mouse wheel
+    /// scrolling, for pagers on the alternate screen that don't track the
+    /// mouse. `None` unless alternate scroll mode (`CSI ? 1007 h`) is on, the
+    /// alternate screen is shown and mouse reporting is off.
+    pub fn wheel_to_keys(&self, up: bool, lines: u16) -> Option<Vec<u8>> {
+        if !self.modes.alternate_scroll
+            || !self.grid.in_alternate_screen()
+            || self.modes.mouse_tracking != MouseTracking::Off
+            || lines == 0
+        {
+            return None;
+        }
+        let key: &[u8] = if up { b"\x1b[A" } else { b"\x1b[B" };
+        Some(key.repeat(lines as usize))
+    }
+    
+    /// Bytes to send to the PTY for a mouse event, if the application enabled
+    /// mouse reporting that covers it
+    pub fn encode_mouse_event(&self, event: &MouseEvent) -> Option<Vec<u8>> {
+        mouse::encode_mouse_event(self.modes.mouse_tracking, self.modes.mouse_encoding, event)
+    }
+    
+    /// Cursor color set via OSC 12, `None` when the renderer default applies
+    pub fn cursor_color(&self) -> Option<Rgba> {
+        self.cursor_color
+    }
+    
+    /// Renderer cursor color, reported to OSC 12 queries until an app sets one
+    pub fn set_default_cursor_color(&mut self, color: Rgba) {
+        self.default_cursor_color = color;
     }
 }
 
 impl<'a> Perform for ParserPerformer<'a> {
     fn print(&mut self, c: char) {
-        // Set current attributes and colors before writing
-        self.grid.set_attrs(self.current_attrs.clone());
-        self.grid.set_fg_color(*self.current_fg);
-        self.grid.set_bg_color(*self.current_bg);
-        
+        // The grid uses NUL for never-written cells; control characters
+        // are never drawn, whichever path they arrive by
+        if c.is_control() {
+            return;
+        }
+        self.sync_grid_pen();
         self.grid.write_char(c);
+        *self.last_printed = Some(c);
     }
     
     fn execute(&mut self, byte: u8) {
+        *self.last_printed = None;
         match byte {
+            0x07 => self.events.push(TerminalEvent::Bell),
             b'\n' => self.grid.newline(),
             b'\r' => self.grid.carriage_return(),
             b'\t' => self.grid.tab(),
             b'\x08' => self.grid.backspace(), // Backspace
+            // NUL is padding and DEL is ignored, as on a VT100; neither
+            // moves the cursor or takes a cell
+            0x00 | 0x7F => {}
+            // 8-bit C1 forms of ESC D / ESC E / ESC H / ESC M
+            0x84 => self.grid.index(),
+            0x85 => self.grid.newline(),
+            0x88 => self.grid.set_tab_stop(),
+            0x8D => self.grid.reverse_index(),
             _ => {} // Ignore other control characters for now
         }
     }
@@ -89,85 +386,206 @@ impl<'a> Perform for ParserPerformer<'a> {
         // TODO: Implement unhook for DCS sequences
     }
     
-    fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {
-        // TODO: Implement OSC sequences (titles, colors, etc.)
-    }
-    
-    fn csi_dispatch(&mut self, params: &vte::Params, _intermediates: &[u8], _ignore: bool, c: char) {
-        match c {
-            // Cursor movement
-            'A' => {
-                let lines = params.iter().next().map_or(1, |p| p[0] as u16);
-                self.grid.cursor_up(lines);
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        match params.first() {
+            // Report current directory: OSC 7 ; file://host/path ST
+            Some(&b"7") => {
+                if let Some(path) = params.get(1).and_then(|uri| parse_file_uri(uri)) {
+                    *self.current_directory = Some(path.clone());
+                    self.events.push(TerminalEvent::CwdChanged(path));
+                }
             }
-            'B' => {
-                let lines = params.iter().next().map_or(1, |p| p[0] as u16);
-                self.grid.cursor_down(lines);
+            // Set window title: OSC 0/2 ; title ST
+            Some(&b"0") | Some(&b"2") => {
+                if let Some(title) = params.get(1) {
+                    self.set_title(String::from_utf8_lossy(title).into_owned());
+                }
             }
-            'C' => {
-                let cols = params.iter().next().map_or(1, |p| p[0] as u16);
-                self.grid.cursor_right(cols);
+            // Query cursor color: OSC 12 ; ? ST, answered with the same terminator
+            Some(&b"12") if params.get(1) == Some(&&b"?"[..]) => {
+                let color = self.cursor_color.unwrap_or(self.default_cursor_color);
+                let terminator = if bell_terminated { "\x07" } else { "\x1b\\" };
+                let reply = format!("\x1b]12;{}{}", format_color_spec(color), terminator);
+                self.events.push(TerminalEvent::Response(reply.into_bytes()));
             }
-            'D' => {
-                let cols = params.iter().next().map_or(1, |p| p[0] as u16);
-                self.grid.cursor_left(cols);
+            // Set cursor color: OSC 12 ; spec ST
+            Some(&b"12") => {
+                if let Some(color) = params.get(1).and_then(|spec| parse_color_spec(spec)) {
+                    *self.cursor_color = Some(color);
+                }
             }
-            'H' | 'f' => {
-                // Cursor position
-                let mut iter = params.iter();
-                let row = iter.next().map_or(1, |p| p[0] as u16).saturating_sub(1);
-                let col = iter.next().map_or(1, |p| p[0] as u16).saturating_sub(1);
-                self.grid.set_cursor(row, col);
+            // Reset cursor color: OSC 112 ST
+            Some(&b"112") => *self.cursor_color = None,
+            // Set clipboard: OSC 52 ; targets ; base64 text ST. Queries (`?`) are
+            // not answered, so applications can't read the user's clipboard.
+            Some(&b"52") if params.len() > 2 => {
+                if let Some(text) = decode_clipboard_text(params[2]) {
+                    self.events.push(TerminalEvent::Clipboard {
+                        selection: String::from_utf8_lossy(params[1]).into_owned(),
+                        text,
+                    });
+                }
             }
-            
-            // Screen clearing
-            'J' => {
-                let mode = params.iter().next().map_or(0, |p| p[0]);
-                match mode {
-                    0 => {} // Clear from cursor to end of screen
-                    1 => {} // Clear from beginning of screen to cursor
-                    2 => self.grid.clear_screen(),
-                    _ => {}
+            // Semantic prompt markers: OSC 133 ; A|B|C|D [; ...] ST
+            Some(&b"133") => self.prompt_marker(params.get(1).copied()),
+            // Shell integration as in VS Code: OSC 633 ; E ; command line [; nonce] ST
+            // announces the command about to run, `P ; Cwd=path` the working
+            // directory, and A-D are the OSC 133 markers
+            Some(&b"633") => match params.get(1).copied() {
+                Some(b"E") => {
+                    if let Some(command) = params.get(2) {
+                        self.events.push(TerminalEvent::CommandStarted {
+                            command: unescape_shell_integration(command),
+                            cwd: self.current_directory.clone(),
+                        });
+                    }
                 }
+                Some(b"P") => {
+                    let property = params.get(2).map(|property| unescape_shell_integration(property));
+                    if let Some(cwd) = property.as_deref().and_then(|property| property.strip_prefix("Cwd=")) {
+                        let path = PathBuf::from(cwd);
+                        *self.current_directory = Some(path.clone());
+                        self.events.push(TerminalEvent::CwdChanged(path));
+                    }
+                }
+                kind => self.prompt_marker(kind),
+            },
+            // Notification (iTerm2): OSC 9 ; body ST. ConEmu reuses OSC 9 with a
+            // numeric subcommand (`9;4;st;pr` progress), which is not a notification.
+            Some(&b"9") => {
+                let is_conemu = params.len() > 2 && params[1].iter().all(u8::is_ascii_digit);
+                if params.len() > 1 && !is_conemu {
+                    self.events.push(TerminalEvent::Notify {
+                        title: None,
+                        body: join_osc_params(&params[1..]),
+                    });
+                }
+            }
+            // Notification (rxvt): OSC 777 ; notify ; title ; body ST
+            Some(&b"777") if params.get(1) == Some(&&b"notify"[..]) => {
+                let title = params.get(2).map(|title| String::from_utf8_lossy(title).into_owned());
+                let body = params.get(3..).map(join_osc_params).unwrap_or_default();
+                self.events.push(TerminalEvent::Notify { title, body });
             }
-            'K' => {
-                let mode = params.iter().next().map_or(0, |p| p[0]);
-                match mode {
-                    0 => self.grid.clear_line_from_cursor(),
-                    1 => self.grid.clear_line_to_cursor(),
-                    2 => self.grid.clear_line(),
-                    _ => {}
+            _ => {
+                // TODO: Implement remaining OSC sequences (titles, colors, etc.)
+            }
+        }
+    }
+    
+    fn csi_dispatch(&mut self, params: &vte::Params, intermediates: &[u8], _ignore: bool, c: char) {
+        let params = CsiParams::new(params);
+        match c {
+            // Cursor movement
+            'A' => self.grid.cursor_up(params.get_or(0, 1)),
+            'B' => self.grid.cursor_down(params.get_or(0, 1)),
+            'C' => self.grid.cursor_right(params.get_or(0, 1)),
+            'D' => self.grid.cursor_left(params.get_or(0, 1)),
+            // Cursor position, 1-based
+            'H' | 'f' => self.set_cursor(params.get_or(0, 1) - 1, params.get_or(1, 1) - 1),
+            
+            // Repeat the preceding graphic character (REP)
+            'b' => {
+                if let Some(c) = *self.last_printed {
+                    let count = params.get_or(0, 1);
+                    // More repeats than cells on screen would only overwrite themselves
+                    let count = count.min(self.grid.rows.saturating_mul(self.grid.cols));
+                    for _ in 0..count {
+                        self.print(c);
+                    }
                 }
             }
             
+            // Screen clearing
+            'J' => match params.get_or(0, 0) {
+                0 => self.grid.clear_screen_from_cursor(),
+                1 => self.grid.clear_screen_to_cursor(),
+                2 => self.grid.clear_screen(),
+                _ => {}
+            },
+            'K' => match params.get_or(0, 0) {
+                0 => self.grid.clear_line_from_cursor(),
+                1 => self.grid.clear_line_to_cursor(),
+                2 => self.grid.clear_line(),
+                _ => {}
+            },
+            
             // Scrolling
-            'S' => {
-                let lines = params.iter().next().map_or(1, |p| p[0] as u16);
-                self.grid.scroll_up(lines);
-            }
-            'T' => {
-                let lines = params.iter().next().map_or(1, |p| p[0] as u16);
-                self.grid.scroll_down(lines);
-            }
+            'S' => self.grid.scroll_up(params.get_or(0, 1)),
+            'T' => self.grid.scroll_down(params.get_or(0, 1)),
             
             // Character attributes (SGR)
             'm' => {
-                self.handle_sgr(params);
+                self.handle_sgr(&params);
+                // Erases and scrolls fill with the new background right away
+                self.sync_grid_pen();
+            }
+            
+            // Window manipulation (XTWINOPS): size reports and the title stack
+            't' => self.handle_window_op(&params),
+            
+            // Cursor style (DECSCUSR): block, underline or bar; odd styles
+            // blink, even ones are steady
+            'q' if intermediates == [b' '] => {
+                let style = params.get_or(0, 0);
+                let (shape, blink) = match style {
+                    0 => (CursorShape::Block, None),
+                    1 | 2 => (CursorShape::Block, Some(style == 1)),
+                    3 | 4 => (CursorShape::Underline, Some(style == 3)),
+                    5 | 6 => (CursorShape::Bar, Some(style == 5)),
+                    _ => return,
+                };
+                self.grid.set_cursor_shape(shape);
+                self.grid.set_cursor_style_blink(blink);
+            }
+            
+            // Device attributes: primary (DA1) and secondary (DA2) requests
+            'c' if params.get_or(0, 0) == 0 => {
+                let reply = match intermediates {
+                    [] => PRIMARY_DEVICE_ATTRIBUTES.to_vec(),
+                    [b'>'] => secondary_device_attributes(),
+                    _ => return,
+                };
+                self.events.push(TerminalEvent::Response(reply));
             }
             
-            // Cursor visibility
-            'h' => {
-                if let Some(param) = params.iter().next() {
-                    if param[0] == 25 {
-                        self.grid.set_cursor_visible(true);
+            // Tab clear (TBC): at the cursor column, or everywhere
+            'g' => match params.get_or(0, 0) {
+                0 => self.grid.clear_tab_stop(),
+                3 => self.grid.clear_all_tab_stops(),
+                _ => {}
+            },
+            
+            // Device status reports: operating status and cursor position (CPR)
+            'n' => {
+                let reply = match (intermediates, params.get_or(0, 0)) {
+                    ([], 5) => b"\x1b[0n".to_vec(),
+                    ([] | [b'?'], 6) => {
+                        let (row, col) = self.grid.cursor_position();
+                        let private = if intermediates.is_empty() { "" } else { "?" };
+                        format!("\x1b[{}{};{}R", private, row + 1, col + 1).into_bytes()
                     }
+                    _ => return,
+                };
+                self.events.push(TerminalEvent::Response(reply));
+            }
+            
+            // Soft terminal reset (DECSTR)
+            'p' if intermediates == [b'!'] => self.soft_reset(),
+            
+            // DEC private modes (DECSET / DECRST)
+            'h' | 'l' if intermediates == [b'?'] => {
+                let enabled = c == 'h';
+                for param in params.iter() {
+                    self.set_private_mode(param[0], enabled);
                 }
             }
-            'l' => {
-                if let Some(param) = params.iter().next() {
-                    if param[0] == 25 {
-                        self.grid.set_cursor_visible(false);
-                    }
+            
+            // ANSI modes (SM / RM)
+            'h' | 'l' if intermediates.is_empty() => {
+                let enabled = c == 'h';
+                for param in params.iter() {
+                    self.set_mode(param[0], enabled);
                 }
             }
             
@@ -177,13 +595,193 @@ impl<'a> Perform for ParserPerformer<'a> {
         }
     }
     
-    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {
-        // TODO: Implement escape sequences
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        match (intermediates, byte) {
+            // DEC line size attributes for the cursor row
+            ([b'#'], b'3') => self.set_line_attr(LineAttr::DoubleHeightTop),
+            ([b'#'], b'4') => self.set_line_attr(LineAttr::DoubleHeightBottom),
+            ([b'#'], b'5') => self.set_line_attr(LineAttr::Normal),
+            ([b'#'], b'6') => self.set_line_attr(LineAttr::DoubleWidth),
+            
+            // Save / restore cursor (DECSC / DECRC)
+            ([], b'7') => self.save_cursor(),
+            ([], b'8') => self.restore_cursor(),
+            
+            // Index, next line, tab set, reverse index
+            ([], b'D') => self.grid.index(),
+            ([], b'E') => self.grid.newline(),
+            ([], b'H') => self.grid.set_tab_stop(),
+            ([], b'M') => self.grid.reverse_index(),
+            _ => {
+                // TODO: Implement remaining escape sequences
+            }
+        }
     }
 }
 
 impl<'a> ParserPerformer<'a> {
-    fn handle_sgr(&mut self, params: &vte::Params) {
+    fn set_mode(&mut self, mode: u16, enabled: bool) {
+        if mode == 4 {
+            self.grid.set_insert_mode(enabled);
+        }
+    }
+    
+    fn set_private_mode(&mut self, mode: u16, enabled: bool) {
+        match mode {
+            12 => self.grid.set_cursor_blink_mode(enabled),
+            47 | 1047 if enabled => self.grid.enter_alternate_screen(),
+            47 | 1047 => self.grid.exit_alternate_screen(),
+            1048 if enabled => self.save_cursor(),
+            1048 => self.restore_cursor(),
+            // 1048 + 1047: the cursor is saved on the main screen before switching
+            // and restored there after switching back
+            1049 if enabled => {
+                self.save_cursor();
+                self.grid.enter_alternate_screen();
+            }
+            1049 => {
+                self.grid.exit_alternate_screen();
+                self.restore_cursor();
+            }
+            6 => {
+                self.modes.origin_mode = enabled;
+                self.set_cursor(0, 0);
+            }
+            7 => self.grid.set_autowrap(enabled),
+            25 => self.grid.set_cursor_visible(enabled),
+            1004 => self.modes.focus_reporting = enabled,
+            1007 => self.modes.alternate_scroll = enabled,
+            9 | 1000 | 1002 | 1003 => {
+                let tracking = match mode {
+                    9 => MouseTracking::X10,
+                    1000 => MouseTracking::Normal,
+                    1002 => MouseTracking::ButtonEvent,
+                    _ => MouseTracking::AnyEvent,
+                };
+                if enabled {
+                    self.modes.mouse_tracking = tracking;
+                } else if self.modes.mouse_tracking == tracking {
+                    self.modes.mouse_tracking = MouseTracking::Off;
+                }
+            }
+            1005 | 1006 => {
+                let encoding = if mode == 1005 { MouseEncoding::Utf8 } else { MouseEncoding::Sgr };
+                if enabled {
+                    self.modes.mouse_encoding = encoding;
+                } else if self.modes.mouse_encoding == encoding {
+                    self.modes.mouse_encoding = MouseEncoding::X10;
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    /// Move the cursor to `row`, `col`; in origin mode the row counts from
+    /// the top of the scroll region and stops at its bottom
+    fn set_cursor(&mut self, row: u16, col: u16) {
+        if self.modes.origin_mode {
+            let (top, bottom) = self.grid.scroll_region();
+            self.grid.set_cursor(top.saturating_add(row).min(bottom), col);
+        } else {
+            self.grid.set_cursor(row, col);
+        }
+    }
+    
+    /// DECSTR: modes and the pen reset as in the grid's soft reset; unlike
+    /// RIS the screen is kept. Mouse and focus reporting stay, as in xterm.
+    fn soft_reset(&mut self) {
+        self.grid.soft_reset();
+        self.modes.origin_mode = false;
+        *self.current_attrs = CellAttributes::default();
+        *self.current_fg = TerminalColor::DefaultFg;
+        *self.current_bg = TerminalColor::DefaultBg;
+        *self.last_printed = None;
+    }
+    
+    /// Record where an OSC 133 (or 633) prompt marker was seen
+    fn prompt_marker(&mut self, kind: Option<&[u8]>) {
+        let (row, col) = self.grid.cursor_position();
+        let position = Some((self.grid.lines_scrolled() + row as u64, col));
+        match kind.and_then(|kind| kind.first()) {
+            // A new prompt starts over
+            Some(b'A') => *self.prompt_markers = PromptMarkers::default(),
+            Some(b'B') => self.prompt_markers.input_start = position,
+            Some(b'C') => self.prompt_markers.output_start = position,
+            _ => {}
+        }
+    }
+    
+    /// Hand the current SGR state to the grid, which writes and erases with it
+    fn sync_grid_pen(&mut self) {
+        self.grid.set_attrs(self.current_attrs.clone());
+        self.grid.set_fg_color(*self.current_fg);
+        self.grid.set_bg_color(*self.current_bg);
+    }
+    
+    /// DECSC: the grid saves the cursor along with the parser's current SGR state
+    fn save_cursor(&mut self) {
+        self.sync_grid_pen();
+        self.grid.save_cursor();
+    }
+    
+    /// DECRC: restore the cursor and take the saved SGR state back
+    fn restore_cursor(&mut self) {
+        self.grid.restore_cursor();
+        *self.current_attrs = self.grid.current_attrs().clone();
+        (*self.current_fg, *self.current_bg) = self.grid.current_colors();
+    }
+    
+    /// Change the window title, queueing an event when it differs
+    fn set_title(&mut self, title: String) {
+        if *self.title != title {
+            self.events.push(TerminalEvent::TitleChanged(title.clone()));
+            *self.title = title;
+        }
+    }
+    
+    fn handle_window_op(&mut self, params: &CsiParams) {
+        let op = params.get_or(0, 0);
+        match op {
+            // Report text area size in pixels: CSI 4 ; height ; width t
+            14 => {
+                if let Some((cell_width, cell_height)) = self.cell_pixel_size {
+                    let height = self.grid.rows as u32 * cell_height;
+                    let width = self.grid.cols as u32 * cell_width;
+                    let report = format!("\x1b[4;{};{}t", height, width);
+                    self.events.push(TerminalEvent::Response(report.into_bytes()));
+                }
+            }
+            // Report text area size in characters: CSI 8 ; rows ; cols t
+            18 => {
+                let report = format!("\x1b[8;{};{}t", self.grid.rows, self.grid.cols);
+                self.events.push(TerminalEvent::Response(report.into_bytes()));
+            }
+            22 | 23 => {
+                // Second parameter: 0 = icon and window title, 1 = icon only, 2 = window only
+                let which = params.get_or(1, 0);
+                if which != 0 && which != 2 {
+                    return;
+                }
+                
+                if op == 22 {
+                    if self.title_stack.len() == MAX_TITLE_STACK_DEPTH {
+                        self.title_stack.remove(0);
+                    }
+                    self.title_stack.push(self.title.clone());
+                } else if let Some(title) = self.title_stack.pop() {
+                    self.set_title(title);
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    fn set_line_attr(&mut self, attr: LineAttr) {
+        let (row, _) = self.grid.cursor_position();
+        self.grid.set_line_attr(row, attr);
+    }
+    
+    fn handle_sgr(&mut self, params: &CsiParams) {
         if params.is_empty() {
             // Reset all attributes
             *self.current_attrs = CellAttributes::default();
@@ -192,7 +790,8 @@ impl<'a> ParserPerformer<'a> {
             return;
         }
         
-        for param in params.iter() {
+        let mut params = params.iter();
+        while let Some(param) = params.next() {
             match param[0] {
                 // Reset
                 0 => {
@@ -203,16 +802,25 @@ impl<'a> ParserPerformer<'a> {
                 
                 // Attributes
                 1 => self.current_attrs.bold = true,
+                2 => self.current_attrs.dim = true,
                 3 => self.current_attrs.italic = true,
                 4 => self.current_attrs.underline = true,
                 5 => self.current_attrs.blink = true,
                 7 => self.current_attrs.reverse = true,
                 9 => self.current_attrs.strikethrough = true,
+                21 => self.current_attrs.double_underline = true,
                 
-                // Reset attributes
-                22 => self.current_attrs.bold = false,
+                // Reset attributes; 22 is "normal intensity" and clears both bold
+                // and dim, 24 clears every underline style
+                22 => {
+                    self.current_attrs.bold = false;
+                    self.current_attrs.dim = false;
+                }
                 23 => self.current_attrs.italic = false,
-                24 => self.current_attrs.underline = false,
+                24 => {
+                    self.current_attrs.underline = false;
+                    self.current_attrs.double_underline = false;
+                }
                 25 => self.current_attrs.blink = false,
                 27 => self.current_attrs.reverse = false,
                 29 => self.current_attrs.strikethrough = false,
@@ -225,20 +833,30 @@ impl<'a> ParserPerformer<'a> {
                 
                 // Background colors
                 40..=47 | 100..=107 => {
-                    *self.current_bg = TerminalColor::from_ansi_code(param[0] as u8 + 10);
+                    *self.current_bg = TerminalColor::from_ansi_code(param[0] as u8 - 10);
                 }
                 49 => *self.current_bg = TerminalColor::DefaultBg,
                 
                 // 256-color and RGB color modes
                 38 => {
-                    // Foreground 256-color or RGB
-                    // TODO: Parse subsequent parameters
+                    if let Some(color) = extended_color(param, &mut params) {
+                        *self.current_fg = color;
+                    }
                 }
                 48 => {
-                    // Background 256-color or RGB
-                    // TODO: Parse subsequent parameters
+                    if let Some(color) = extended_color(param, &mut params) {
+                        *self.current_bg = color;
+                    }
                 }
                 
+                // Underline color, drawn instead of the foreground
+                58 => {
+                    if let Some(color) = extended_color(param, &mut params) {
+                        self.current_attrs.underline_color = Some(color);
+                    }
+                }
+                59 => self.current_attrs.underline_color = None,
+                
                 _ => {
                     // Ignore unknown parameters
                 }
@@ -247,6 +865,146 @@ impl<'a> ParserPerformer<'a> {
     }
 }
 
+/// The color of an SGR 38/48/58 parameter: `5;n` (palette index) or
+/// `2;r;g;b` as the following parameters, or the colon forms `5:n`,
+/// `2:r:g:b` and `2::r:g:b` (empty color space id) as subparameters.
+/// Following parameters are consumed even when the color is invalid.
+fn extended_color<'a>(param: &[u16], params: &mut impl Iterator<Item = &'a [u16]>) -> Option<TerminalColor> {
+    let (mode, values): (u16, Vec<u16>) = if param.len() > 1 {
+        let values = match param {
+            [_, 2, _, r, g, b] => vec![*r, *g, *b],
+            [_, _, rest @ ..] => rest.to_vec(),
+            _ => Vec::new(),
+        };
+        (param[1], values)
+    } else {
+        let mode = params.next()?[0];
+        let count = match mode {
+            5 => 1,
+            2 => 3,
+            _ => 0,
+        };
+        (mode, params.by_ref().take(count).map(|value| value[0]).collect())
+    };
+    
+    let byte = |value: u16| u8::try_from(value).ok();
+    match (mode, values.as_slice()) {
+        (5, [index]) => Some(TerminalColor::Indexed(byte(*index)?)),
+        (2, [r, g, b]) => Some(TerminalColor::Rgb { r: byte(*r)?, g: byte(*g)?, b: byte(*b)? }),
+        _ => None,
+    }
+}
+
+/// Extract the path from a `file://host/path` URI, decoding percent-escapes
+fn parse_file_uri(uri: &[u8]) -> Option<PathBuf> {
+    let rest = uri.strip_prefix(b"file://")?;
+    // Skip the (possibly empty) host component
+    let path_start = rest.iter().position(|&b| b == b'/')?;
+    let decoded = percent_decode(&rest[path_start..]);
+    Some(PathBuf::from(String::from_utf8_lossy(&decoded).into_owned()))
+}
+
+/// Text of an OSC 52 payload; `None` for queries, invalid base64 or non-UTF-8 text
+fn decode_clipboard_text(payload: &[u8]) -> Option<String> {
+    use base64::Engine;
+    
+    if payload == b"?" {
+        return None;
+    }
+    let bytes = base64::engine::general_purpose::STANDARD.decode(payload).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Rejoin OSC parameters the parser split on `;`, for free text that may contain one
+fn join_osc_params(params: &[&[u8]]) -> String {
+    String::from_utf8_lossy(&params.join(&b';')).into_owned()
+}
+
+/// Format a color as the `rgb:RRRR/GGGG/BBBB` spec xterm uses in replies
+fn format_color_spec(color: Rgba) -> String {
+    // Scale 8-bit channels to 16 bits, so 0xff becomes 0xffff
+    let scale = |channel: u8| channel as u16 * 0x101;
+    format!("rgb:{:04x}/{:04x}/{:04x}", scale(color.r), scale(color.g), scale(color.b))
+}
+
+/// Parse an X11 color spec as used by OSC 10-12: `#rrggbb` or `rgb:r/g/b`
+/// with 1-4 hex digits per channel
+fn parse_color_spec(spec: &[u8]) -> Option<Rgba> {
+    let spec = std::str::from_utf8(spec).ok()?;
+    
+    if let Some(hex) = spec.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Rgba::rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    
+    let mut channels = spec.strip_prefix("rgb:")?.split('/').map(|part| {
+        if part.is_empty() || part.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(part, 16).ok()?;
+        // Scale to 8 bits, e.g. "f" -> 0xff and "ffff" -> 0xff
+        let max = (1u32 << (4 * part.len())) - 1;
+        Some((value * 255 / max) as u8)
+    });
+    let color = Rgba::rgb(channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(color)
+}
+
+fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] == b'%' && i + 2 < input.len() {
+            let hex = std::str::from_utf8(&input[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(input[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Undo OSC 633's escaping: `\\` is a backslash and `\xHH` a byte, which is
+/// how `;` and control characters such as newlines travel inside a parameter
+fn unescape_shell_integration(input: &[u8]) -> String {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match &input[i..] {
+            [b'\\', b'\\', ..] => {
+                out.push(b'\\');
+                i += 2;
+            }
+            [b'\\', b'x', high, low, ..] => {
+                let hex = std::str::from_utf8(&[*high, *low]).ok().and_then(|h| u8::from_str_radix(h, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 4;
+                    }
+                    None => {
+                        out.push(b'\\');
+                        i += 1;
+                    }
+                }
+            }
+            [byte, ..] => {
+                out.push(*byte);
+                i += 1;
+            }
+            [] => break,
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -258,6 +1016,78 @@ mod tests {
         assert_eq!(parser.grid().cols, 80);
     }
     
+    #[test]
+    fn test_whole_slice_matches_bytewise_parse() {
+        let mut input = Vec::new();
+        for i in 0..200 {
+            input.extend_from_slice(format!("\x1b]0;job {}\x07\x1b[1;3{}mline {} \u{4e2d}\u{6587}\x1b[0m e\u{301}\r\n", i, i % 8, i).as_bytes());
+            if i % 50 == 0 {
+                input.extend_from_slice(b"\x1b[2J\x1b[H\x1b[?1049h\x1b[5;10Hfull screen\x1b[?1049l\x1b[6n");
+            }
+        }
+        input.extend_from_slice(b"[####      ] 40%\r[##########] 100%\x1b[K");
+        
+        let mut whole = TerminalParser::new(24, 80);
+        whole.parse(&input);
+        // One call per byte, like the old loop rebuilding the performer each time
+        let mut bytewise = TerminalParser::new(24, 80);
+        for byte in &input {
+            bytewise.parse(std::slice::from_ref(byte));
+        }
+        
+        assert_eq!(whole.state_snapshot(), bytewise.state_snapshot());
+        for row in 0..24 {
+            assert_eq!(whole.grid().row(row), bytewise.grid().row(row));
+        }
+        assert_eq!(whole.drain_events(), bytewise.drain_events());
+    }
+    
+    #[test]
+    fn test_erase_in_display_fills_background() {
+        let mut parser = TerminalParser::new(4, 10);
+        parser.parse(b"one\r\ntwo\r\nthree\r\nfour");
+        
+        // ED 0 from the middle of row 1
+        parser.parse(b"\x1b[2;2H\x1b[44m\x1b[J");
+        assert_eq!(parser.grid().to_string(), "one\nt\n\n");
+        let blue = TerminalColor::Blue;
+        assert_eq!(parser.grid().cell_at(1, 0).unwrap().bg_color, TerminalColor::Black);
+        assert_eq!(parser.grid().cell_at(1, 1).unwrap().bg_color, blue);
+        assert_eq!(parser.grid().cell_at(3, 9).unwrap().bg_color, blue);
+        
+        // ED 1 up to and including the cursor, with the default background
+        parser.parse(b"\x1b[2;1Hx\x1b[49m\x1b[1J");
+        assert_eq!(parser.grid().to_string(), "\n\n\n");
+        assert_eq!(parser.grid().cell_at(0, 5).unwrap().bg_color, TerminalColor::DefaultBg);
+        assert_eq!(parser.grid().cell_at(1, 1).unwrap().bg_color, TerminalColor::DefaultBg);
+        assert_eq!(parser.grid().cell_at(1, 2).unwrap().bg_color, blue);
+    }
+    
+    #[test]
+    fn test_tab_clear() {
+        let mut parser = TerminalParser::new(2, 30);
+        // Clear the default stop at 8, then tab from 0 lands on 16
+        parser.parse(b"\x1b[1;9H\x1b[g\r\tx");
+        assert_eq!(parser.grid().cursor_position(), (0, 17));
+        
+        parser.parse(b"\x1b[3g\r\t");
+        assert_eq!(parser.grid().cursor_position(), (0, 29));
+    }
+    
+    #[test]
+    fn test_device_status_reports() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b[5n\x1b[3;7H\x1b[6n\x1b[?6n\x1b[99n");
+        assert_eq!(
+            parser.drain_events(),
+            vec![
+                TerminalEvent::Response(b"\x1b[0n".to_vec()),
+                TerminalEvent::Response(b"\x1b[3;7R".to_vec()),
+                TerminalEvent::Response(b"\x1b[?3;7R".to_vec()),
+            ]
+        );
+    }
+    
     #[test]
     fn test_simple_text() {
         let mut parser = TerminalParser::new(24, 80);
@@ -267,6 +1097,24 @@ mod tests {
         assert_eq!(parser.grid().cell_at(0, 4).unwrap().ch, 'o');
     }
     
+    #[test]
+    fn test_nul_and_del_are_dropped() {
+        let mut parser = TerminalParser::new(3, 10);
+        parser.parse(b"a\x00b\x7fc\x00\x00\x01\x1f\xc2\x9f");
+        
+        let grid = parser.grid();
+        assert_eq!(grid.cursor_position(), (0, 3));
+        assert_eq!(grid.logical_lines().next().unwrap(), "abc");
+        // Everything after the text is still never-written space
+        for col in 3..10 {
+            assert!(grid.cell_at(0, col).unwrap().ch == '\0', "col {}", col);
+        }
+        
+        // REP after a dropped control has nothing to repeat
+        parser.parse(b"\x00\x1b[3b");
+        assert_eq!(parser.grid().cursor_position(), (0, 3));
+    }
+    
     #[test]
     fn test_newline() {
         let mut parser = TerminalParser::new(24, 80);
@@ -275,4 +1123,745 @@ mod tests {
         assert_eq!(parser.grid().cell_at(0, 0).unwrap().ch, 'L');
         assert_eq!(parser.grid().cell_at(1, 0).unwrap().ch, 'L');
     }
+    
+    #[test]
+    fn test_osc7_current_directory() {
+        let mut parser = TerminalParser::new(24, 80);
+        assert!(parser.current_directory().is_none());
+        
+        parser.parse(b"\x1b]7;file:///home/user/my%20proj\x07");
+        assert_eq!(parser.current_directory(), Some(Path::new("/home/user/my proj")));
+        
+        // Host component is skipped and ST terminator is accepted
+        parser.parse(b"\x1b]7;file://myhost/tmp/caf%C3%A9\x1b\\");
+        assert_eq!(parser.current_directory(), Some(Path::new("/tmp/café")));
+    }
+    
+    #[test]
+    fn test_osc7_ignores_invalid_uri() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b]7;/not/a/uri\x07");
+        assert!(parser.current_directory().is_none());
+        
+        // Malformed escapes are kept literally
+        assert_eq!(percent_decode(b"/a%zzb%2"), b"/a%zzb%2".to_vec());
+    }
+    
+    #[test]
+    fn test_line_size_attributes() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b#3Banner\r\n\x1b#4Banner\r\n\x1b#6Wide");
+        
+        assert_eq!(parser.grid().line_attr(0), LineAttr::DoubleHeightTop);
+        assert_eq!(parser.grid().line_attr(1), LineAttr::DoubleHeightBottom);
+        assert_eq!(parser.grid().line_attr(2), LineAttr::DoubleWidth);
+        assert_eq!(parser.grid().line_attr(3), LineAttr::Normal);
+        
+        // DECSWL returns the row to single width
+        parser.parse(b"\x1b#5");
+        assert_eq!(parser.grid().line_attr(2), LineAttr::Normal);
+    }
+    
+    #[test]
+    fn test_feed_str() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.feed_str("héllo\r\n\x1b[1mwörld");
+        
+        assert_eq!(parser.grid().cell_at(0, 1).unwrap().ch, 'é');
+        assert_eq!(parser.grid().cell_at(1, 1).unwrap().ch, 'ö');
+        assert!(parser.grid().cell_at(1, 0).unwrap().attrs.bold);
+    }
+    
+    #[test]
+    fn test_flush_discards_dangling_sequence() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.feed_str("\x1b[31mA");
+        parser.flush();
+        
+        // A completed sequence is not affected by flushing
+        assert_eq!(parser.grid().cell_at(0, 0).unwrap().ch, 'A');
+        assert_eq!(parser.grid().cell_at(0, 0).unwrap().fg_color, TerminalColor::Red);
+        
+        // Without flush, "B" would be swallowed as a CSI final byte
+        parser.feed_str("\x1b[1");
+        parser.flush();
+        parser.feed_str("B");
+        assert_eq!(parser.grid().cell_at(0, 1).unwrap().ch, 'B');
+        assert_eq!(parser.grid().cursor_position(), (0, 2));
+    }
+    
+    #[test]
+    fn test_sgr_22_clears_bold_and_dim() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b[1;2mA\x1b[22mB");
+        
+        let styled = &parser.grid().cell_at(0, 0).unwrap().attrs;
+        assert!(styled.bold && styled.dim);
+        let plain = &parser.grid().cell_at(0, 1).unwrap().attrs;
+        assert!(!plain.bold && !plain.dim);
+    }
+    
+    #[test]
+    fn test_sgr_24_clears_double_underline() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b[4;21mA\x1b[24mB");
+        
+        let styled = &parser.grid().cell_at(0, 0).unwrap().attrs;
+        assert!(styled.underline && styled.double_underline);
+        let plain = &parser.grid().cell_at(0, 1).unwrap().attrs;
+        assert!(!plain.underline && !plain.double_underline);
+    }
+    
+    #[test]
+    fn test_sgr_default_colors_keep_attributes() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b[1;3;31;42m\x1b[39;49mA");
+        
+        let cell = parser.grid().cell_at(0, 0).unwrap();
+        assert_eq!(cell.fg_color, TerminalColor::DefaultFg);
+        assert_eq!(cell.bg_color, TerminalColor::DefaultBg);
+        assert!(cell.attrs.bold && cell.attrs.italic);
+    }
+    
+    #[test]
+    fn test_sgr_58_underline_color() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b[4;58;2;255;64;0mA\x1b[58;5;196mB\x1b[58:2::0:128:255mC\x1b[59mD\x1b[0mE");
+        
+        let attrs = |col| parser.grid().cell_at(0, col).unwrap().attrs.clone();
+        assert_eq!(attrs(0).underline_color, Some(TerminalColor::Rgb { r: 255, g: 64, b: 0 }));
+        assert!(attrs(0).underline);
+        assert_eq!(attrs(1).underline_color, Some(TerminalColor::Indexed(196)));
+        assert_eq!(attrs(2).underline_color, Some(TerminalColor::Rgb { r: 0, g: 128, b: 255 }));
+        // 59 resets only the color; the foreground was never touched
+        assert_eq!(attrs(3).underline_color, None);
+        assert!(attrs(3).underline);
+        let mut plain = TerminalParser::new(24, 80);
+        plain.parse(b"C");
+        assert_eq!(parser.grid().cell_at(0, 2).unwrap().fg_color, plain.grid().cell_at(0, 0).unwrap().fg_color);
+        assert_eq!(attrs(4), CellAttributes::default());
+    }
+    
+    #[test]
+    fn test_sgr_extended_colors_consume_their_params() {
+        let mut parser = TerminalParser::new(24, 80);
+        // The color components must not be read as SGR 1 (bold) or 0 (reset)
+        parser.parse(b"\x1b[3;38;2;1;0;200;48;5;1mA\x1b[58;2;300;0;0;1mB");
+        
+        let cell = parser.grid().cell_at(0, 0).unwrap();
+        assert_eq!(cell.fg_color, TerminalColor::Rgb { r: 1, g: 0, b: 200 });
+        assert_eq!(cell.bg_color, TerminalColor::Indexed(1));
+        assert!(cell.attrs.italic && !cell.attrs.bold);
+        // An out-of-range component drops the color but keeps what follows
+        let cell = parser.grid().cell_at(0, 1).unwrap();
+        assert_eq!(cell.attrs.underline_color, None);
+        assert!(cell.attrs.bold);
+    }
+    
+    #[test]
+    fn test_title_stack_push_pop() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b]2;vim\x07");
+        assert_eq!(parser.title(), "vim");
+        
+        parser.parse(b"\x1b[22;0t\x1b]0;less\x07");
+        assert_eq!(parser.title(), "less");
+        
+        parser.parse(b"\x1b[23;0t");
+        assert_eq!(parser.title(), "vim");
+        
+        // Popping an empty stack keeps the current title
+        parser.parse(b"\x1b[23t");
+        assert_eq!(parser.title(), "vim");
+    }
+    
+    #[test]
+    fn test_title_stack_ignores_malformed_params() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b]2;shell\x07\x1b[22;7t\x1b]2;top\x07\x1b[23;7t");
+        assert_eq!(parser.title(), "top");
+        
+        // Icon-only push saves nothing for the window title
+        parser.parse(b"\x1b[22;1t\x1b[23;0t");
+        assert_eq!(parser.title(), "top");
+    }
+    
+    #[test]
+    fn test_osc12_cursor_color() {
+        let mut parser = TerminalParser::new(24, 80);
+        assert_eq!(parser.cursor_color(), None);
+        
+        parser.parse(b"\x1b]12;#ff8000\x07");
+        assert_eq!(parser.cursor_color(), Some(Rgba::rgb(0xff, 0x80, 0x00)));
+        
+        parser.parse(b"\x1b]12;rgb:ffff/0/80\x1b\\");
+        assert_eq!(parser.cursor_color(), Some(Rgba::rgb(0xff, 0x00, 0x80)));
+        
+        // Invalid specs leave the color unchanged
+        parser.parse(b"\x1b]12;rgb:12/34\x07\x1b]12;#zzzzzz\x07");
+        assert_eq!(parser.cursor_color(), Some(Rgba::rgb(0xff, 0x00, 0x80)));
+        
+        parser.parse(b"\x1b]112\x07");
+        assert_eq!(parser.cursor_color(), None);
+    }
+    
+    #[test]
+    fn test_osc12_cursor_color_query() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.set_default_cursor_color(Rgba::rgb(0x10, 0x20, 0x30));
+        
+        // Unset: the default is reported
+        parser.parse(b"\x1b]12;?\x07");
+        assert_eq!(responses(&mut parser), b"\x1b]12;rgb:1010/2020/3030\x07".to_vec());
+        
+        // Set color, queried with an ST terminator
+        parser.parse(b"\x1b]12;#ff8000\x07\x1b]12;?\x1b\\");
+        assert_eq!(responses(&mut parser), b"\x1b]12;rgb:ffff/8080/0000\x1b\\".to_vec());
+        assert_eq!(parser.cursor_color(), Some(Rgba::rgb(0xff, 0x80, 0x00)));
+        
+        // Malformed queries get no reply and leave the color alone
+        parser.parse(b"\x1b]12;??\x07\x1b]12;?x\x07");
+        assert!(responses(&mut parser).is_empty());
+        assert_eq!(parser.cursor_color(), Some(Rgba::rgb(0xff, 0x80, 0x00)));
+    }
+    
+    #[test]
+    fn test_focus_reporting() {
+        let mut parser = TerminalParser::new(24, 80);
+        assert_eq!(parser.focus_report_bytes(true), None);
+        
+        parser.parse(b"\x1b[?1004h");
+        assert!(parser.modes().focus_reporting);
+        assert_eq!(parser.focus_report_bytes(true), Some(b"\x1b[I".to_vec()));
+        assert_eq!(parser.focus_report_bytes(false), Some(b"\x1b[O".to_vec()));
+        
+        parser.parse(b"\x1b[?1004l");
+        assert_eq!(parser.focus_report_bytes(false), None);
+        
+        // Without the `?` prefix this is an ANSI mode, not focus reporting
+        parser.parse(b"\x1b[1004h");
+        assert_eq!(parser.focus_report_bytes(true), None);
+    }
+    
+    #[test]
+    fn test_mouse_modes() {
+        use crate::renderer::{MouseAction, MouseButton};
+        use winit::keyboard::ModifiersState;
+        
+        let mut parser = TerminalParser::new(24, 80);
+        let drag = MouseEvent {
+            action: MouseAction::Motion(Some(MouseButton::Left)),
+            row: 1,
+            col: 2,
+            modifiers: ModifiersState::empty(),
+        };
+        assert_eq!(parser.encode_mouse_event(&drag), None);
+        
+        parser.parse(b"\x1b[?1002h\x1b[?1006h");
+        assert_eq!(parser.modes().mouse_tracking, MouseTracking::ButtonEvent);
+        assert_eq!(parser.encode_mouse_event(&drag), Some(b"\x1b[<32;3;2M".to_vec()));
+        
+        // Resetting a mode that isn't active leaves the current one alone
+        parser.parse(b"\x1b[?1000l\x1b[?1005l");
+        assert_eq!(parser.modes().mouse_tracking, MouseTracking::ButtonEvent);
+        assert_eq!(parser.modes().mouse_encoding, MouseEncoding::Sgr);
+        
+        parser.parse(b"\x1b[?1006l");
+        assert_eq!(parser.encode_mouse_event(&drag), Some(b"\x1b[M@#\"".to_vec()));
+        parser.parse(b"\x1b[?1002l");
+        assert_eq!(parser.encode_mouse_event(&drag), None);
+    }
+    
+    #[test]
+    fn test_alternate_scroll_wheel_keys() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b[?1007h");
+        // Only on the alternate screen
+        assert_eq!(parser.wheel_to_keys(true, 3), None);
+        
+        parser.parse(b"\x1b[?1049h");
+        assert_eq!(parser.wheel_to_keys(true, 3), Some(b"\x1b[A\x1b[A\x1b[A".to_vec()));
+        assert_eq!(parser.wheel_to_keys(false, 1), Some(b"\x1b[B".to_vec()));
+        assert_eq!(parser.wheel_to_keys(false, 0), None);
+        
+        // Applications tracking the mouse get the wheel as mouse reports instead
+        parser.parse(b"\x1b[?1000h");
+        assert_eq!(parser.wheel_to_keys(true, 1), None);
+        parser.parse(b"\x1b[?1000l\x1b[?1007l");
+        assert!(!parser.modes().alternate_scroll);
+        assert_eq!(parser.wheel_to_keys(true, 1), None);
+    }
+    
+    #[test]
+    fn test_device_attributes() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b[c");
+        assert_eq!(responses(&mut parser), b"\x1b[?62;22c");
+        parser.parse(b"\x1b[0c");
+        assert_eq!(responses(&mut parser), b"\x1b[?62;22c");
+        
+        // termind 0.3.0
+        parser.parse(b"\x1b[>c");
+        assert_eq!(responses(&mut parser), b"\x1b[>1;300;0c");
+        
+        // Tertiary DA and nonzero parameters are not answered
+        parser.parse(b"\x1b[=c\x1b[1c");
+        assert!(responses(&mut parser).is_empty());
+    }
+    
+    /// Bytes of the `Response` events queued so far; other events are dropped
+    fn responses(parser: &mut TerminalParser) -> Vec<u8> {
+        parser
+            .drain_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                TerminalEvent::Response(bytes) => Some(bytes),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+    
+    fn screen_text(parser: &TerminalParser) -> Vec<String> {
+        (0..parser.grid().rows)
+            .map(|row| parser.grid().row(row).unwrap().iter().map(|cell| cell.ch).collect())
+            .collect()
+    }
+    
+    #[test]
+    fn test_c1_controls_match_esc_forms() {
+        let cases: [(&[u8], &[u8]); 4] = [
+            (b"ab\x1bDc", b"ab\x84c"),
+            (b"ab\x1bEc", b"ab\x85c"),
+            (b"\x1b[1;4H\x1bH\r\tx", b"\x1b[1;4H\x88\r\tx"),
+            (b"\x1b[2;3H\x1bM\x1bMy", b"\x1b[2;3H\x8d\x8dy"),
+        ];
+        
+        for (esc_form, c1_form) in cases {
+            let mut esc_parser = TerminalParser::new(4, 20);
+            esc_parser.parse(esc_form);
+            let mut c1_parser = TerminalParser::new(4, 20);
+            c1_parser.parse(c1_form);
+            
+            assert_eq!(screen_text(&esc_parser), screen_text(&c1_parser), "{:?}", c1_form);
+            assert_eq!(esc_parser.grid().cursor_position(), c1_parser.grid().cursor_position());
+        }
+        
+        // Spot-check the effects themselves
+        let mut parser = TerminalParser::new(4, 20);
+        parser.parse(b"ab\x84c\r\n\x1b[1;4H\x88\r\tx");
+        assert_eq!(parser.grid().cell_at(1, 2).unwrap().ch, 'c');
+        assert_eq!(parser.grid().cell_at(0, 3).unwrap().ch, 'x');
+    }
+    
+    #[test]
+    fn test_window_size_reports() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b[18t");
+        assert_eq!(responses(&mut parser), b"\x1b[8;24;80t".to_vec());
+        assert!(responses(&mut parser).is_empty());
+        
+        // Pixel size needs the renderer's cell size
+        parser.parse(b"\x1b[14t");
+        assert!(responses(&mut parser).is_empty());
+        parser.set_cell_pixel_size(9, 18);
+        parser.parse(b"\x1b[14t");
+        assert_eq!(responses(&mut parser), b"\x1b[4;432;720t".to_vec());
+        
+        // Unknown operations are ignored
+        parser.parse(b"\x1b[99t\x1b[11t");
+        assert!(responses(&mut parser).is_empty());
+    }
+    
+    #[test]
+    fn test_cursor_blink_mode() {
+        let mut parser = TerminalParser::new(24, 80);
+        assert!(!parser.grid().cursor_blink_enabled());
+        
+        parser.parse(b"\x1b[?12h");
+        assert!(parser.grid().cursor_blink_enabled());
+        parser.parse(b"\x1b[?12l");
+        assert!(!parser.grid().cursor_blink_enabled());
+        
+        // An explicit steady style overrides the mode until DECSCUSR 0
+        parser.parse(b"\x1b[?12h\x1b[2 q");
+        assert!(!parser.grid().cursor_blink_enabled());
+        parser.parse(b"\x1b[0 q");
+        assert!(parser.grid().cursor_blink_enabled());
+        parser.parse(b"\x1b[?12l\x1b[5 q");
+        assert!(parser.grid().cursor_blink_enabled());
+        assert_eq!(parser.grid().cursor_shape(), CursorShape::Bar);
+        parser.parse(b"\x1b[4 q");
+        assert_eq!(parser.grid().cursor_shape(), CursorShape::Underline);
+        assert!(!parser.grid().cursor_blink_enabled());
+        parser.parse(b"\x1b[0 q");
+        assert_eq!(parser.grid().cursor_shape(), CursorShape::Block);
+    }
+    
+    #[test]
+    fn test_osc9_notification() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b]9;Build finished\x07");
+        // A ';' in the message survives
+        parser.parse(b"\x1b]9;done; 3 warnings\x1b\\");
+        assert_eq!(parser.drain_events(), [
+            TerminalEvent::Notify { title: None, body: "Build finished".to_string() },
+            TerminalEvent::Notify { title: None, body: "done; 3 warnings".to_string() },
+        ]);
+        
+        // ConEmu progress and an empty OSC 9 are not notifications
+        parser.parse(b"\x1b]9;4;1;50\x07\x1b]9\x07");
+        assert!(parser.drain_events().is_empty());
+    }
+    
+    #[test]
+    fn test_osc777_notification() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b]777;notify;cargo test;All 42 tests passed\x07");
+        assert_eq!(parser.drain_events(), [TerminalEvent::Notify {
+            title: Some("cargo test".to_string()),
+            body: "All 42 tests passed".to_string(),
+        }]);
+        
+        // Missing body, then missing title
+        parser.parse(b"\x1b]777;notify;Done\x07\x1b]777;notify\x07");
+        assert_eq!(parser.drain_events(), [
+            TerminalEvent::Notify { title: Some("Done".to_string()), body: String::new() },
+            TerminalEvent::Notify { title: None, body: String::new() },
+        ]);
+        
+        // Other OSC 777 subcommands are ignored
+        parser.parse(b"\x1b]777;precmd\x07");
+        assert!(parser.drain_events().is_empty());
+    }
+    
+    #[test]
+    fn test_osc633_command_started() {
+        let mut parser = TerminalParser::new(5, 40);
+        parser.parse(b"\x1b]633;P;Cwd=/home/me/a\\x3bb\x07\x1b]633;A\x07$ \x1b]633;B\x07");
+        // `;`, newlines and backslashes arrive escaped; the nonce is ignored
+        parser.parse(b"ls 'x;y'\r\n\x1b]633;E;ls 'x\\x3by'\\x0aecho \\\\;f00d\x1b\\\x1b]633;C\x07");
+        
+        let cwd = PathBuf::from("/home/me/a;b");
+        assert_eq!(
+            parser.drain_events(),
+            vec![
+                TerminalEvent::CwdChanged(cwd.clone()),
+                TerminalEvent::CommandStarted {
+                    command: "ls 'x;y'\necho \\".to_string(),
+                    cwd: Some(cwd.clone()),
+                },
+            ]
+        );
+        assert_eq!(parser.current_directory(), Some(cwd.as_path()));
+        // A-C double as the OSC 133 markers
+        assert_eq!(parser.command_line_between_markers().as_deref(), Some("ls 'x;y'"));
+        
+        // Without a command line there is nothing to announce
+        parser.parse(b"\x1b]633;E\x07");
+        assert!(parser.drain_events().is_empty());
+    }
+    
+    #[test]
+    fn test_command_line_between_markers() {
+        let mut parser = TerminalParser::new(5, 20);
+        assert_eq!(parser.command_line_between_markers(), None);
+        
+        // Prompt, then a typed command long enough to wrap
+        parser.parse(b"\x1b]133;A\x07~/src $ \x1b]133;B\x07");
+        parser.parse(b"grep -rn needle src/ --include=*.rs");
+        assert_eq!(parser.command_line_between_markers(), None);
+        
+        parser.parse(b"\r\n\x1b]133;C\x07src/lib.rs:1: needle\r\n");
+        assert_eq!(
+            parser.command_line_between_markers().as_deref(),
+            Some("grep -rn needle src/ --include=*.rs")
+        );
+        
+        // A new prompt clears the previous markers
+        parser.parse(b"\x1b]133;D;0\x07\x1b]133;A\x07$ \x1b]133;B\x07");
+        assert_eq!(parser.command_line_between_markers(), None);
+    }
+    
+    #[test]
+    fn test_command_line_survives_scrolling() {
+        let mut parser = TerminalParser::new(3, 10);
+        parser.parse(b"one\r\ntwo\r\n");
+        
+        // The prompt sits on the bottom row, so submitting scrolls the screen
+        parser.parse(b"\x1b]133;A\x07$ \x1b]133;B\x07ls -la /tmp\r\n\x1b]133;C\x07");
+        assert_eq!(parser.command_line_between_markers().as_deref(), Some("ls -la /tmp"));
+        
+        // Once the input scrolls off screen it can no longer be read
+        parser.parse(b"a\r\nb\r\nc\r\nd\r\n");
+        assert_eq!(parser.command_line_between_markers(), None);
+    }
+    
+    #[test]
+    fn test_multiline_command_between_markers() {
+        let mut parser = TerminalParser::new(5, 20);
+        parser.parse(b"\x1b]133;A\x07$ \x1b]133;B\x07for f in *; do\r\n> echo $f; done\r\n\x1b]133;C\x07");
+        assert_eq!(
+            parser.command_line_between_markers().as_deref(),
+            Some("for f in *; do\n> echo $f; done")
+        );
+    }
+    
+    #[test]
+    fn test_decset_1048_saves_cursor() {
+        let mut parser = TerminalParser::new(10, 20);
+        parser.parse(b"\x1b[3;5H\x1b[1;31m\x1b[?1048h");
+        parser.parse(b"\x1b[8;1H\x1b[0mplain");
+        parser.parse(b"\x1b[?1048lX");
+        
+        // Back at row 3, col 5 with bold red restored
+        let cell = parser.grid().cell_at(2, 4).unwrap();
+        assert_eq!(cell.ch, 'X');
+        assert!(cell.attrs.bold);
+        assert_eq!(cell.fg_color, TerminalColor::Red);
+        assert_eq!(parser.grid().cursor_position(), (2, 5));
+        
+        // ESC 7 / ESC 8 share the same slot
+        parser.parse(b"\x1b[5;2H\x1b7\x1b[H\x1b8");
+        assert_eq!(parser.grid().cursor_position(), (4, 1));
+    }
+    
+    #[test]
+    fn test_insert_mode() {
+        let mut parser = TerminalParser::new(3, 8);
+        parser.parse(b"world\r\x1b[4hhello \x1b[4l");
+        assert!(!parser.grid().insert_mode());
+        assert_eq!(screen_text(&parser)[0], "hello wo");
+        
+        // Back in replace mode, printing overwrites
+        parser.parse(b"\rJ");
+        assert_eq!(screen_text(&parser)[0], "Jello wo");
+        
+        // A private mode 4 is not IRM
+        parser.parse(b"\x1b[?4h\rK");
+        assert_eq!(screen_text(&parser)[0], "Kello wo");
+    }
+    
+    #[test]
+    fn test_zero_count_params_move_one() {
+        let mut parser = TerminalParser::new(10, 20);
+        parser.parse(b"\x1b[5;5H\x1b[0A\x1b[0C");
+        assert_eq!(parser.grid().cursor_position(), (3, 5));
+        parser.parse(b"\x1b[0;0H");
+        assert_eq!(parser.grid().cursor_position(), (0, 0));
+        parser.parse(b"\x1b[;8H");
+        assert_eq!(parser.grid().cursor_position(), (0, 7));
+    }
+    
+    #[test]
+    fn test_origin_mode_addresses_scroll_region() {
+        let mut parser = TerminalParser::new(8, 10);
+        parser.grid_mut().set_scroll_region(2, 5);
+        parser.parse(b"\x1b[4;4H\x1b[?6h");
+        assert!(parser.modes().origin_mode);
+        assert_eq!(parser.grid().cursor_position(), (2, 0));
+        
+        parser.parse(b"\x1b[2;3H");
+        assert_eq!(parser.grid().cursor_position(), (3, 2));
+        parser.parse(b"\x1b[99;1H");
+        assert_eq!(parser.grid().cursor_position(), (5, 0));
+        
+        parser.parse(b"\x1b[?6l");
+        assert_eq!(parser.grid().cursor_position(), (0, 0));
+        parser.parse(b"\x1b[?6h\x1b[!p");
+        assert!(!parser.modes().origin_mode);
+    }
+    
+    #[test]
+    fn test_resize_keeps_origin_mode_and_scroll_region() {
+        let mut parser = TerminalParser::new(10, 20);
+        parser.parse(b"0123456789abcdefghijKLM\r\n");
+        parser.grid_mut().set_scroll_region(3, 8);
+        parser.parse(b"\x1b[?6h\x1b[6;16H\x1b7");
+        assert_eq!(parser.grid().cursor_position(), (8, 15));
+        
+        // The soft-wrapped line rejoins at a wider size; the region is untouched
+        parser.resize(10, 30);
+        assert_eq!(parser.grid().logical_lines().next().unwrap(), "0123456789abcdefghijKLM");
+        assert_eq!(parser.grid().scroll_region(), (3, 8));
+        
+        parser.resize(6, 10);
+        assert!(parser.modes().origin_mode);
+        assert_eq!(parser.grid().scroll_region(), (3, 5));
+        let (row, _) = parser.grid().cursor_position();
+        assert!((3..=5).contains(&row));
+        
+        // Addressing still goes through the clamped region
+        parser.parse(b"\x1b[1;1H");
+        assert_eq!(parser.grid().cursor_position(), (3, 0));
+        parser.parse(b"\x1b[9;1H");
+        assert_eq!(parser.grid().cursor_position(), (5, 0));
+        
+        // The saved cursor was clamped along with the grid
+        parser.parse(b"\x1b8");
+        assert_eq!(parser.grid().cursor_position(), (5, 9));
+        
+        // A region left with fewer than two rows resets to the whole screen
+        parser.resize(3, 30);
+        assert_eq!(parser.grid().scroll_region(), (0, 2));
+        assert!(parser.modes().origin_mode);
+    }
+    
+    #[test]
+    fn test_soft_reset_keeps_screen() {
+        let mut parser = TerminalParser::new(5, 10);
+        parser.parse(b"hello\r\nworld\x1b[1;4;31;44m\x1b[?25l\x1b[?7l\x1b[4h\x1b[2;3H\x1b7");
+        parser.grid_mut().set_scroll_region(1, 3);
+        
+        parser.parse(b"\x1b[!p");
+        
+        let grid = parser.grid();
+        assert_eq!(grid.logical_lines().take(2).collect::<Vec<_>>(), ["hello", "world"]);
+        assert_eq!(grid.cursor_position(), (1, 2));
+        assert!(grid.cursor_visible());
+        assert!(grid.autowrap());
+        assert!(!grid.insert_mode());
+        assert_eq!(grid.scroll_region(), (0, 4));
+        assert_eq!(grid.current_attrs(), &CellAttributes::default());
+        assert_eq!(grid.current_colors(), (TerminalColor::DefaultFg, TerminalColor::DefaultBg));
+        
+        // Text written afterwards is plain, and the saved cursor is gone
+        parser.parse(b"X\x1b8");
+        let cell = parser.grid().cell_at(1, 2).unwrap();
+        assert_eq!(cell.ch, 'X');
+        assert!(!cell.attrs.bold && !cell.attrs.underline);
+        assert_eq!(cell.fg_color, TerminalColor::DefaultFg);
+        assert_eq!(parser.grid().cursor_position(), (0, 0));
+    }
+    
+    #[test]
+    fn test_decset_1049_alternate_screen() {
+        let mut parser = TerminalParser::new(5, 20);
+        parser.parse(b"$ vim notes.txt\r\n");
+        parser.parse(b"\x1b[?1049h");
+        assert!(parser.grid().in_alternate_screen());
+        assert!((0..5).all(|row| parser.grid().row(row).unwrap().iter().all(crate::renderer::Cell::is_empty)));
+        
+        // The full-screen app draws and moves the cursor around, saving its own cursor
+        parser.parse(b"\x1b[H~\r\n~\x1b[4;3H\x1b7\x1b[5;10Hsaved\r\n\r\n");
+        
+        parser.parse(b"\x1b[?1049l");
+        assert!(!parser.grid().in_alternate_screen());
+        assert!(screen_text(&parser)[0].starts_with("$ vim notes.txt"));
+        // Cursor is back where it was on the main screen, not the app's saved spot
+        assert_eq!(parser.grid().cursor_position(), (1, 0));
+        // The alternate screen does not feed scrollback
+        assert_eq!(parser.grid().scrollback_len(), 0);
+        
+        // Leaving when not on the alternate screen changes nothing
+        parser.parse(b"\x1b[?1047l");
+        assert!(screen_text(&parser)[0].starts_with("$ vim notes.txt"));
+    }
+    
+    #[test]
+    fn test_state_snapshot_is_stable() {
+        let input: &[u8] = b"\x1b]2;golden\x07\x1b[?1004h\x1b[?25l\x1b[?12h\
+            \x1b[0;1;4;33mwarn\x1b[22m ok\r\nabcdefghij";
+        let snapshot = |input: &[u8]| {
+            let mut parser = TerminalParser::new(4, 10);
+            parser.parse(input);
+            parser.state_snapshot()
+        };
+        
+        let state = snapshot(input);
+        assert_eq!(state, snapshot(input));
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::json!({
+                "rows": 4,
+                "cols": 10,
+                "cursor_row": 1,
+                "cursor_col": 9,
+                "wrap_pending": true,
+                "cursor_visible": false,
+                "cursor_blink": true,
+                "scroll_region": [0, 3],
+                "alternate_screen": false,
+                "modes": {
+                    "focus_reporting": true,
+                    "mouse_tracking": "Off",
+                    "mouse_encoding": "X10",
+                    "alternate_scroll": false,
+                    "origin_mode": false
+                },
+                "attrs": {
+                    "bold": false,
+                    "dim": false,
+                    "italic": false,
+                    "underline": true,
+                    "double_underline": false,
+                    "strikethrough": false,
+                    "blink": false,
+                    "reverse": false
+                },
+                "fg": "Yellow",
+                "bg": "DefaultBg",
+                "title": "golden",
+                "current_directory": null,
+                "lines": ["warn ok", "abcdefghij", "", ""]
+            })
+        );
+    }
+    
+    #[test]
+    fn test_repeat_last_character() {
+        let mut parser = TerminalParser::new(3, 10);
+        parser.parse(b"A\x1b[3b");
+        assert_eq!(screen_text(&parser)[0], "AAAA\0\0\0\0\0\0");
+        assert_eq!(parser.grid().cursor_position(), (0, 4));
+        
+        // A zero or missing count repeats once
+        parser.parse(b"\x1b[0b\x1b[b");
+        assert_eq!(parser.grid().cursor_position(), (0, 6));
+        
+        // Control characters are never repeated
+        parser.parse(b"\r\n\x1b[5b");
+        assert_eq!(parser.grid().cursor_position(), (1, 0));
+        assert_eq!(parser.grid().to_string(), "AAAAAA\n\n");
+    }
+    
+    #[test]
+    fn test_events_in_emission_order() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b]2;build\x07make\x07\x1b[18t\x1b]7;file://host/src/app\x1b\\");
+        parser.parse(b"\x1b]52;c;aGVsbG8=\x07\x1b]9;done\x07\x1b]2;build\x07\x1b]0;idle\x07");
+        
+        assert_eq!(parser.drain_events(), [
+            TerminalEvent::TitleChanged("build".to_string()),
+            TerminalEvent::Bell,
+            TerminalEvent::Response(b"\x1b[8;24;80t".to_vec()),
+            TerminalEvent::CwdChanged(PathBuf::from("/src/app")),
+            TerminalEvent::Clipboard { selection: "c".to_string(), text: "hello".to_string() },
+            TerminalEvent::Notify { title: None, body: "done".to_string() },
+            // Setting the same title again is not a change
+            TerminalEvent::TitleChanged("idle".to_string()),
+        ]);
+        assert!(parser.drain_events().is_empty());
+        
+        // Popping a saved title reports the restored one
+        parser.parse(b"\x1b[22t\x1b]2;vim\x07\x1b[23t");
+        assert_eq!(parser.drain_events(), [
+            TerminalEvent::TitleChanged("vim".to_string()),
+            TerminalEvent::TitleChanged("idle".to_string()),
+        ]);
+    }
+    
+    #[test]
+    fn test_osc52_clipboard() {
+        let mut parser = TerminalParser::new(24, 80);
+        parser.parse(b"\x1b]52;pc;Y29weSBtZQ==\x1b\\");
+        assert_eq!(parser.drain_events(), [TerminalEvent::Clipboard {
+            selection: "pc".to_string(),
+            text: "copy me".to_string(),
+        }]);
+        
+        // Queries, bad base64 and a missing payload are ignored
+        parser.parse(b"\x1b]52;c;?\x07\x1b]52;c;not base64!\x07\x1b]52;c\x07");
+        assert!(parser.drain_events().is_empty());
+    }
 }
@@ -0,0 +1,56 @@
+// Text selection made with the mouse, in grid coordinates
+
+/// A stream selection between the cell where the drag started and the cell
+/// under the pointer, both included, running through whole rows in between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    anchor: (u16, u16),
+    head: (u16, u16),
+}
+
+impl Selection {
+    /// A selection of the single cell at `(row, col)`
+    pub fn new(row: u16, col: u16) -> Self {
+        Self {
+            anchor: (row, col),
+            head: (row, col),
+        }
+    }
+    
+    /// Move the free end to `(row, col)`; the anchor stays put
+    pub fn extend_to(&mut self, row: u16, col: u16) {
+        self.head = (row, col);
+    }
+    
+    /// `(start, end)` in reading order, whichever way the drag went
+    pub fn ordered(&self) -> ((u16, u16), (u16, u16)) {
+        if self.head < self.anchor {
+            (self.head, self.anchor)
+        } else {
+            (self.anchor, self.head)
+        }
+    }
+    
+    pub fn contains(&self, row: u16, col: u16) -> bool {
+        let (start, end) = self.ordered();
+        (start..=end).contains(&(row, col))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_backwards_drag_is_ordered() {
+        let mut selection = Selection::new(3, 5);
+        selection.extend_to(1, 8);
+        assert_eq!(selection.ordered(), ((1, 8), (3, 5)));
+        
+        assert!(selection.contains(1, 8));
+        assert!(selection.contains(2, 0));
+        assert!(selection.contains(3, 5));
+        assert!(!selection.contains(1, 7));
+        assert!(!selection.contains(3, 6));
+    }
+}
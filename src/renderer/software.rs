@@ -1,7 +1,12 @@
-use fontdue::{Font, FontSettings};
+use std::path::PathBuf;
+
+use fontdue::{Font, FontSettings, LineMetrics};
 use winit::dpi::PhysicalSize;
 
-use crate::renderer::{TextGrid, RenderError};
+use crate::renderer::bell::bell_tint;
+use crate::renderer::fallback::{box_drawing_bitmap, load_fonts, FontChain};
+use crate::renderer::font::{FontSizeLimits, DEFAULT_FONT_SIZE};
+use crate::renderer::{TextGrid, CursorShape, CursorState, LineAttr, Palette, Rgba, RenderError, CellGeometry, Renderer, TerminalColor};
 
 /// Represents a rectangular cell in the terminal grid
 #[derive(Debug, Clone, Copy)]
@@ -13,8 +18,10 @@ struct CellRect {
 }
 
 pub struct SoftwareRenderer {
-    font: Font,
+    // Primary font and the fallbacks for glyphs it lacks
+    fonts: FontChain,
     font_size: f32,
+    font_size_limits: FontSizeLimits,
     char_width: u32,
     char_height: u32,
     size: PhysicalSize<u32>,
@@ -28,10 +35,24 @@ pub struct SoftwareRenderer {
     baseline_offset: u32,
     ascent: f32,
     descent: f32,
+    palette: Palette,
+    // Packed base colors from the palette
+    default_fg: u32,
+    default_bg: u32,
+    cursor_color: u32,
+    // Whether a blinking cursor is in its visible phase
+    cursor_blink_on: bool,
+    // Visual bell strength; 0.0 when no flash is showing
+    bell_flash: f32,
 }
 
 impl SoftwareRenderer {
     pub fn new(size: PhysicalSize<u32>) -> Result<Self, RenderError> {
+        Self::with_palette(size, Palette::default())
+    }
+    
+    /// Create a renderer whose base screen uses the palette's default colors
+    pub fn with_palette(size: PhysicalSize<u32>, palette: Palette) -> Result<Self, RenderError> {
         tracing::info!("🖥️  Initializing software renderer");
         
         // Load system font
@@ -45,57 +66,43 @@ impl SoftwareRenderer {
             
         tracing::info!("✅ Font parsed successfully");
         
-        // Calculate character dimensions using proper metrics
-        let font_size = 16.0;
-        let (metrics, _) = font.rasterize('M', font_size); // Use 'M' for measuring
-        let line_metrics = font.horizontal_line_metrics(font_size).unwrap();
-        
-        // Store font metrics for consistent baseline positioning
-        let ascent = line_metrics.ascent;
-        let descent = line_metrics.descent;
-        
-        // Calculate ideal cell dimensions (make them slightly larger than character bounds)
-        let char_width = metrics.advance_width.ceil() as u32;
-        let char_height = (ascent - descent + line_metrics.line_gap).ceil() as u32;
-        
-        // Make cells uniform rectangles with some padding
-        let cell_width = char_width.max(12); // Minimum cell width
-        let cell_height = char_height.max(20); // Minimum cell height
-        
-        // Calculate grid dimensions based on window size
-        let padding = 8; // 8px padding around the entire grid
-        let usable_width = size.width.saturating_sub(padding * 2);
-        let usable_height = size.height.saturating_sub(padding * 2);
-        
-        let grid_cols = usable_width / cell_width;
-        let grid_rows = usable_height / cell_height;
-        
-        // Calculate baseline offset within each cell (where characters sit)
-        let baseline_offset = (cell_height as f32 * 0.8) as u32;
-        
-        tracing::info!("📊 Font metrics - advance_width: {}, ascent: {}, descent: {}, line_gap: {}", 
-                      metrics.advance_width, ascent, descent, line_metrics.line_gap);
-        
-        tracing::info!("🔤 Character dimensions: {}x{}", char_width, char_height);
-        tracing::info!("📐 Cell dimensions: {}x{}", cell_width, cell_height);
+        Self::with_font(size, font, palette)
+    }
+    
+    /// Create a renderer drawing with `font` instead of a system font
+    pub fn with_font(size: PhysicalSize<u32>, font: Font, palette: Palette) -> Result<Self, RenderError> {
+        let font_size = DEFAULT_FONT_SIZE;
+        let metrics = CellMetrics::measure(&font, font_size)?;
+        let (grid_cols, grid_rows) = grid_dimensions(size, metrics.cell_width, metrics.cell_height);
+        
+        tracing::info!("🔤 Character dimensions: {}x{}", metrics.char_width, metrics.char_height);
+        tracing::info!("📐 Cell dimensions: {}x{}", metrics.cell_width, metrics.cell_height);
         tracing::info!("📋 Grid dimensions: {}x{} cells", grid_cols, grid_rows);
         
-        let pixel_buffer = vec![0xFF000000u32; (size.width * size.height) as usize]; // Black background
+        let default_bg = palette.default_bg.to_u32();
+        let pixel_buffer = blank_frame(size, default_bg);
         
         Ok(Self {
-            font,
+            fonts: FontChain::new(font),
             font_size,
-            char_width,
-            char_height,
+            font_size_limits: FontSizeLimits::default(),
+            char_width: metrics.char_width,
+            char_height: metrics.char_height,
             size,
             pixel_buffer,
             grid_cols,
             grid_rows,
-            cell_width,
-            cell_height,
-            baseline_offset,
-            ascent,
-            descent,
+            cell_width: metrics.cell_width,
+            cell_height: metrics.cell_height,
+            baseline_offset: metrics.baseline_offset,
+            ascent: metrics.ascent,
+            descent: metrics.descent,
+            default_fg: palette.default_fg.to_u32(),
+            default_bg,
+            cursor_color: palette.cursor.to_u32(),
+            cursor_blink_on: true,
+            bell_flash: 0.0,
+            palette,
         })
     }
     
@@ -124,8 +131,8 @@ impl SoftwareRenderer {
     }
     
     pub fn render_frame(&mut self, grid: &TextGrid) -> Result<&[u32], RenderError> {
-        // Clear buffer to black
-        self.pixel_buffer.fill(0xFF000000u32);
+        // Clear to the theme background
+        self.pixel_buffer.fill(self.default_bg);
         
         tracing::debug!("🖥️  Software rendering frame {}x{}", self.size.width, self.size.height);
         
@@ -146,20 +153,39 @@ impl SoftwareRenderer {
         tracing::debug!("📐 Grid render area: {}x{} cells, cell_size={}x{}", 
                        max_rows, max_cols, self.cell_width, self.cell_height);
         
+        // Scrolled up, the top rows come from scrollback
         for row in 0..max_rows {
-            if let Some(row_data) = grid.row(row) {
-                for col in 0..(row_data.len().min(max_cols as usize)) {
-                    if let Some(cell) = grid.cell_at(row, col as u16) {
+            let line_attr = grid.viewport_line_attr(row);
+            // Double-width rows only fit half as many cells
+            let row_cols = if line_attr.is_double_width() { max_cols / 2 } else { max_cols };
+            
+            if let Some(row_data) = grid.viewport_row(row) {
+                for col in 0..(row_data.len().min(row_cols as usize)) {
+                    if let Some(cell) = row_data.get(col) {
+                        // Calculate the exact cell rectangle
+                        let cell_rect = self.get_cell_rect(row as u32, col as u32, grid_start_x, grid_start_y, line_attr);
+                        
+                        // Plain default-colored text uses the theme foreground
+                        let fg = if cell.fg_color == TerminalColor::DefaultFg && !cell.attrs.reverse && !cell.attrs.dim {
+                            self.default_fg
+                        } else {
+                            cell.resolved_colors(&self.palette).0.to_u32()
+                        };
+                        
+                        // Underlines run under blanks too, in the SGR 58 color if one is set
+                        if cell.attrs.underline || cell.attrs.double_underline {
+                            let color = match cell.attrs.underline_color {
+                                Some(_) => cell.resolved_underline_color(&self.palette).to_u32(),
+                                None => fg,
+                            };
+                            for line in underline_rects(cell_rect, cell.attrs.double_underline) {
+                                self.fill_rect(line, color);
+                            }
+                        }
+                        
                         if cell.ch != '\0' && cell.ch != ' ' {
-                            // Calculate the exact cell rectangle
-                            let cell_rect = self.get_cell_rect(row as u32, col as u32, grid_start_x, grid_start_y);
-                            
                             // Render character centered within its cell
-                            self.render_char_in_cell(
-                                cell.ch,
-                                cell_rect,
-                                0xFFFFFFFFu32, // White text
-                            );
+                            self.render_char_in_cell(cell.ch, cell_rect, fg, line_attr);
                             chars_rendered += 1;
                         }
                     }
@@ -167,6 +193,18 @@ impl SoftwareRenderer {
             }
         }
         
+        let cursor = grid.cursor();
+        if cursor.visible && (self.cursor_blink_on || !cursor.blinking) {
+            // Not drawn while scrolled away from its row
+            if let Some(row) = grid.viewport_cursor_row() {
+                self.draw_cursor(grid, CursorState { row, ..cursor }, grid_start_x, grid_start_y);
+            }
+        }
+        
+        if self.bell_flash > 0.0 {
+            tint_frame(&mut self.pixel_buffer, self.palette.default_fg, self.bell_flash);
+        }
+        
         if chars_rendered > 0 {
             tracing::debug!("🔤 Software rendered {} characters in grid cells", chars_rendered);
         } else {
@@ -177,15 +215,32 @@ impl SoftwareRenderer {
     }
     
     /// Calculate the exact rectangle for a grid cell
-    fn get_cell_rect(&self, row: u32, col: u32, grid_start_x: u32, grid_start_y: u32) -> CellRect {
-        let x = grid_start_x + col * self.cell_width;
-        let y = grid_start_y + row * self.cell_height;
-        
-        CellRect {
-            x,
-            y,
-            width: self.cell_width,
-            height: self.cell_height,
+    fn get_cell_rect(&self, row: u32, col: u32, grid_start_x: u32, grid_start_y: u32, line_attr: LineAttr) -> CellRect {
+        cell_rect(row, col, self.cell_width, self.cell_height, grid_start_x, grid_start_y, line_attr)
+    }
+    
+    /// Draw the cursor, at viewport row `cursor.row`, in its shape: a block
+    /// inverts the cell so the character under it stays readable, underline
+    /// and bar are solid
+    fn draw_cursor(&mut self, grid: &TextGrid, cursor: CursorState, grid_start_x: u32, grid_start_y: u32) {
+        if cursor.row as u32 >= self.grid_rows || cursor.col as u32 >= self.grid_cols {
+            return;
+        }
+        
+        let rect = self.get_cell_rect(cursor.row as u32, cursor.col as u32, grid_start_x, grid_start_y, grid.viewport_line_attr(cursor.row));
+        let (x_range, y_range) = match cursor.shape {
+            CursorShape::Block => (rect.x..rect.x + rect.width, rect.y..rect.y + rect.height),
+            CursorShape::Underline => (rect.x..rect.x + rect.width, rect.y + rect.height - 2.min(rect.height)..rect.y + rect.height),
+            CursorShape::Bar => (rect.x..rect.x + 2.min(rect.width), rect.y..rect.y + rect.height),
+        };
+        for y in y_range.start..y_range.end.min(self.size.height) {
+            for x in x_range.start..x_range.end.min(self.size.width) {
+                let pixel = &mut self.pixel_buffer[(y * self.size.width + x) as usize];
+                *pixel = match cursor.shape {
+                    CursorShape::Block => !*pixel | 0xFF000000,
+                    _ => self.cursor_color,
+                };
+            }
         }
     }
     
@@ -223,20 +278,35 @@ impl SoftwareRenderer {
     }
     
     /// Render a character within a specific cell rectangle
-    fn render_char_in_cell(&mut self, ch: char, cell_rect: CellRect, color: u32) {
-        let (metrics, bitmap) = self.font.rasterize(ch, self.font_size);
+    fn render_char_in_cell(&mut self, ch: char, cell_rect: CellRect, color: u32, line_attr: LineAttr) {
+        // Box drawing no font covers is drawn as lines filling the cell
+        if self.fonts.font_for(ch).is_none() {
+            if let Some(bitmap) = box_drawing_bitmap(ch, cell_rect.width as usize, cell_rect.height as usize) {
+                self.fill_cell_bitmap(&bitmap, cell_rect, color);
+                return;
+            }
+        }
+        
+        let (metrics, bitmap) = self.fonts.rasterize(ch, self.font_size);
+        
+        // Double-size lines stretch the glyph; the cell rect is already widened
+        let scale_x = if line_attr.is_double_width() { 2 } else { 1 };
+        let scale_y = if line_attr.is_double_height() { 2 } else { 1 };
+        
+        // Double-height glyphs span two rows; the bottom row shows the lower half
+        let glyph_top = if line_attr == LineAttr::DoubleHeightBottom {
+            cell_rect.y as i64 - cell_rect.height as i64
+        } else {
+            cell_rect.y as i64
+        };
         
         // Calculate character position within the cell
         // Center horizontally, align to baseline vertically
-        let char_x = cell_rect.x + (cell_rect.width.saturating_sub(metrics.width as u32)) / 2;
-        let char_y = cell_rect.y + self.baseline_offset;
+        let char_x = cell_rect.x + (cell_rect.width.saturating_sub(metrics.width as u32 * scale_x)) / 2;
+        let char_y = glyph_top + (self.baseline_offset * scale_y) as i64;
         
         // Adjust for font metrics (handle negative ymin safely)
-        let final_char_y = if metrics.ymin < 0 {
-            char_y + (-metrics.ymin) as u32
-        } else {
-            char_y.saturating_sub(metrics.ymin as u32)
-        };
+        let final_char_y = char_y - (metrics.ymin as i64 * scale_y as i64);
         
         // Debug logging for first few characters
         if ch == 'T' || ch == 'H' {
@@ -248,38 +318,29 @@ impl SoftwareRenderer {
         // Draw character bitmap within the cell bounds
         for bitmap_y in 0..metrics.height {
             for bitmap_x in 0..metrics.width {
-                let pixel_x = char_x + bitmap_x as u32;
-                let pixel_y = final_char_y + bitmap_y as u32;
+                let bitmap_idx = bitmap_y * metrics.width + bitmap_x;
+                if bitmap_idx >= bitmap.len() {
+                    continue;
+                }
+                let alpha = bitmap[bitmap_idx];
+                if alpha <= 64 { // Antialiasing threshold
+                    continue;
+                }
                 
-                // Ensure we stay within cell boundaries and screen bounds
-                if pixel_x >= cell_rect.x && pixel_x < cell_rect.x + cell_rect.width &&
-                   pixel_y >= cell_rect.y && pixel_y < cell_rect.y + cell_rect.height &&
-                   pixel_x < self.size.width && pixel_y < self.size.height {
-                    
-                    let bitmap_idx = bitmap_y * metrics.width + bitmap_x;
-                    if bitmap_idx < bitmap.len() {
-                        let alpha = bitmap[bitmap_idx];
-                        if alpha > 64 { // Antialiasing threshold
-                            let buffer_idx = (pixel_y * self.size.width + pixel_x) as usize;
-                            if buffer_idx < self.pixel_buffer.len() {
-                                // High-quality alpha blending
-                                let alpha_f = alpha as f32 / 255.0;
-                                let existing = self.pixel_buffer[buffer_idx];
-                                
-                                let existing_r = ((existing >> 16) & 0xFF) as f32;
-                                let existing_g = ((existing >> 8) & 0xFF) as f32;
-                                let existing_b = (existing & 0xFF) as f32;
-                                
-                                let new_r = ((color >> 16) & 0xFF) as f32;
-                                let new_g = ((color >> 8) & 0xFF) as f32;
-                                let new_b = (color & 0xFF) as f32;
-                                
-                                let blended_r = (existing_r * (1.0 - alpha_f) + new_r * alpha_f).clamp(0.0, 255.0) as u32;
-                                let blended_g = (existing_g * (1.0 - alpha_f) + new_g * alpha_f).clamp(0.0, 255.0) as u32;
-                                let blended_b = (existing_b * (1.0 - alpha_f) + new_b * alpha_f).clamp(0.0, 255.0) as u32;
-                                
-                                self.pixel_buffer[buffer_idx] = 0xFF000000 | (blended_r << 16) | (blended_g << 8) | blended_b;
-                            }
+                for dy in 0..scale_y {
+                    for dx in 0..scale_x {
+                        let pixel_x = char_x + bitmap_x as u32 * scale_x + dx;
+                        let pixel_y = final_char_y + (bitmap_y as u32 * scale_y + dy) as i64;
+                        if pixel_y < 0 {
+                            continue;
+                        }
+                        let pixel_y = pixel_y as u32;
+                        
+                        // Ensure we stay within cell boundaries and screen bounds
+                        if pixel_x >= cell_rect.x && pixel_x < cell_rect.x + cell_rect.width &&
+                           pixel_y >= cell_rect.y && pixel_y < cell_rect.y + cell_rect.height &&
+                           pixel_x < self.size.width && pixel_y < self.size.height {
+                            self.blend_pixel(pixel_x, pixel_y, color, alpha);
                         }
                     }
                 }
@@ -287,18 +348,54 @@ impl SoftwareRenderer {
         }
     }
     
+    /// Fill `rect` with a solid color, clipped to the frame
+    fn fill_rect(&mut self, rect: CellRect, color: u32) {
+        for y in rect.y..(rect.y + rect.height).min(self.size.height) {
+            for x in rect.x..(rect.x + rect.width).min(self.size.width) {
+                self.pixel_buffer[(y * self.size.width + x) as usize] = color;
+            }
+        }
+    }
+    
+    /// Blend a bitmap covering exactly `cell_rect` onto the frame
+    fn fill_cell_bitmap(&mut self, bitmap: &[u8], cell_rect: CellRect, color: u32) {
+        for (idx, &alpha) in bitmap.iter().enumerate() {
+            let x = cell_rect.x + idx as u32 % cell_rect.width;
+            let y = cell_rect.y + idx as u32 / cell_rect.width;
+            if alpha > 0 && x < self.size.width && y < self.size.height {
+                self.blend_pixel(x, y, color, alpha);
+            }
+        }
+    }
+    
+    /// Alpha-blend `color` onto the pixel at (x, y)
+    fn blend_pixel(&mut self, x: u32, y: u32, color: u32, alpha: u8) {
+        let buffer_idx = (y * self.size.width + x) as usize;
+        if buffer_idx < self.pixel_buffer.len() {
+            // High-quality alpha blending
+            let alpha_f = alpha as f32 / 255.0;
+            let existing = Rgba::from_u32(self.pixel_buffer[buffer_idx]);
+            let new = Rgba::from_u32(color);
+            
+            let blend = |old: u8, new: u8| {
+                (old as f32 * (1.0 - alpha_f) + new as f32 * alpha_f).clamp(0.0, 255.0) as u8
+            };
+            
+            self.pixel_buffer[buffer_idx] = Rgba::rgb(
+                blend(existing.r, new.r),
+                blend(existing.g, new.g),
+                blend(existing.b, new.b),
+            ).to_u32();
+        }
+    }
+    
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) -> Result<(), RenderError> {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
-            self.pixel_buffer = vec![0xFF000000u32; (new_size.width * new_size.height) as usize];
+            self.pixel_buffer = blank_frame(new_size, self.default_bg);
             
             // Recalculate grid dimensions for new window size
-            let padding = 8;
-            let usable_width = new_size.width.saturating_sub(padding * 2);
-            let usable_height = new_size.height.saturating_sub(padding * 2);
-            
-            self.grid_cols = usable_width / self.cell_width;
-            self.grid_rows = usable_height / self.cell_height;
+            (self.grid_cols, self.grid_rows) = grid_dimensions(new_size, self.cell_width, self.cell_height);
             
             tracing::info!("📏 Software renderer resized to {}x{}", new_size.width, new_size.height);
             tracing::info!("📋 New grid dimensions: {}x{} cells", self.grid_cols, self.grid_rows);
@@ -306,6 +403,86 @@ impl SoftwareRenderer {
         Ok(())
     }
     
+    /// Cell layout inside the window, including the 8px padding
+    pub fn geometry(&self) -> CellGeometry {
+        let padding = 8;
+        CellGeometry {
+            origin_x: padding,
+            origin_y: padding,
+            cell_width: self.cell_width,
+            cell_height: self.cell_height,
+            rows: self.grid_rows,
+            cols: self.grid_cols,
+        }
+    }
+    
+    /// Grid `(row, col)` under a window pixel, `None` in the padding or outside the grid
+    pub fn cell_at_pixel(&self, x: u32, y: u32) -> Option<(u16, u16)> {
+        self.geometry().cell_at_pixel(x, y)
+    }
+    
+    /// Set the blink phase; only affects grids whose cursor blinks
+    pub fn set_cursor_blink_phase(&mut self, on: bool) {
+        self.cursor_blink_on = on;
+    }
+    
+    pub fn set_bell_flash(&mut self, intensity: f32) {
+        self.bell_flash = intensity.clamp(0.0, 1.0);
+    }
+    
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.default_fg = palette.default_fg.to_u32();
+        self.default_bg = palette.default_bg.to_u32();
+        self.cursor_color = palette.cursor.to_u32();
+        self.palette = palette;
+    }
+    
+    /// Load the fonts at `paths` as fallbacks, tried in order for glyphs
+    /// the primary font lacks
+    pub fn set_fallback_fonts(&mut self, paths: &[PathBuf]) -> Result<(), RenderError> {
+        self.fonts.set_fallbacks(load_fonts(paths)?);
+        tracing::info!("🔤 {} fallback font(s) loaded", paths.len());
+        Ok(())
+    }
+    
+    /// Change the font size, clamped to the renderer's limits, and re-measure
+    /// the cells and grid. Returns the size actually applied.
+    pub fn set_font_size(&mut self, font_size: f32) -> Result<f32, RenderError> {
+        let font_size = self.font_size_limits.clamp(font_size);
+        if font_size == self.font_size {
+            return Ok(font_size);
+        }
+        
+        let metrics = CellMetrics::measure(self.fonts.primary(), font_size)?;
+        self.apply_metrics(font_size, metrics);
+        
+        tracing::info!("🔍 Font size {} ({}x{} cells, grid {}x{})",
+                      font_size, self.cell_width, self.cell_height, self.grid_cols, self.grid_rows);
+        Ok(font_size)
+    }
+    
+    fn apply_metrics(&mut self, font_size: f32, metrics: CellMetrics) {
+        self.font_size = font_size;
+        self.char_width = metrics.char_width;
+        self.char_height = metrics.char_height;
+        self.cell_width = metrics.cell_width;
+        self.cell_height = metrics.cell_height;
+        self.baseline_offset = metrics.baseline_offset;
+        self.ascent = metrics.ascent;
+        self.descent = metrics.descent;
+        (self.grid_cols, self.grid_rows) = grid_dimensions(self.size, self.cell_width, self.cell_height);
+    }
+    
+    pub fn font_size(&self) -> f32 {
+        self.font_size
+    }
+    
+    /// Limit later `set_font_size` calls to `limits`; the current size is clamped right away
+    pub fn set_font_size_limits(&mut self, limits: FontSizeLimits) -> Result<f32, RenderError> {
+        self.font_size_limits = limits;
+        self.set_font_size(self.font_size)
+    }
+    
     pub fn char_width(&self) -> u32 {
         self.char_width
     }
@@ -317,4 +494,336 @@ impl SoftwareRenderer {
     pub fn size(&self) -> PhysicalSize<u32> {
         self.size
     }
+    
+    /// The last rendered frame, one packed ARGB pixel per entry
+    pub fn pixel_buffer(&self) -> &[u32] {
+        &self.pixel_buffer
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn render_frame(&mut self, grid: &TextGrid) -> Result<(), RenderError> {
+        SoftwareRenderer::render_frame(self, grid).map(|_| ())
+    }
+    
+    fn resize(&mut self, size: PhysicalSize<u32>) -> Result<(), RenderError> {
+        SoftwareRenderer::resize(self, size)
+    }
+    
+    fn cell_size(&self) -> (u32, u32) {
+        (self.char_width, self.char_height)
+    }
+    
+    fn geometry(&self) -> CellGeometry {
+        SoftwareRenderer::geometry(self)
+    }
+    
+    fn font_size(&self) -> f32 {
+        self.font_size
+    }
+    
+    fn set_font_size(&mut self, font_size: f32) -> Result<f32, RenderError> {
+        SoftwareRenderer::set_font_size(self, font_size)
+    }
+    
+    fn set_font_size_limits(&mut self, limits: FontSizeLimits) -> Result<f32, RenderError> {
+        SoftwareRenderer::set_font_size_limits(self, limits)
+    }
+    
+    fn set_fallback_fonts(&mut self, paths: &[PathBuf]) -> Result<(), RenderError> {
+        SoftwareRenderer::set_fallback_fonts(self, paths)
+    }
+    
+    fn set_bell_flash(&mut self, intensity: f32) {
+        SoftwareRenderer::set_bell_flash(self, intensity)
+    }
+}
+
+/// Character and cell dimensions for one font size
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CellMetrics {
+    char_width: u32,
+    char_height: u32,
+    cell_width: u32,
+    cell_height: u32,
+    // Where characters sit within a cell
+    baseline_offset: u32,
+    ascent: f32,
+    descent: f32,
+}
+
+impl CellMetrics {
+    /// Measure `font` at `font_size`, using 'M' for the character width
+    fn measure(font: &Font, font_size: f32) -> Result<Self, RenderError> {
+        let (metrics, _) = font.rasterize('M', font_size);
+        let line_metrics = font.horizontal_line_metrics(font_size)
+            .ok_or_else(|| RenderError::Font("Font has no horizontal line metrics".to_string()))?;
+        
+        tracing::debug!("📊 Font metrics at {} - advance_width: {}, ascent: {}, descent: {}, line_gap: {}",
+                       font_size, metrics.advance_width, line_metrics.ascent, line_metrics.descent, line_metrics.line_gap);
+        
+        Ok(Self::from_font_metrics(font_size, metrics.advance_width, &line_metrics))
+    }
+    
+    fn from_font_metrics(font_size: f32, advance_width: f32, line_metrics: &LineMetrics) -> Self {
+        let char_width = advance_width.ceil() as u32;
+        let char_height = (line_metrics.ascent - line_metrics.descent + line_metrics.line_gap).ceil() as u32;
+        
+        // Make cells uniform rectangles with some padding; the minimum cell
+        // size scales with the font so zooming out still shrinks the cells
+        let scale = font_size / DEFAULT_FONT_SIZE;
+        let cell_width = char_width.max((12.0 * scale).round() as u32).max(1);
+        let cell_height = char_height.max((20.0 * scale).round() as u32).max(1);
+        
+        Self {
+            char_width,
+            char_height,
+            cell_width,
+            cell_height,
+            baseline_offset: (cell_height as f32 * 0.8) as u32,
+            ascent: line_metrics.ascent,
+            descent: line_metrics.descent,
+        }
+    }
+}
+
+/// Grid `(cols, rows)` that fits in a window of `size` inside the 8px padding
+fn grid_dimensions(size: PhysicalSize<u32>, cell_width: u32, cell_height: u32) -> (u32, u32) {
+    let padding = 8;
+    let usable_width = size.width.saturating_sub(padding * 2);
+    let usable_height = size.height.saturating_sub(padding * 2);
+    (usable_width / cell_width, usable_height / cell_height)
+}
+
+/// A frame of `size` filled with the background color
+fn blank_frame(size: PhysicalSize<u32>, background: u32) -> Vec<u32> {
+    vec![background; (size.width * size.height) as usize]
+}
+
+/// Tint every pixel of a finished frame towards `flash` for the visual bell
+fn tint_frame(pixels: &mut [u32], flash: Rgba, intensity: f32) {
+    for pixel in pixels {
+        *pixel = bell_tint(Rgba::from_u32(*pixel), flash, intensity).to_u32();
+    }
+}
+
+/// Rectangle occupied by a grid cell; cells on double-width rows are twice as wide
+fn cell_rect(
+    row: u32,
+    col: u32,
+    cell_width: u32,
+    cell_height: u32,
+    grid_start_x: u32,
+    grid_start_y: u32,
+    line_attr: LineAttr,
+) -> CellRect {
+    let width = if line_attr.is_double_width() { cell_width * 2 } else { cell_width };
+    
+    CellRect {
+        x: grid_start_x + col * width,
+        y: grid_start_y + row * cell_height,
+        width,
+        height: cell_height,
+    }
+}
+
+/// The lines of a cell's underline, at the bottom of `cell`: one line, or
+/// two a pixel apart for a double underline
+fn underline_rects(cell: CellRect, double: bool) -> Vec<CellRect> {
+    let bottom = cell.y + cell.height;
+    let line = |offset: u32| CellRect {
+        x: cell.x,
+        y: bottom.saturating_sub(offset + 1).max(cell.y),
+        width: cell.width,
+        height: 1,
+    };
+    if double && cell.height >= 3 {
+        vec![line(3), line(1)]
+    } else {
+        vec![line(1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{Cell, CellAttributes};
+    
+    /// A renderer over a system font, or a common Linux one; `None` on
+    /// machines with neither
+    fn test_renderer(size: PhysicalSize<u32>, palette: Palette) -> Option<SoftwareRenderer> {
+        let data = SoftwareRenderer::load_system_font().ok().flatten().or_else(|| {
+            ["/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf", "/usr/share/fonts/TTF/DejaVuSansMono.ttf"]
+                .iter()
+                .find_map(|path| std::fs::read(path).ok())
+        })?;
+        let font = Font::from_bytes(data, FontSettings::default()).ok()?;
+        SoftwareRenderer::with_font(size, font, palette).ok()
+    }
+    
+    /// The pixels of `rect` in a `width`-pixel-wide frame
+    fn pixels_in(frame: &[u32], width: u32, rect: CellRect) -> Vec<u32> {
+        (rect.y..rect.y + rect.height)
+            .flat_map(|y| (rect.x..rect.x + rect.width).map(move |x| frame[(y * width + x) as usize]))
+            .collect()
+    }
+    
+    #[test]
+    fn test_render_uses_palette_text_and_cursor_colors() {
+        let palette = Palette {
+            default_fg: Rgba::rgb(0x12, 0xEE, 0x34),
+            default_bg: Rgba::rgb(0x20, 0x10, 0x30),
+            cursor: Rgba::rgb(0xF0, 0x80, 0x10),
+            ..Palette::default()
+        };
+        let size = PhysicalSize::new(240, 120);
+        let Some(mut renderer) = test_renderer(size, palette.clone()) else {
+            eprintln!("skipping: no font to render with");
+            return;
+        };
+        
+        let mut grid = TextGrid::new(3, 10);
+        grid.set_fg_color(TerminalColor::DefaultFg);
+        grid.write_char('H');
+        grid.set_cursor(1, 2);
+        grid.set_cursor_shape(CursorShape::Bar);
+        let frame = renderer.render_frame(&grid).unwrap().to_vec();
+        
+        // Default-colored text is drawn in the theme foreground
+        let glyph = pixels_in(&frame, size.width, renderer.get_cell_rect(0, 0, 8, 8, LineAttr::Normal));
+        assert!(glyph.contains(&palette.default_fg.to_u32()));
+        assert!(!glyph.contains(&Palette::default().default_fg.to_u32()));
+        
+        // A bar cursor is a solid stripe of the cursor color on the background
+        let cursor = renderer.get_cell_rect(1, 2, 8, 8, LineAttr::Normal);
+        let bar = CellRect { width: 2, ..cursor };
+        assert!(pixels_in(&frame, size.width, bar).iter().all(|&pixel| pixel == palette.cursor.to_u32()));
+        let beside = CellRect { x: cursor.x + 2, width: cursor.width - 2, ..cursor };
+        assert!(pixels_in(&frame, size.width, beside).iter().all(|&pixel| pixel == palette.default_bg.to_u32()));
+    }
+    
+    #[test]
+    fn test_scrolled_viewport_renders_scrollback() {
+        let size = PhysicalSize::new(240, 120);
+        let Some(mut renderer) = test_renderer(size, Palette::default()) else {
+            eprintln!("skipping: no font to render with");
+            return;
+        };
+        let mut grid = TextGrid::new(3, 10);
+        grid.write_str("A\r\nB\r\nC\r\nD");
+        // The inside of cell (row, 0), clear of the debug grid lines
+        let cell = |renderer: &SoftwareRenderer, frame: &[u32], row| {
+            let rect = renderer.get_cell_rect(row, 0, 8, 8, LineAttr::Normal);
+            pixels_in(frame, size.width, CellRect { x: rect.x + 1, y: rect.y + 1, width: rect.width - 1, height: rect.height - 1 })
+        };
+        
+        let live = renderer.render_frame(&grid).unwrap().to_vec();
+        grid.scroll_viewport_up(1);
+        let scrolled = renderer.render_frame(&grid).unwrap().to_vec();
+        
+        // `A` comes back from scrollback on top, and `B` moves down a row
+        assert_ne!(cell(&renderer, &scrolled, 0), cell(&renderer, &live, 0));
+        assert_eq!(cell(&renderer, &scrolled, 1), cell(&renderer, &live, 0));
+        assert_eq!(cell(&renderer, &scrolled, 2), cell(&renderer, &live, 1));
+    }
+    
+    #[test]
+    fn test_blank_frame_uses_palette_background() {
+        let palette = Palette {
+            default_bg: Rgba::rgb(0x28, 0x2c, 0x34),
+            ..Palette::default()
+        };
+        let frame = blank_frame(PhysicalSize::new(4, 3), palette.default_bg.to_u32());
+        
+        assert_eq!(frame.len(), 12);
+        assert!(frame.iter().all(|&pixel| pixel == 0xFF282C34));
+    }
+    
+    #[test]
+    fn test_tint_frame_brightens_towards_flash_color() {
+        let background = Rgba::rgb(0x10, 0x10, 0x10).to_u32();
+        let mut frame = vec![background; 4];
+        tint_frame(&mut frame, Rgba::rgb(0xFF, 0xFF, 0xFF), 0.0);
+        assert!(frame.iter().all(|&pixel| pixel == background));
+        
+        tint_frame(&mut frame, Rgba::rgb(0xFF, 0xFF, 0xFF), 1.0);
+        let tinted = Rgba::from_u32(frame[0]);
+        assert!(tinted.r > 0x10 && tinted.r < 0xFF);
+        assert_eq!(tinted.a, 0xFF);
+    }
+    
+    #[test]
+    fn test_underline_uses_sgr_58_color() {
+        let palette = Palette::default();
+        let mut cell = Cell::empty();
+        cell.fg_color = TerminalColor::Green;
+        cell.attrs = CellAttributes { underline: true, ..Default::default() };
+        assert_eq!(cell.resolved_underline_color(&palette), palette.resolve(TerminalColor::Green));
+        
+        cell.attrs.underline_color = Some(TerminalColor::Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(cell.resolved_underline_color(&palette).to_u32(), 0xFFFF0000);
+        // Reverse video swaps the text colors, not the underline's
+        cell.attrs.reverse = true;
+        assert_eq!(cell.resolved_underline_color(&palette), Rgba::rgb(255, 0, 0));
+        
+        let rect = cell_rect(0, 2, 10, 20, 8, 8, LineAttr::Normal);
+        let single = underline_rects(rect, false);
+        assert_eq!(single.iter().map(|r| (r.x, r.y, r.width, r.height)).collect::<Vec<_>>(), [(28, 26, 10, 1)]);
+        let double = underline_rects(rect, true);
+        assert_eq!(double.iter().map(|r| r.y).collect::<Vec<_>>(), [24, 26]);
+        assert!(double.iter().all(|r| r.y >= rect.y && r.y < rect.y + rect.height));
+    }
+    
+    #[test]
+    fn test_double_width_cell_rect() {
+        let normal = cell_rect(1, 3, 10, 20, 8, 8, LineAttr::Normal);
+        assert_eq!((normal.x, normal.y, normal.width, normal.height), (38, 28, 10, 20));
+        
+        let wide = cell_rect(1, 3, 10, 20, 8, 8, LineAttr::DoubleWidth);
+        assert_eq!((wide.x, wide.y, wide.width, wide.height), (68, 28, 20, 20));
+        
+        // Double-height rows are also double width
+        let tall = cell_rect(0, 1, 10, 20, 8, 8, LineAttr::DoubleHeightTop);
+        assert_eq!((tall.x, tall.width), (28, 20));
+    }
+    
+    /// Line metrics for a font whose proportions match Menlo, at `font_size`
+    fn menlo_like(font_size: f32) -> (f32, LineMetrics) {
+        let ascent = font_size * 0.928;
+        let descent = font_size * -0.236;
+        let line_metrics = LineMetrics {
+            ascent,
+            descent,
+            line_gap: 0.0,
+            new_line_size: ascent - descent,
+        };
+        (font_size * 0.602, line_metrics)
+    }
+    
+    #[test]
+    fn test_font_size_changes_metrics_and_grid() {
+        let window = PhysicalSize::new(800, 600);
+        let at = |font_size: f32| {
+            let (advance, line_metrics) = menlo_like(font_size);
+            let metrics = CellMetrics::from_font_metrics(font_size, advance, &line_metrics);
+            let grid = grid_dimensions(window, metrics.cell_width, metrics.cell_height);
+            (metrics, grid)
+        };
+        
+        let (default, default_grid) = at(DEFAULT_FONT_SIZE);
+        assert_eq!((default.char_width, default.char_height), (10, 19));
+        assert_eq!((default.cell_width, default.cell_height), (12, 20));
+        assert_eq!(default_grid, (65, 29));
+        
+        let (zoomed_in, zoomed_in_grid) = at(24.0);
+        assert_eq!((zoomed_in.char_width, zoomed_in.char_height), (15, 28));
+        assert_eq!((zoomed_in.cell_width, zoomed_in.cell_height), (18, 30));
+        assert_eq!(zoomed_in_grid, (43, 19));
+        
+        // The minimum cell size scales down with the font
+        let (zoomed_out, zoomed_out_grid) = at(8.0);
+        assert_eq!((zoomed_out.char_width, zoomed_out.char_height), (5, 10));
+        assert_eq!((zoomed_out.cell_width, zoomed_out.cell_height), (6, 10));
+        assert_eq!(zoomed_out_grid, (130, 58));
+    }
 }
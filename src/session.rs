@@ -0,0 +1,155 @@
+// Headless command session - runs commands without a PTY or window
+// Useful for scripting, reproducibility and replaying stored blocks
+
+use std::env;
+use std::path::Path;
+use std::time::Instant;
+
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::blocks::{Block, BlockDetector, BlockStorage, BlockStore};
+use crate::error::{Result, TermindError};
+
+pub struct HeadlessSession<S = BlockStore> {
+    detector: BlockDetector<S>,
+    shell: String,
+}
+
+impl HeadlessSession {
+    pub async fn new() -> Result<Self> {
+        Ok(Self::with_detector(BlockDetector::new().await?))
+    }
+}
+
+impl<S: BlockStorage> HeadlessSession<S> {
+    /// A session recording its blocks through `detector`
+    pub fn with_detector(detector: BlockDetector<S>) -> Self {
+        let shell = env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        Self { detector, shell }
+    }
+
+    pub fn with_shell(mut self, shell: String) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Run a command through the shell in `cwd` and store the resulting block
+    pub async fn run(&mut self, command: &str, cwd: &Path) -> Result<Block> {
+        info!("Running headless command in {}: {}", cwd.display(), command);
+
+        self.detector.start_command(
+            command.to_string(),
+            cwd.to_string_lossy().to_string(),
+            self.shell.clone(),
+        );
+
+        let started = Instant::now();
+        let output = Command::new(&self.shell)
+            .arg("-c")
+            .arg(command)
+            .current_dir(cwd)
+            .output()
+            .await
+            .map_err(|e| TermindError::ShellExecution(format!("Failed to run '{}': {}", command, e)))?;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        self.detector.add_output(&String::from_utf8_lossy(&output.stdout), false);
+        self.detector.add_output(&String::from_utf8_lossy(&output.stderr), true);
+
+        // A missing exit code means the command was killed by a signal
+        let exit_code = output.status.code().unwrap_or(-1);
+
+        let finished = self.detector
+            .finish_command(exit_code, duration_ms)
+            .await?
+            .ok_or_else(|| TermindError::ShellExecution("No block was recorded for the command".to_string()))?;
+        if !finished.is_stored() {
+            warn!("Block {} for '{}' could not be stored", finished.block().id, command);
+        }
+        Ok(finished.into_block())
+    }
+
+    /// Re-run a stored block's command in its recorded directory, returning the new block
+    pub async fn replay(&mut self, block_id: &str) -> Result<Block> {
+        let original = self.detector
+            .get_block(block_id)
+            .await?
+            .ok_or_else(|| TermindError::BlockNotFound(block_id.to_string()))?;
+
+        let cwd = Path::new(&original.cwd);
+        if !cwd.is_dir() {
+            return Err(TermindError::ShellExecution(format!(
+                "Cannot replay block {}: recorded directory '{}' no longer exists",
+                block_id, original.cwd
+            )));
+        }
+
+        let replayed = self.run(&original.command, cwd).await?;
+
+        if original.exit_code.is_some() && original.exit_code != replayed.exit_code {
+            warn!(
+                "Replay of block {} exited with {:?}, original exited with {:?}",
+                block_id, replayed.exit_code, original.exit_code
+            );
+        }
+
+        Ok(replayed)
+    }
+
+    pub fn detector(&self) -> &BlockDetector<S> {
+        &self.detector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::MemoryBlockStore;
+
+    /// A session that keeps its blocks in memory, away from the user's history
+    fn memory_session() -> HeadlessSession<MemoryBlockStore> {
+        HeadlessSession::with_detector(BlockDetector::with_storage(MemoryBlockStore::new()))
+    }
+
+    #[tokio::test]
+    async fn test_replay_block() -> Result<()> {
+        let mut session = memory_session().with_shell("/bin/sh".to_string());
+        let cwd = env::temp_dir();
+
+        let original = session.run("echo replay-test", &cwd).await?;
+        assert_eq!(original.exit_code, Some(0));
+        assert_eq!(original.stdout, "replay-test\n");
+
+        let replayed = session.replay(&original.id).await?;
+        assert_ne!(replayed.id, original.id);
+        assert_eq!(replayed.command, original.command);
+        assert_eq!(replayed.cwd, original.cwd);
+        assert_eq!(replayed.exit_code, original.exit_code);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replay_missing_cwd() -> Result<()> {
+        let mut session = memory_session().with_shell("/bin/sh".to_string());
+        let missing = env::temp_dir().join(format!("termind-replay-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&missing)?;
+
+        let original = session.run("true", &missing).await?;
+        std::fs::remove_dir(&missing)?;
+
+        let result = session.replay(&original.id).await;
+        assert!(matches!(result, Err(TermindError::ShellExecution(msg)) if msg.contains("no longer exists")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replay_unknown_block() -> Result<()> {
+        let mut session = memory_session();
+        let result = session.replay("does-not-exist").await;
+        assert!(matches!(result, Err(TermindError::BlockNotFound(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+// Text helpers for fitting strings into a fixed number of terminal columns
+// and for turning raw terminal output into plain text
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Shorten `s` to at most `max_cols` display columns, ending with `…` when cut.
+/// Wide characters (CJK, emoji) count as two columns and are never split.
+pub fn truncate_display(s: &str, max_cols: usize) -> String {
+    if s.width() <= max_cols {
+        return s.to_string();
+    }
+    if max_cols == 0 {
+        return String::new();
+    }
+    
+    // Leave one column for the ellipsis
+    let budget = max_cols - 1;
+    let mut width = 0;
+    let mut truncated = String::new();
+    for ch in s.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if width + ch_width > budget {
+            break;
+        }
+        width += ch_width;
+        truncated.push(ch);
+    }
+    truncated.push('…');
+    truncated
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum AnsiState {
+    Ground,
+    Escape,
+    // ESC followed by intermediate bytes, waiting for the final byte
+    EscapeIntermediate,
+    Csi,
+    // OSC, DCS, SOS, PM and APC payloads, ended by BEL or ST
+    String,
+    // ESC inside a string; `\` completes ST
+    StringEscape,
+}
+
+/// Remove escape sequences (CSI, OSC, DCS and other ESC forms) from terminal
+/// output, keeping the visible text and ordinary control characters like `\n`.
+/// Unterminated sequences at the end of the input are dropped.
+pub fn strip_ansi(input: &str) -> String {
+    let mut plain = String::with_capacity(input.len());
+    let mut state = AnsiState::Ground;
+    
+    for ch in input.chars() {
+        state = match state {
+            AnsiState::Ground => match ch {
+                '\x1b' => AnsiState::Escape,
+                '\u{9b}' => AnsiState::Csi,
+                '\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}' => AnsiState::String,
+                _ => {
+                    plain.push(ch);
+                    AnsiState::Ground
+                }
+            },
+            AnsiState::Escape => match ch {
+                '[' => AnsiState::Csi,
+                ']' | 'P' | 'X' | '^' | '_' => AnsiState::String,
+                ' '..='/' => AnsiState::EscapeIntermediate,
+                _ => AnsiState::Ground,
+            },
+            AnsiState::EscapeIntermediate => match ch {
+                ' '..='/' => AnsiState::EscapeIntermediate,
+                _ => AnsiState::Ground,
+            },
+            // Parameters and intermediates run until a final byte in @..~
+            AnsiState::Csi => match ch {
+                '@'..='~' => AnsiState::Ground,
+                _ => AnsiState::Csi,
+            },
+            AnsiState::String => match ch {
+                '\x07' | '\u{9c}' => AnsiState::Ground,
+                '\x1b' => AnsiState::StringEscape,
+                _ => AnsiState::String,
+            },
+            AnsiState::StringEscape => match ch {
+                '\\' => AnsiState::Ground,
+                '\x1b' => AnsiState::StringEscape,
+                _ => AnsiState::String,
+            },
+        };
+    }
+    
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_ascii_truncation() {
+        assert_eq!(truncate_display("cargo build", 20), "cargo build");
+        assert_eq!(truncate_display("cargo build", 11), "cargo build");
+        assert_eq!(truncate_display("cargo build", 6), "cargo…");
+        assert_eq!(truncate_display("cargo", 0), "");
+    }
+    
+    #[test]
+    fn test_wide_character_truncation() {
+        // Each CJK character is two columns wide
+        let truncated = truncate_display("日本語のテキスト", 6);
+        assert_eq!(truncated, "日本…");
+        assert!(truncated.width() <= 6);
+        
+        // A wide character that would straddle the limit is dropped whole
+        let truncated = truncate_display("ab🚀🚀cd", 5);
+        assert_eq!(truncated, "ab🚀…");
+        assert_eq!(truncate_display("ab🚀🚀cd", 4), "ab…");
+    }
+    
+    #[test]
+    fn test_strip_colored_ls_output() {
+        let ls = "\x1b[0m\x1b[01;34mdocs\x1b[0m  \x1b[01;32mbuild.sh\x1b[0m  Cargo.toml\r\n\x1b[38;2;255;100;0msrc\x1b[m\n";
+        assert_eq!(strip_ansi(ls), "docs  build.sh  Cargo.toml\r\nsrc\n");
+    }
+    
+    #[test]
+    fn test_strip_osc_and_escape_forms() {
+        // Title set with BEL, hyperlink with ST, charset designation, keypad mode
+        let input = "\x1b]0;vim\x07\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\\x1b(Bok\x1b=\x1b[?25h!";
+        assert_eq!(strip_ansi(input), "linkok!");
+        
+        // An unterminated sequence at the end is dropped
+        assert_eq!(strip_ansi("done\x1b[31"), "done");
+        assert_eq!(strip_ansi("plain text ünïcode"), "plain text ünïcode");
+    }
+}
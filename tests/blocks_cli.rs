@@ -0,0 +1,78 @@
+// Integration tests for the `termind blocks` subcommand
+
+use std::process::Command;
+
+use termind::blocks::{Block, BlockStore};
+
+#[tokio::test]
+async fn test_blocks_search_json() -> termind::Result<()> {
+    let db_path = std::env::temp_dir().join(format!("termind-cli-{}.db", uuid::Uuid::new_v4()));
+    let store = BlockStore::open(&db_path).await?;
+
+    let block = Block::new("cargo build".to_string(), "/tmp".to_string(), "bash".to_string())
+        .with_output("Compiling termind\n".to_string(), String::new())
+        .with_exit_code(0)
+        .with_duration(1200);
+    store.store(block.clone()).await?;
+    store
+        .store(Block::new("ls".to_string(), "/tmp".to_string(), "bash".to_string()).with_exit_code(0))
+        .await?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_termind"))
+        .args(["blocks", "search", "cargo", "--json", "--db"])
+        .arg(&db_path)
+        .output()?;
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let found: Vec<Block> = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, block.id);
+    assert_eq!(found[0].command, "cargo build");
+
+    // The table view lists the short id and command
+    let output = Command::new(env!("CARGO_BIN_EXE_termind"))
+        .args(["blocks", "--db"])
+        .arg(&db_path)
+        .args(["search", "cargo"])
+        .output()?;
+    let table = String::from_utf8_lossy(&output.stdout);
+    assert!(table.contains(&block.id[..8]));
+    assert!(table.contains("cargo build"));
+    assert!(!table.contains("  ls"));
+
+    let _ = std::fs::remove_file(&db_path);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocks_gc_prunes_and_vacuums() -> termind::Result<()> {
+    let db_path = std::env::temp_dir().join(format!("termind-cli-gc-{}.db", uuid::Uuid::new_v4()));
+    let store = BlockStore::open(&db_path).await?;
+
+    let mut old = Block::new("make".to_string(), "/tmp".to_string(), "bash".to_string())
+        .with_output("y".repeat(64 * 1024), String::new());
+    old.timestamp = chrono::Utc::now() - chrono::Duration::days(10);
+    store.store(old).await?;
+    let fresh = Block::new("ls".to_string(), "/tmp".to_string(), "bash".to_string());
+    store.store(fresh.clone()).await?;
+    drop(store);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_termind"))
+        .args(["blocks", "gc", "--older-than-days", "7", "--json", "--db"])
+        .arg(&db_path)
+        .output()?;
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["removed"], 1);
+    assert_eq!(report["remaining"], 1);
+    assert!(report["size_after"].as_u64() < report["size_before"].as_u64(), "{}", report);
+
+    let store = BlockStore::open(&db_path).await?;
+    assert!(store.get(&fresh.id).await?.is_some());
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", db_path.display(), suffix));
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+// Integration tests for `termind --exec`
+
+use std::process::Command;
+
+use termind::blocks::Block;
+
+fn termind_exec(data_dir: &std::path::Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_termind"));
+    // Keep the recorded blocks out of the real data directory
+    command.env("TERMIND_DATA_DIR", data_dir).env("SHELL", "/bin/sh");
+    command
+}
+
+#[test]
+fn test_exec_prints_output_and_exits_with_status() -> termind::Result<()> {
+    let data_dir = std::env::temp_dir().join(format!("termind-exec-{}", uuid::Uuid::new_v4()));
+
+    let output = termind_exec(&data_dir).args(["--exec", "echo hi"]).output()?;
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, b"hi\n");
+
+    let output = termind_exec(&data_dir).args(["--exec", "echo oops >&2; exit 3"]).output()?;
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(output.stdout, b"");
+    assert_eq!(output.stderr, b"oops\n");
+
+    let output = termind_exec(&data_dir).args(["--exec", "echo hi", "--json"]).output()?;
+    assert!(output.status.success());
+    let block: Block = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(block.command, "echo hi");
+    assert_eq!(block.stdout, "hi\n");
+    assert_eq!(block.exit_code, Some(0));
+
+    let _ = std::fs::remove_dir_all(&data_dir);
+    Ok(())
+}